@@ -0,0 +1,445 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use super::ast::*;
+use super::builtin::Builtin;
+
+//returns the direct children of `node` in the source order
+pub fn children(node: &dyn Node) -> Vec<&dyn Node> {
+    if let Some(n) = node.as_any().downcast_ref::<RootNode>() {
+        return n.statements().iter().map(|e| e.as_node()).collect();
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<BlockExpressionNode>() {
+        return n.statements().iter().map(|e| e.as_node()).collect();
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<LetStatementNode>() {
+        return vec![n.identifier().as_node(), n.expression().as_node()];
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<ReturnStatementNode>() {
+        return n.expression().iter().map(|e| e.as_node()).collect();
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<ExpressionStatementNode>() {
+        return vec![n.expression().as_node()];
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<UnaryExpressionNode>() {
+        return vec![n.expression().as_node()];
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<BinaryExpressionNode>() {
+        return vec![n.left().as_node(), n.right().as_node()];
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<RangeExpressionNode>() {
+        return vec![n.start().as_node(), n.end().as_node()];
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<IndexExpressionNode>() {
+        return vec![n.array().as_node(), n.index().as_node()];
+    }
+
+    //the field name is not an identifier to resolve
+    if let Some(n) = node.as_any().downcast_ref::<FieldExpressionNode>() {
+        return vec![n.record().as_node()];
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<CallExpressionNode>() {
+        let mut v = vec![n.function().as_node()];
+        v.extend(n.arguments().iter().map(|e| e.as_node()));
+        return v;
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<IfExpressionNode>() {
+        let mut v = vec![n.condition().as_node(), n.if_value().as_node()];
+        v.extend(n.else_value().iter().map(|e| e.as_node()));
+        return v;
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<MatchExpressionNode>() {
+        let mut v = vec![n.scrutinee().as_node()];
+        for arm in n.arms() {
+            if let MatchPattern::Value(e) = arm.pattern() {
+                v.push(e.as_node());
+            }
+            v.push(arm.value().as_node());
+        }
+        return v;
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<ArrayLiteralNode>() {
+        return n.elements().iter().map(|e| e.as_node()).collect();
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<HashLiteralNode>() {
+        return n
+            .pairs()
+            .iter()
+            .flat_map(|(k, v)| [k.as_node(), v.as_node()])
+            .collect();
+    }
+
+    if let Some(n) = node.as_any().downcast_ref::<FunctionLiteralNode>() {
+        let mut v: Vec<&dyn Node> = n.parameters().iter().map(|e| e.as_node()).collect();
+        v.push(n.body().as_node());
+        return v;
+    }
+
+    //leaves (literals and identifiers)
+    vec![]
+}
+
+//calls `f` for `node` and all of its descendants in the pre-order
+pub fn walk<'a>(node: &'a dyn Node, f: &mut dyn FnMut(&'a dyn Node)) {
+    f(node);
+    for child in children(node) {
+        walk(child, f);
+    }
+}
+
+/*-------------------------------------*/
+
+//finds the statements following a `return` in the same block (or at the top level)
+//At most one warning is reported per block.
+pub fn check_unreachable_code(root: &RootNode) -> Vec<String> {
+    let mut warnings = vec![];
+    walk(root, &mut |node| {
+        let statements = if let Some(n) = node.as_any().downcast_ref::<RootNode>() {
+            n.statements()
+        } else if let Some(n) = node.as_any().downcast_ref::<BlockExpressionNode>() {
+            n.statements()
+        } else {
+            return;
+        };
+        let position = statements
+            .iter()
+            .position(|e| e.as_any().downcast_ref::<ReturnStatementNode>().is_some());
+        if let Some(i) = position {
+            if i + 1 < statements.len() {
+                warnings.push(format!(
+                    "unreachable code: {} statement(s) after `return`",
+                    statements.len() - i - 1
+                ));
+            }
+        }
+    });
+    warnings
+}
+
+/*-------------------------------------*/
+
+//A warning doesn't have its position yet as the AST nodes don't keep the spans of their tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintWarning {
+    UnusedVariable(String),
+    UnusedParameter(String),
+    ShadowedVariable(String),
+    ShadowedBuiltin(String),
+    UseBeforeDefinition(String),
+    UndefinedIdentifier(String),
+    DeadIfBranch,   //`if (false) { ... }`
+    DeadElseBranch, //`if (true) { ... } else { ... }`
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnusedVariable(s) => write!(f, "unused variable `{}`", s),
+            Self::UnusedParameter(s) => write!(f, "unused parameter `{}`", s),
+            Self::ShadowedVariable(s) => write!(f, "`{}` shadows a variable in an outer scope", s),
+            Self::ShadowedBuiltin(s) => write!(f, "`{}` shadows a built-in identifier", s),
+            Self::UseBeforeDefinition(s) => write!(f, "`{}` is used before its definition", s),
+            Self::UndefinedIdentifier(s) => write!(f, "`{}` is not defined", s),
+            Self::DeadIfBranch => write!(
+                f,
+                "the condition is always `false` so the `if` branch is never evaluated"
+            ),
+            Self::DeadElseBranch => write!(
+                f,
+                "the condition is always `true` so the `else` branch is never evaluated"
+            ),
+        }
+    }
+}
+
+//reports unused variables and parameters, shadowing, uses of undefined identifiers, and dead branches of `if` with a
+// literal condition
+//Names starting with `_` are never reported as unused.
+pub fn lint(root: &RootNode) -> Vec<LintWarning> {
+    lint_with_builtin(root, &Builtin::new())
+}
+
+//same as `lint()` but regards the identifiers in `builtin` (which may have custom ones) as defined
+pub fn lint_with_builtin(root: &RootNode, builtin: &Builtin) -> Vec<LintWarning> {
+    let mut linter = Linter {
+        builtin,
+        scopes: vec![],
+        warnings: vec![],
+    };
+    linter.visit_statements(root.statements(), false);
+    linter.warnings
+}
+
+struct Variable {
+    name: String,
+    is_parameter: bool,
+    is_used: bool,
+}
+
+struct Scope {
+    variables: Vec<Variable>,
+    is_function: bool,
+    hoisted: HashSet<String>, //all the names defined by `let` in the scope, including those defined later
+    used_before_definition: HashSet<String>, //names used (inside a function) before defined by `let`
+}
+
+struct Linter<'a> {
+    builtin: &'a Builtin,
+    scopes: Vec<Scope>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter<'_> {
+    fn push_scope(&mut self, is_function: bool, hoisted: HashSet<String>) {
+        self.scopes.push(Scope {
+            variables: vec![],
+            is_function,
+            hoisted,
+            used_before_definition: HashSet::new(),
+        });
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        for v in scope.variables {
+            if v.is_used || v.name.starts_with('_') {
+                continue;
+            }
+            self.warnings.push(match v.is_parameter {
+                true => LintWarning::UnusedParameter(v.name),
+                false => LintWarning::UnusedVariable(v.name),
+            });
+        }
+    }
+
+    fn define(&mut self, name: &str, is_parameter: bool) {
+        let (current, outer) = self.scopes.split_last_mut().unwrap();
+        if outer
+            .iter()
+            .any(|s| s.variables.iter().any(|v| v.name == name))
+        {
+            self.warnings
+                .push(LintWarning::ShadowedVariable(name.to_string()));
+        } else if self.builtin.lookup_builtin_identifier(name).is_some() {
+            self.warnings
+                .push(LintWarning::ShadowedBuiltin(name.to_string()));
+        }
+        let is_used = current.used_before_definition.contains(name);
+        current.variables.push(Variable {
+            name: name.to_string(),
+            is_parameter,
+            is_used,
+        });
+    }
+
+    fn use_identifier(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(v) = scope.variables.iter_mut().rev().find(|v| v.name == name) {
+                v.is_used = true;
+                return;
+            }
+        }
+        if self.builtin.lookup_builtin_identifier(name).is_some() {
+            return;
+        }
+        //A function body may refer to a variable defined after the function (e.g. mutual recursion)
+        // as it is resolved when the function is called.
+        let mut in_function = false;
+        for scope in self.scopes.iter_mut().rev() {
+            if scope.hoisted.contains(name) {
+                if in_function {
+                    scope.used_before_definition.insert(name.to_string());
+                } else {
+                    self.warnings
+                        .push(LintWarning::UseBeforeDefinition(name.to_string()));
+                }
+                return;
+            }
+            in_function |= scope.is_function;
+        }
+        self.warnings
+            .push(LintWarning::UndefinedIdentifier(name.to_string()));
+    }
+
+    fn visit_statements(&mut self, statements: &[Box<dyn StatementNode>], is_function: bool) {
+        let hoisted = statements
+            .iter()
+            .filter_map(|e| e.as_any().downcast_ref::<LetStatementNode>())
+            .map(|e| e.identifier().get_name().to_string())
+            .collect();
+        self.push_scope(is_function, hoisted);
+        for statement in statements {
+            self.visit(statement.as_node());
+        }
+        self.pop_scope();
+    }
+
+    fn visit(&mut self, node: &dyn Node) {
+        if let Some(n) = node.as_any().downcast_ref::<LetStatementNode>() {
+            self.visit(n.expression().as_node());
+            self.define(n.identifier().get_name(), false);
+            return;
+        }
+
+        if let Some(n) = node.as_any().downcast_ref::<BlockExpressionNode>() {
+            self.visit_statements(n.statements(), false);
+            return;
+        }
+
+        if let Some(n) = node.as_any().downcast_ref::<FunctionLiteralNode>() {
+            self.push_scope(true, HashSet::new());
+            for parameter in n.parameters().iter() {
+                self.define(parameter.get_name(), true);
+            }
+            self.visit_statements(n.body().statements(), false);
+            self.pop_scope();
+            return;
+        }
+
+        if let Some(n) = node.as_any().downcast_ref::<IdentifierNode>() {
+            self.use_identifier(n.get_name());
+            return;
+        }
+
+        //The dead branch is still visited so that the identifiers in it are checked.
+        if let Some(n) = node.as_any().downcast_ref::<IfExpressionNode>() {
+            if let Some(c) = n.condition().as_any().downcast_ref::<BooleanLiteralNode>() {
+                match c.get_value() {
+                    false => self.warnings.push(LintWarning::DeadIfBranch),
+                    true if n.else_value().is_some() => {
+                        self.warnings.push(LintWarning::DeadElseBranch)
+                    }
+                    true => (),
+                }
+            }
+        }
+
+        for child in children(node) {
+            self.visit(child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::lexer::Lexer;
+    use super::super::parser::Parser;
+    use super::*;
+
+    fn parse(s: &str) -> RootNode {
+        Parser::new(Lexer::new(s).tokenize().unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_children() {
+        let root = parse("let f = fn(x) { if (x) { [1, -2] } else { g(x[0]) } };");
+        let mut count = 0;
+        walk(&root, &mut |_| count += 1);
+        //root, let, f, fn, x, {}, stmt, if, x, {}, stmt, [], 1, -, 2, {}, stmt, call, g, [], x, 0
+        assert_eq!(22, count);
+    }
+
+    #[test]
+    fn test_check_unreachable_code() {
+        let root = parse("let f = fn() { return 1; }; f()");
+        assert!(check_unreachable_code(&root).is_empty());
+
+        let root = parse("let f = fn() { let a = 1; return a; a + 1; 2 }; f()");
+        assert_eq!(
+            vec!["unreachable code: 2 statement(s) after `return`"],
+            check_unreachable_code(&root)
+        );
+
+        //nested blocks and the top level
+        let root = parse("fn() { if (true) { return; 1 } return 2; 3 }; return; 4");
+        assert_eq!(3, check_unreachable_code(&root).len());
+
+        //a `return` nested in a block doesn't make the following statements unreachable
+        let root = parse("fn() { if (true) { return 1; } 2 }");
+        assert!(check_unreachable_code(&root).is_empty());
+    }
+
+    #[test]
+    fn test_lint() {
+        use LintWarning::*;
+
+        let root = parse(
+            r#"
+            let add = fn(a, b) { a + b };
+            let is_even = fn(n) { if (n == 0) { true } else { is_odd(n - 1) } };
+            let is_odd = fn(n) { if (n == 0) { false } else { is_even(n - 1) } };
+            print(add(1, 2) + len([is_odd(3)]));
+        "#,
+        );
+        assert!(lint(&root).is_empty());
+
+        let root = parse("let a = 1; let _b = 2; let f = fn(x, y, _z) { x }; f(a)");
+        assert_eq!(vec![UnusedParameter("y".to_string())], lint(&root));
+
+        let root = parse("let a = 1; let b = 2; a");
+        assert_eq!(vec![UnusedVariable("b".to_string())], lint(&root));
+
+        let root = parse("let a = 1; let f = fn(a) { a }; if (true) { let a = 2; f(a) }; a");
+        assert_eq!(
+            vec![
+                ShadowedVariable("a".to_string()),
+                ShadowedVariable("a".to_string())
+            ],
+            lint(&root)
+        );
+
+        let root = parse("let a = b; let b = 1; print(a + b + c)");
+        assert_eq!(
+            vec![
+                UseBeforeDefinition("b".to_string()),
+                UndefinedIdentifier("c".to_string())
+            ],
+            lint(&root)
+        );
+
+        let root = parse("let f = fn(len) { let max = len; max }; { let pi = 3; f(pi) }");
+        assert_eq!(
+            vec![
+                ShadowedBuiltin("len".to_string()),
+                ShadowedBuiltin("max".to_string()),
+                ShadowedBuiltin("pi".to_string())
+            ],
+            lint(&root)
+        );
+
+        let root =
+            parse("if (false) { 1 } else { 2 }; if (true) { 1 } else { x }; if (true) { 1 }");
+        assert_eq!(
+            vec![
+                DeadIfBranch,
+                DeadElseBranch,
+                UndefinedIdentifier("x".to_string())
+            ],
+            lint(&root)
+        );
+        assert_eq!(
+            "the condition is always `false` so the `if` branch is never evaluated",
+            DeadIfBranch.to_string()
+        );
+
+        //only a literal condition is regarded as constant
+        let root = parse("let a = true; if (a) { 1 } else { 2 }; if (!false) { 1 } else { 2 }");
+        assert!(lint(&root).is_empty());
+    }
+}
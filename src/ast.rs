@@ -1,5 +1,6 @@
 use std::any::Any;
 use std::fmt::Debug;
+use std::mem;
 use std::rc::Rc;
 
 use super::token::Token;
@@ -8,6 +9,7 @@ use super::token::Token;
 
 pub trait Node: Base + Debug {
     fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
 pub trait StatementNode: Node {}
@@ -18,11 +20,15 @@ pub trait ExpressionNode: Node {}
 //ref: |https://stackoverflow.com/questions/28632968/why-doesnt-rust-support-trait-object-upcasting|
 pub trait Base {
     fn as_node(&self) -> &dyn Node;
+    fn as_node_mut(&mut self) -> &mut dyn Node;
 }
 impl<T: Node> Base for T {
     fn as_node(&self) -> &dyn Node {
         self
     }
+    fn as_node_mut(&mut self) -> &mut dyn Node {
+        self
+    }
 }
 
 macro_rules! impl_node {
@@ -31,6 +37,9 @@ macro_rules! impl_node {
             fn as_any(&self) -> &dyn Any {
                 self
             }
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
         }
     };
 }
@@ -169,6 +178,41 @@ impl BinaryExpressionNode {
 
 /*-------------------------------------*/
 
+#[derive(Debug)]
+pub struct RangeExpressionNode {
+    start: Box<dyn ExpressionNode>,
+    end: Box<dyn ExpressionNode>,
+    inclusive: bool, //`..=`
+}
+
+impl_node!(RangeExpressionNode);
+impl_expression_node!(RangeExpressionNode);
+
+impl RangeExpressionNode {
+    pub fn new(
+        start: Box<dyn ExpressionNode>,
+        end: Box<dyn ExpressionNode>,
+        inclusive: bool,
+    ) -> Self {
+        RangeExpressionNode {
+            start,
+            end,
+            inclusive,
+        }
+    }
+    pub fn start(&self) -> &dyn ExpressionNode {
+        self.start.as_ref()
+    }
+    pub fn end(&self) -> &dyn ExpressionNode {
+        self.end.as_ref()
+    }
+    pub fn inclusive(&self) -> bool {
+        self.inclusive
+    }
+}
+
+/*-------------------------------------*/
+
 #[derive(Debug)]
 pub struct IndexExpressionNode {
     array: Box<dyn ExpressionNode>,
@@ -192,6 +236,30 @@ impl IndexExpressionNode {
 
 /*-------------------------------------*/
 
+//`<record>.<field>`
+#[derive(Debug)]
+pub struct FieldExpressionNode {
+    record: Box<dyn ExpressionNode>,
+    field: IdentifierNode,
+}
+
+impl_node!(FieldExpressionNode);
+impl_expression_node!(FieldExpressionNode);
+
+impl FieldExpressionNode {
+    pub fn new(record: Box<dyn ExpressionNode>, field: IdentifierNode) -> Self {
+        FieldExpressionNode { record, field }
+    }
+    pub fn record(&self) -> &dyn ExpressionNode {
+        self.record.as_ref()
+    }
+    pub fn field(&self) -> &IdentifierNode {
+        &self.field
+    }
+}
+
+/*-------------------------------------*/
+
 #[derive(Debug)]
 pub struct CallExpressionNode {
     function: Box<dyn ExpressionNode>,
@@ -249,6 +317,64 @@ impl IfExpressionNode {
     pub fn else_value(&self) -> &Option<BlockExpressionNode> {
         &self.else_value
     }
+    //moves out the block evaluated when the condition is `condition` (an empty block for the missing `else`)
+    pub fn take_branch(&mut self, condition: bool) -> BlockExpressionNode {
+        if condition {
+            mem::replace(&mut self.if_value, BlockExpressionNode::new(vec![]))
+        } else {
+            self.else_value
+                .take()
+                .unwrap_or_else(|| BlockExpressionNode::new(vec![]))
+        }
+    }
+}
+
+/*-------------------------------------*/
+
+#[derive(Debug)]
+pub enum MatchPattern {
+    Wildcard,                       //`_`
+    Type(String),                   //type name such as `int` or `string`
+    Value(Box<dyn ExpressionNode>), //compared with `==`
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pattern: MatchPattern,
+    value: Box<dyn ExpressionNode>,
+}
+
+impl MatchArm {
+    pub fn new(pattern: MatchPattern, value: Box<dyn ExpressionNode>) -> Self {
+        MatchArm { pattern, value }
+    }
+    pub fn pattern(&self) -> &MatchPattern {
+        &self.pattern
+    }
+    pub fn value(&self) -> &dyn ExpressionNode {
+        self.value.as_ref()
+    }
+}
+
+#[derive(Debug)]
+pub struct MatchExpressionNode {
+    scrutinee: Box<dyn ExpressionNode>,
+    arms: Vec<MatchArm>,
+}
+
+impl_node!(MatchExpressionNode);
+impl_expression_node!(MatchExpressionNode);
+
+impl MatchExpressionNode {
+    pub fn new(scrutinee: Box<dyn ExpressionNode>, arms: Vec<MatchArm>) -> Self {
+        MatchExpressionNode { scrutinee, arms }
+    }
+    pub fn scrutinee(&self) -> &dyn ExpressionNode {
+        self.scrutinee.as_ref()
+    }
+    pub fn arms(&self) -> &Vec<MatchArm> {
+        &self.arms
+    }
 }
 
 /*-------------------------------------*/
@@ -275,6 +401,30 @@ impl IntegerLiteralNode {
 
 /*-------------------------------------*/
 
+//integer literal too large for `i64` (only with `bignum` feature)
+#[derive(Debug)]
+pub struct BigIntegerLiteralNode {
+    token: Token,
+}
+
+impl_node!(BigIntegerLiteralNode);
+impl_expression_node!(BigIntegerLiteralNode);
+
+impl BigIntegerLiteralNode {
+    pub fn new(token: Token) -> Self {
+        BigIntegerLiteralNode { token }
+    }
+    //returns the decimal representation
+    pub fn get_value(&self) -> &str {
+        match &self.token {
+            Token::BigInt(s) => s,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/*-------------------------------------*/
+
 #[derive(Debug)]
 pub struct FloatLiteralNode {
     token: Token,
@@ -383,6 +533,28 @@ impl ArrayLiteralNode {
 
 /*-------------------------------------*/
 
+//`(<key>, <value>)`
+pub type HashLiteralPair = (Box<dyn ExpressionNode>, Box<dyn ExpressionNode>);
+
+#[derive(Debug)]
+pub struct HashLiteralNode {
+    pairs: Vec<HashLiteralPair>,
+}
+
+impl_node!(HashLiteralNode);
+impl_expression_node!(HashLiteralNode);
+
+impl HashLiteralNode {
+    pub fn new(pairs: Vec<HashLiteralPair>) -> Self {
+        HashLiteralNode { pairs }
+    }
+    pub fn pairs(&self) -> &Vec<HashLiteralPair> {
+        &self.pairs
+    }
+}
+
+/*-------------------------------------*/
+
 #[derive(Debug)]
 pub struct FunctionLiteralNode {
     parameters: Rc<Vec<IdentifierNode>>,
@@ -409,6 +581,7 @@ impl FunctionLiteralNode {
 #[derive(Debug)]
 pub struct LetStatementNode {
     identifier: IdentifierNode,
+    annotation: Option<String>, //type name checked at runtime (e.g. `int` in `let a: int = 1;`)
     expression: Box<dyn ExpressionNode>,
 }
 
@@ -416,15 +589,23 @@ impl_node!(LetStatementNode);
 impl_statement_node!(LetStatementNode);
 
 impl LetStatementNode {
-    pub fn new(identifier: IdentifierNode, expression: Box<dyn ExpressionNode>) -> Self {
+    pub fn new(
+        identifier: IdentifierNode,
+        annotation: Option<String>,
+        expression: Box<dyn ExpressionNode>,
+    ) -> Self {
         LetStatementNode {
             identifier,
+            annotation,
             expression,
         }
     }
     pub fn identifier(&self) -> &IdentifierNode {
         &self.identifier
     }
+    pub fn annotation(&self) -> Option<&str> {
+        self.annotation.as_deref()
+    }
     pub fn expression(&self) -> &dyn ExpressionNode {
         self.expression.as_ref()
     }
@@ -469,3 +650,158 @@ impl ExpressionStatementNode {
 }
 
 /*-------------------------------------*/
+
+//a comment kept by `Lexer::with_comments()`
+//Only the formatter parses comments; a program evaluated by `Evaluator` never contains this node.
+#[derive(Debug)]
+pub struct CommentNode {
+    text: String,
+    trailing: bool,
+}
+
+impl_node!(CommentNode);
+impl_statement_node!(CommentNode);
+
+impl CommentNode {
+    pub fn new(text: String, trailing: bool) -> Self {
+        CommentNode { text, trailing }
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    //whether the comment follows the previous statement on the same line
+    pub fn trailing(&self) -> bool {
+        self.trailing
+    }
+}
+
+/*-------------------------------------*/
+
+//a child of a node which a pass rewriting the AST (e.g. `optimizer::optimize()`) can modify
+//An expression is given as the box holding it so that it can be replaced as a whole.
+pub enum ChildMut<'a> {
+    Expression(&'a mut Box<dyn ExpressionNode>),
+    Block(&'a mut BlockExpressionNode),
+}
+
+//mutable counterpart of `analysis::children()`
+//The statements of a block are skipped and their expressions are returned instead, and so is the body of a function
+// literal which is already shared (e.g. by a function object).
+pub fn children_mut(node: &mut dyn Node) -> Vec<ChildMut<'_>> {
+    let node = node.as_any_mut();
+
+    if node.is::<RootNode>() {
+        let n = node.downcast_mut::<RootNode>().unwrap();
+        return statement_children_mut(&mut n.statements);
+    }
+
+    if node.is::<BlockExpressionNode>() {
+        let n = node.downcast_mut::<BlockExpressionNode>().unwrap();
+        return statement_children_mut(&mut n.statements);
+    }
+
+    if node.is::<UnaryExpressionNode>() {
+        let n = node.downcast_mut::<UnaryExpressionNode>().unwrap();
+        return vec![ChildMut::Expression(&mut n.expression)];
+    }
+
+    if node.is::<BinaryExpressionNode>() {
+        let n = node.downcast_mut::<BinaryExpressionNode>().unwrap();
+        return vec![
+            ChildMut::Expression(&mut n.left),
+            ChildMut::Expression(&mut n.right),
+        ];
+    }
+
+    if node.is::<RangeExpressionNode>() {
+        let n = node.downcast_mut::<RangeExpressionNode>().unwrap();
+        return vec![
+            ChildMut::Expression(&mut n.start),
+            ChildMut::Expression(&mut n.end),
+        ];
+    }
+
+    if node.is::<IndexExpressionNode>() {
+        let n = node.downcast_mut::<IndexExpressionNode>().unwrap();
+        return vec![
+            ChildMut::Expression(&mut n.array),
+            ChildMut::Expression(&mut n.index),
+        ];
+    }
+
+    if node.is::<FieldExpressionNode>() {
+        let n = node.downcast_mut::<FieldExpressionNode>().unwrap();
+        return vec![ChildMut::Expression(&mut n.record)];
+    }
+
+    if node.is::<CallExpressionNode>() {
+        let n = node.downcast_mut::<CallExpressionNode>().unwrap();
+        let mut v = vec![ChildMut::Expression(&mut n.function)];
+        v.extend(n.arguments.iter_mut().map(ChildMut::Expression));
+        return v;
+    }
+
+    if node.is::<IfExpressionNode>() {
+        let n = node.downcast_mut::<IfExpressionNode>().unwrap();
+        let mut v = vec![
+            ChildMut::Expression(&mut n.condition),
+            ChildMut::Block(&mut n.if_value),
+        ];
+        v.extend(n.else_value.as_mut().map(ChildMut::Block));
+        return v;
+    }
+
+    if node.is::<MatchExpressionNode>() {
+        let n = node.downcast_mut::<MatchExpressionNode>().unwrap();
+        let mut v = vec![ChildMut::Expression(&mut n.scrutinee)];
+        for arm in &mut n.arms {
+            if let MatchPattern::Value(e) = &mut arm.pattern {
+                v.push(ChildMut::Expression(e));
+            }
+            v.push(ChildMut::Expression(&mut arm.value));
+        }
+        return v;
+    }
+
+    if node.is::<ArrayLiteralNode>() {
+        let n = node.downcast_mut::<ArrayLiteralNode>().unwrap();
+        return n.elements.iter_mut().map(ChildMut::Expression).collect();
+    }
+
+    if node.is::<HashLiteralNode>() {
+        let n = node.downcast_mut::<HashLiteralNode>().unwrap();
+        return n
+            .pairs
+            .iter_mut()
+            .flat_map(|(k, v)| [ChildMut::Expression(k), ChildMut::Expression(v)])
+            .collect();
+    }
+
+    if node.is::<FunctionLiteralNode>() {
+        let n = node.downcast_mut::<FunctionLiteralNode>().unwrap();
+        return Rc::get_mut(&mut n.body)
+            .map(ChildMut::Block)
+            .into_iter()
+            .collect();
+    }
+
+    vec![]
+}
+
+fn statement_children_mut(statements: &mut [Box<dyn StatementNode>]) -> Vec<ChildMut<'_>> {
+    let mut v = vec![];
+    for statement in statements {
+        let statement = statement.as_any_mut();
+        if statement.is::<LetStatementNode>() {
+            let n = statement.downcast_mut::<LetStatementNode>().unwrap();
+            v.push(ChildMut::Expression(&mut n.expression));
+        } else if statement.is::<ReturnStatementNode>() {
+            let n = statement.downcast_mut::<ReturnStatementNode>().unwrap();
+            v.extend(n.expression.as_mut().map(ChildMut::Expression));
+        } else if statement.is::<ExpressionStatementNode>() {
+            let n = statement.downcast_mut::<ExpressionStatementNode>().unwrap();
+            v.push(ChildMut::Expression(&mut n.expression));
+        }
+    }
+    v
+}
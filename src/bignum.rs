@@ -0,0 +1,344 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+
+//arbitrary-precision signed integer
+//
+//The magnitude is stored in base 2^32 in little-endian order.
+//A value is always normalized: the magnitude has no trailing zero limb, and zero is non-negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigNum {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigNum {
+    pub fn zero() -> Self {
+        Self {
+            negative: false,
+            magnitude: vec![],
+        }
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        Self {
+            negative,
+            magnitude,
+        }
+    }
+
+    //parses a decimal literal optionally prefixed with `-`
+    pub fn parse(s: &str) -> Option<Self> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut magnitude = vec![];
+        for c in digits.chars() {
+            mag_mul_small_add(&mut magnitude, 10, c.to_digit(10).unwrap());
+        }
+        Some(Self::from_parts(negative, magnitude))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    //number of the bits of the magnitude (`0` for zero)
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            None => 0,
+            Some(d) => (self.magnitude.len() as u64 - 1) * 32 + (32 - d.leading_zeros()) as u64,
+        }
+    }
+
+    //returns `None` if the value doesn't fit in `i64`
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let m = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &d| (acc << 32) | d as u64);
+        if self.negative {
+            if m <= i64::MAX as u64 + 1 {
+                Some((m as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i64::try_from(m).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let m = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &d| acc * 4294967296.0 + d as f64);
+        if self.negative {
+            -m
+        } else {
+            m
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::from_parts(!self.negative, self.magnitude.clone())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return Self::from_parts(self.negative, mag_add(&self.magnitude, &other.magnitude));
+        }
+        match mag_cmp(&self.magnitude, &other.magnitude) {
+            Ordering::Less => {
+                Self::from_parts(other.negative, mag_sub(&other.magnitude, &self.magnitude))
+            }
+            _ => Self::from_parts(self.negative, mag_sub(&self.magnitude, &other.magnitude)),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::from_parts(
+            self.negative != other.negative,
+            mag_mul(&self.magnitude, &other.magnitude),
+        )
+    }
+
+    //truncated division as `i64::div()` and `i64::rem()`
+    //returns `None` if `other` is zero
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (q, r) = mag_div_rem(&self.magnitude, &other.magnitude);
+        Some((
+            Self::from_parts(self.negative != other.negative, q),
+            Self::from_parts(self.negative, r),
+        ))
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut ret = Self::from(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                ret = ret.mul(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base);
+            }
+        }
+        ret
+    }
+}
+
+impl From<i64> for BigNum {
+    fn from(i: i64) -> Self {
+        let m = i.unsigned_abs();
+        Self::from_parts(i < 0, vec![m as u32, (m >> 32) as u32])
+    }
+}
+
+impl PartialOrd for BigNum {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigNum {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => mag_cmp(&self.magnitude, &other.magnitude),
+            (true, true) => mag_cmp(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl Display for BigNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        //converts to base 10^9 by repeated division
+        let mut m = self.magnitude.clone();
+        let mut chunks = vec![];
+        while !m.is_empty() {
+            chunks.push(mag_div_rem_small(&mut m, 1_000_000_000));
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.last().unwrap())?;
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/*-------------------------------------*/
+//operations on magnitudes
+
+fn mag_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn mag_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut ret = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let s = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        ret.push(s as u32);
+        carry = s >> 32;
+    }
+    ret.push(carry as u32);
+    ret
+}
+
+//assumes `a >= b`
+fn mag_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut ret = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &d) in a.iter().enumerate() {
+        let mut s = d as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if s < 0 {
+            s += 1 << 32;
+            borrow = 1;
+        }
+        ret.push(s as u32);
+    }
+    ret
+}
+
+fn mag_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut ret = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let s = ret[i + j] as u64 + x as u64 * y as u64 + carry;
+            ret[i + j] = s as u32;
+            carry = s >> 32;
+        }
+        ret[i + b.len()] = carry as u32;
+    }
+    ret
+}
+
+//`m = m * k + c`
+fn mag_mul_small_add(m: &mut Vec<u32>, k: u32, c: u32) {
+    let mut carry = c as u64;
+    for d in m.iter_mut() {
+        let s = *d as u64 * k as u64 + carry;
+        *d = s as u32;
+        carry = s >> 32;
+    }
+    if carry > 0 {
+        m.push(carry as u32);
+    }
+}
+
+//divides `m` by `k` in place (keeping `m` normalized) and returns the remainder
+fn mag_div_rem_small(m: &mut Vec<u32>, k: u32) -> u32 {
+    let mut rem = 0u64;
+    for d in m.iter_mut().rev() {
+        let cur = (rem << 32) | *d as u64;
+        *d = (cur / k as u64) as u32;
+        rem = cur % k as u64;
+    }
+    while m.last() == Some(&0) {
+        m.pop();
+    }
+    rem as u32
+}
+
+//bitwise long division
+//This is O(n^2) per bit but is fast enough for the sizes the interpreter deals with.
+fn mag_div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut q = vec![0u32; a.len()];
+    let mut r: Vec<u32> = vec![];
+    for i in (0..a.len() * 32).rev() {
+        //r = (r << 1) | bit i of a
+        mag_mul_small_add(&mut r, 2, (a[i / 32] >> (i % 32)) & 1);
+        if mag_cmp(&r, b) != Ordering::Less {
+            r = mag_sub(&r, b);
+            while r.last() == Some(&0) {
+                r.pop();
+            }
+            q[i / 32] |= 1 << (i % 32);
+        }
+    }
+    (q, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn n(s: &str) -> BigNum {
+        BigNum::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_bignum() {
+        assert_eq!("0", BigNum::zero().to_string());
+        assert_eq!("0", n("-0").to_string());
+        assert_eq!("-1", BigNum::from(-1).to_string());
+        assert_eq!(i64::MIN.to_string(), BigNum::from(i64::MIN).to_string());
+        assert_eq!(Some(i64::MIN), BigNum::from(i64::MIN).to_i64());
+        assert_eq!(Some(i64::MAX), BigNum::from(i64::MAX).to_i64());
+        assert_eq!(None, n("9223372036854775808").to_i64());
+        assert_eq!(None, n("-9223372036854775809").to_i64());
+        assert_eq!(None, BigNum::parse("1a"));
+        assert_eq!(None, BigNum::parse(""));
+
+        let a = n("123456789012345678901234567890");
+        let b = n("-987654321098765432109876543210");
+        assert_eq!("-864197532086419753208641975320", a.add(&b).to_string());
+        assert_eq!("1111111110111111111011111111100", a.sub(&b).to_string());
+        assert_eq!(
+            "-121932631137021795226185032733622923332237463801111263526900",
+            a.mul(&b).to_string()
+        );
+        let (q, r) = b.div_rem(&a).unwrap();
+        assert_eq!(
+            ("-8".to_string(), "-9000000000900000000090".to_string()),
+            (q.to_string(), r.to_string())
+        );
+        assert_eq!(None, a.div_rem(&BigNum::zero()));
+        assert_eq!(
+            "1267650600228229401496703205376",
+            BigNum::from(2).pow(100).to_string()
+        );
+        assert_eq!(0, BigNum::zero().bits());
+        assert_eq!(1, BigNum::from(-1).bits());
+        assert_eq!(101, BigNum::from(2).pow(100).bits());
+        assert!(b < a);
+        assert!(n("-2") < n("-1"));
+        assert_eq!(1e30, n("1000000000000000000000000000000").to_f64());
+    }
+}
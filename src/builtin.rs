@@ -1,91 +1,1605 @@
-use std::collections::HashMap;
-use std::process;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::num::IntErrorKind;
 use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use super::ast::IdentifierNode;
+use super::bignum::BigNum;
 use super::environment::Environment;
-use super::evaluator::EvalResult;
+use super::evaluator::{EvalResult, Evaluator};
 use super::object::*;
+use super::operator;
 use super::token::Token;
 
+//destination of `print()` and `eprint()`
+pub type OutputSink = Rc<RefCell<dyn Write>>;
+
+//built-in functions which access the host (the file system, the command line and the environment variables)
+pub const IO_BUILTINS: &[&str] = &["read_file", "write_file", "argv", "args", "env"];
+
+//built-in functions which read the clock of the host and thus make the result nondeterministic
+pub const TIME_BUILTINS: &[&str] = &["time", "clock"];
+
+//built-in identifiers which cannot be shadowed by `let` at the top level
+//The other standard ones (e.g. `sum()` and `join()`) were added later, so they can be shadowed anywhere not to break
+// the programs which already use the names as variables.
+const RESERVED_BUILTINS: &[&str] = &[
+    "print", "eprint", "exit", "len", "append", "bool", "str", "int", "float", "pi",
+];
+
 pub struct Builtin {
     m: HashMap<String, Rc<dyn Object>>,
+    reserved: HashSet<String>, //`RESERVED_BUILTINS` and the custom ones
 }
 
 impl Builtin {
+    //`print()` and `eprint()` write to stdout and stderr respectively
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        initialize_builtin()
+        Self::with_output(
+            Rc::new(RefCell::new(io::stdout())),
+            Rc::new(RefCell::new(io::stderr())),
+        )
+    }
+
+    //`print()` writes to `out` and `eprint()` writes to `err`
+    //This is useful for an environment without a terminal (e.g. WebAssembly) or for capturing the output.
+    pub fn with_output(out: OutputSink, err: OutputSink) -> Self {
+        initialize_builtin(out, err)
     }
     pub fn lookup_builtin_identifier(&self, s: &str) -> Option<Rc<dyn Object>> {
-        self.m.get(s).cloned()
+        self.get(s).cloned()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rc<dyn Object>> {
+        self.m.get(name)
+    }
+
+    //whether `name` cannot be shadowed by `let` at the top level (see `RESERVED_BUILTINS`)
+    pub fn is_reserved(&self, name: &str) -> bool {
+        self.reserved.contains(name)
+    }
+
+    //iterates over the built-in identifiers (including the custom ones) in an arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Rc<dyn Object>)> {
+        self.m.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    //names of the built-in identifiers (including the custom ones) in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        let mut v: Vec<&str> = self.m.keys().map(|k| k.as_str()).collect();
+        v.sort_unstable();
+        v
+    }
+
+    //registers a custom built-in identifier (overwriting the existing one if any)
+    //A custom one is reserved (see `is_reserved()`) as the host relies on it.
+    pub fn register(&mut self, name: &str, o: Rc<dyn Object>) {
+        self.m.insert(name.to_string(), o);
+        self.reserved.insert(name.to_string());
+    }
+
+    //same as `register()` but for a function built by `BuiltinFunction::new()` and alike, which has direct access to
+    // the arguments (see `register_fn()` for a plain Rust closure)
+    pub fn register_function(&mut self, name: &str, f: BuiltinFunction) {
+        self.register(name, Rc::new(f));
+    }
+
+    //replaces the built-in function `name` with the one which always fails
+    //This is used to deny untrusted code a capability (e.g. file I/O) while keeping the name reserved, so that the
+    // error tells why it is unavailable.
+    pub fn disable(&mut self, name: &str) {
+        let (parameters, variadic) = match self.m.get(name) {
+            None => return,
+            Some(o) => match o.as_any().downcast_ref::<BuiltinFunction>() {
+                None => return,
+                Some(f) => (copy_parameters(f.parameters()), f.is_variadic()),
+            },
+        };
+        let message = format!("`{}` is disabled in this sandbox", name);
+        let f: Rc<BuiltinFunctionBody> =
+            Rc::new(move |_: &Evaluator, _: &Environment| -> EvalResult { Err(message.clone()) });
+        let f = if variadic {
+            BuiltinFunction::variadic(parameters, f)
+        } else {
+            BuiltinFunction::with_evaluator(parameters, f)
+        };
+        self.m.insert(name.to_string(), Rc::new(f));
+    }
+
+    //sets the return value of `argv()` and `args()` (empty by default), i.e. the command-line arguments given to the
+    // script (not including the script itself)
+    //Call this before `disable_io()`, which would be undone otherwise.
+    pub fn set_argv(&mut self, args: Vec<String>) {
+        let argv: Rc<dyn Object> = Rc::new((move || args.clone()).into_builtin_function("argv"));
+        self.m.insert("argv".to_string(), argv.clone());
+        self.m.insert("args".to_string(), argv);
+    }
+
+    //disables the built-in functions which access the host (see `IO_BUILTINS`)
+    pub fn disable_io(&mut self) {
+        for name in IO_BUILTINS {
+            self.disable(name);
+        }
+    }
+
+    //registers a Rust closure as a built-in function
+    //Arguments are converted via `TryFrom<&dyn Object>` and the return value via `IntoObject`.
+    //
+    //Example:
+    // builtin.register_fn("add", |a: i64, b: i64| a + b);
+    pub fn register_fn<Args, F: IntoBuiltinFunction<Args>>(&mut self, name: &str, f: F) {
+        self.register(name, Rc::new(f.into_builtin_function(name)));
+    }
+}
+
+/*-------------------------------------*/
+
+//describes the built-in identifier `name` for `help()`
+//A function is shown with its parameters (e.g. `len(s)` and `print(args...)`), and a constant with its type (e.g.
+// `pi: float`).
+fn signature(name: &str, o: &dyn Object) -> String {
+    match o.as_any().downcast_ref::<BuiltinFunction>() {
+        None => format!("{}: {}", name, o.type_name()),
+        Some(f) => {
+            let mut parameters: Vec<String> = f
+                .parameters()
+                .iter()
+                .map(|p| p.get_name().to_string())
+                .collect();
+            if f.is_variadic() {
+                parameters.last_mut().unwrap().push_str("...");
+            }
+            format!("{}({})", name, parameters.join(", "))
+        }
+    }
+}
+
+/*-------------------------------------*/
+
+//`Args` is the tuple of the argument types, which is needed to implement this trait for closures with different arities.
+pub trait IntoBuiltinFunction<Args> {
+    fn into_builtin_function(self, name: &str) -> BuiltinFunction;
+}
+
+impl<F, R> IntoBuiltinFunction<()> for F
+where
+    F: Fn() -> R + 'static,
+    R: IntoObject,
+{
+    fn into_builtin_function(self, _name: &str) -> BuiltinFunction {
+        BuiltinFunction::new(
+            Rc::new(vec![]),
+            Rc::new(move |_env: &Environment| -> EvalResult { Ok(self().into_object()) }),
+        )
+    }
+}
+
+//The parameters are named `arg1`, `arg2`, ... and a conversion failure is reported as
+// "argument 2 of `add`: expected int, got string".
+macro_rules! impl_into_builtin_function {
+    ($($arg:ident $i:literal),+) => {
+        impl<F, R, $($arg),+> IntoBuiltinFunction<($($arg,)+)> for F
+        where
+            F: Fn($($arg),+) -> R + 'static,
+            R: IntoObject,
+            $($arg: for<'a> TryFrom<&'a dyn Object, Error = ConversionError>,)+
+        {
+            fn into_builtin_function(self, name: &str) -> BuiltinFunction {
+                let name = name.to_string();
+                BuiltinFunction::new(
+                    Rc::new(vec![$(IdentifierNode::new(Token::Ident(format!("arg{}", $i)))),+]),
+                    Rc::new(move |env: &Environment| -> EvalResult {
+                        Ok(self($(
+                            match $arg::try_from(env.get(&format!("arg{}", $i)).unwrap().as_ref()) {
+                                Ok(v) => v,
+                                Err(e) => {
+                                    return Err(format!("argument {} of `{}`: {}", $i, name, e))
+                                }
+                            }
+                        ),+)
+                        .into_object())
+                    }),
+                )
+            }
+        }
+    };
+}
+
+impl_into_builtin_function!(A1 1);
+impl_into_builtin_function!(A1 1, A2 2);
+impl_into_builtin_function!(A1 1, A2 2, A3 3);
+impl_into_builtin_function!(A1 1, A2 2, A3 3, A4 4);
+
+//copies `parameters` as `IdentifierNode` is not `Clone`
+fn copy_parameters(parameters: &[IdentifierNode]) -> Rc<Vec<IdentifierNode>> {
+    Rc::new(
+        parameters
+            .iter()
+            .map(|e| IdentifierNode::new(Token::Ident(e.get_name().to_string())))
+            .collect(),
+    )
+}
+
+//creates a built-in function which takes `parameters` and passes the arguments to `f` in the order of the parameters
+//If `variadic` is `true`, the rest of the arguments received by the last parameter are flattened.
+//This is used by higher-order built-in functions which return a function (e.g. `partial()`).
+fn forwarding_function<F>(
+    parameters: Rc<Vec<IdentifierNode>>,
+    variadic: bool,
+    f: F,
+) -> BuiltinFunction
+where
+    F: Fn(&Evaluator, &Environment, Vec<Rc<dyn Object>>) -> EvalResult + 'static,
+{
+    let names: Vec<String> = parameters
+        .iter()
+        .map(|p| p.get_name().to_string())
+        .collect();
+    let body = Rc::new(
+        move |evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let mut arguments = Vec::with_capacity(names.len());
+            for (i, name) in names.iter().enumerate() {
+                let argument = env.get(name).unwrap();
+                if variadic && (i + 1 == names.len()) {
+                    let rest = argument.as_any().downcast_ref::<Array>().unwrap();
+                    arguments.extend(rest.elements().iter().cloned());
+                } else {
+                    arguments.push(argument.clone());
+                }
+            }
+            f(evaluator, env, arguments)
+        },
+    );
+    if variadic {
+        BuiltinFunction::variadic(parameters, body)
+    } else {
+        BuiltinFunction::with_evaluator(parameters, body)
+    }
+}
+
+//stable merge sort which stops at the first error of `less`
+//Unlike `slice::sort_by()`, this never panics even if `less` is inconsistent (e.g. a user-defined comparator).
+fn merge_sort<F>(v: &[Rc<dyn Object>], less: &mut F) -> Result<Vec<Rc<dyn Object>>, String>
+where
+    F: FnMut(&Rc<dyn Object>, &Rc<dyn Object>) -> Result<bool, String>,
+{
+    if v.len() <= 1 {
+        return Ok(v.to_vec());
+    }
+    let (left, right) = v.split_at(v.len() / 2);
+    let left = merge_sort(left, less)?;
+    let right = merge_sort(right, less)?;
+    let mut ret = Vec::with_capacity(v.len());
+    let (mut i, mut j) = (0, 0);
+    while (i < left.len()) && (j < right.len()) {
+        //takes the left one unless the right one is strictly less, which makes the sort stable
+        if less(&right[j], &left[i])? {
+            ret.push(right[j].clone());
+            j += 1;
+        } else {
+            ret.push(left[i].clone());
+            i += 1;
+        }
+    }
+    ret.extend_from_slice(&left[i..]);
+    ret.extend_from_slice(&right[j..]);
+    Ok(ret)
+}
+
+//returns the argument `h` of the built-in function `name`, which must be a hash
+fn hash_argument<'a>(name: &str, env: &'a Environment) -> Result<&'a Hash, String> {
+    let h = env.get("h").unwrap();
+    h.as_any().downcast_ref::<Hash>().ok_or_else(|| {
+        format!(
+            "argument 1 of `{}`: expected hash, got {}",
+            name,
+            h.type_name()
+        )
+    })
+}
+
+//implements `min()` and `max()`
+//`args` is the array of the arguments, and a single array argument is regarded as the list of the values.
+//`better(x, y)` returns `true` if `x` should replace `y` (so the first one wins a tie).
+fn extremum<F>(name: &str, args: &dyn Object, better: F) -> EvalResult
+where
+    F: Fn(&dyn Object, &dyn Object) -> EvalResult,
+{
+    let mut values = args.as_any().downcast_ref::<Array>().unwrap().elements();
+    if let [a] = values.as_slice() {
+        if let Some(a) = a.as_any().downcast_ref::<Array>() {
+            values = a.elements();
+        }
+    }
+    let mut ret = match values.first() {
+        None => return Err(format!("`{}` requires at least one value", name)),
+        Some(v) => v,
+    };
+    for v in &values[1..] {
+        let b = better(v.as_ref(), ret.as_ref())
+            .map_err(|_| format!("cannot compare {} with {}", v.type_name(), ret.type_name()))?;
+        if b.as_any().downcast_ref::<Bool>().unwrap().value() {
+            ret = v;
+        }
+    }
+    Ok(ret.clone())
+}
+
+//implements `sum()` and `product()`
+//The result is a float if any of the elements is a float, and an integer otherwise. An integer overflow is an error
+// (unless `bignum` feature is enabled) as `op` is the corresponding operator.
+fn fold_numbers<F>(name: &str, l: &dyn Object, initial: i64, op: F) -> EvalResult
+where
+    F: Fn(&dyn Object, &dyn Object) -> EvalResult,
+{
+    let a = match l.as_any().downcast_ref::<Array>() {
+        None => return Err("argument type mismatch".to_string()),
+        Some(a) => a,
+    };
+    let to_f64 = |o: &dyn Object| -> Option<f64> {
+        if let Some(o) = o.as_any().downcast_ref::<Int>() {
+            return Some(o.value() as f64);
+        }
+        if let Some(o) = o.as_any().downcast_ref::<BigInt>() {
+            return Some(o.value().to_f64());
+        }
+        o.as_any().downcast_ref::<Float>().map(|o| o.value())
+    };
+    let mut ret: Rc<dyn Object> = Rc::new(Int::new(initial));
+    for (i, e) in a.elements().iter().enumerate() {
+        if to_f64(e.as_ref()).is_none() {
+            let article = match e.type_name().starts_with(['a', 'e', 'i', 'o', 'u']) {
+                true => "an",
+                false => "a",
+            };
+            return Err(format!(
+                "element {} of `{}` argument is {} {}",
+                i,
+                name,
+                article,
+                e.type_name()
+            ));
+        }
+        let is_float = (ret.as_any().downcast_ref::<Float>().is_some())
+            || (e.as_any().downcast_ref::<Float>().is_some());
+        ret = if is_float {
+            let l = Float::new(to_f64(ret.as_ref()).unwrap());
+            let r = Float::new(to_f64(e.as_ref()).unwrap());
+            op(&l, &r)?
+        } else {
+            op(ret.as_ref(), e.as_ref())?
+        };
+    }
+    Ok(ret)
+}
+
+//implements `floor()`, `ceil()` and `round()` with one argument
+fn round_to_integer<F: Fn(f64) -> f64>(name: &str, x: &Rc<dyn Object>, f: F) -> EvalResult {
+    if x.as_any().downcast_ref::<Int>().is_some() || x.as_any().downcast_ref::<BigInt>().is_some() {
+        return Ok(x.clone());
+    }
+    if let Some(x) = x.as_any().downcast_ref::<Float>() {
+        let y = f(x.value());
+        //`as` would saturate (and convert NaN to `0`) silently
+        //`-2^63` is exactly representable while `2^63 - 1` is not.
+        if (-9223372036854775808.0..9223372036854775808.0).contains(&y) {
+            return Ok(Rc::new(Int::new(y as i64)));
+        }
+        return Err(format!("result of `{}` is out of the range of int", name));
+    }
+    Err("argument type mismatch".to_string())
+}
+
+//parses a string as an integer in base `radix` (e.g. `int(" -ff ", 16)` is `-255`)
+//Surrounding whitespaces and a sign are allowed while a prefix such as `0x` is not.
+fn parse_integer(s: &str, radix: i64) -> EvalResult {
+    let radix = match u32::try_from(radix) {
+        Ok(r) if (2..=36).contains(&r) => r,
+        _ => return Err(format!("radix `{}` is not between 2 and 36", radix)),
+    };
+    let digits = s.trim();
+    match i64::from_str_radix(digits, radix) {
+        Ok(i) => Ok(Rc::new(Int::new(i))),
+        Err(e)
+            if matches!(
+                e.kind(),
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+            ) =>
+        {
+            //only decimal digits are supported by `BigNum::parse()`
+            if cfg!(feature = "bignum") && (radix == 10) {
+                let digits = digits.strip_prefix('+').unwrap_or(digits);
+                return Ok(new_integer(BigNum::parse(digits).unwrap()));
+            }
+            Err(format!("`{}` is out of the range of int", s))
+        }
+        Err(_) => Err(format!("cannot parse `{}` as int", s)),
+    }
+}
+
+//parses a string as a float (e.g. `float(" -1.5e3 ")` is `-1500.0`)
+//`inf` and `NaN` are rejected as they cannot be written as literals either.
+fn parse_float(s: &str) -> EvalResult {
+    let digits = s.trim();
+    if !digits
+        .chars()
+        .all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+    {
+        return Err(format!("cannot parse `{}` as float", s));
+    }
+    match digits.parse::<f64>() {
+        Ok(f) if f.is_finite() => Ok(Rc::new(Float::new(f))),
+        Ok(_) => Err(format!("`{}` is out of the range of float", s)),
+        Err(_) => Err(format!("cannot parse `{}` as float", s)),
+    }
+}
+
+//creates a built-in function which applies `f` to a float (or an integer converted to a float)
+//`domain(x)` returns whether `f(x)` is defined.
+fn math_function(
+    name: &'static str,
+    domain: fn(f64) -> bool,
+    f: fn(f64) -> f64,
+) -> BuiltinFunction {
+    BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("x".to_string()))]),
+        Rc::new(move |env: &Environment| -> EvalResult {
+            let x = env.get("x").unwrap();
+            let x = if let Some(x) = x.as_any().downcast_ref::<Float>() {
+                x.value()
+            } else if let Some(x) = x.as_any().downcast_ref::<Int>() {
+                x.value() as f64
+            } else if let Some(x) = x.as_any().downcast_ref::<BigInt>() {
+                x.value().to_f64()
+            } else {
+                return Err("argument type mismatch".to_string());
+            };
+            if !domain(x) {
+                return Err(format!("`{}` is not defined for {}", name, x));
+            }
+            let y = f(x);
+            if !y.is_finite() {
+                return Err(format!("result of `{}` is out of the range of float", name));
+            }
+            Ok(Rc::new(Float::new(y)))
+        }),
+    )
+}
+
+//implements `take_while()` and `drop_while()`
+//returns the array `l` and the length of its longest prefix whose elements satisfy `f`
+fn prefix_length<'a>(
+    name: &str,
+    evaluator: &Evaluator,
+    env: &'a Environment,
+) -> Result<(&'a Array, usize), String> {
+    let l = env.get("l").unwrap();
+    let f = env.get("f").unwrap();
+    if as_function(f.as_ref()).is_none() {
+        return Err(format!("argument `f` of `{}` is not a function", name));
+    }
+    let a = match l.as_any().downcast_ref::<Array>() {
+        None => return Err("argument type mismatch".to_string()),
+        Some(a) => a,
+    };
+    for (i, e) in a.elements().iter().enumerate() {
+        let b = evaluator.call_function(f.as_ref(), vec![e.clone()], env)?;
+        match b.as_any().downcast_ref::<Bool>() {
+            None => return Err(format!("predicate of `{}` returned a non-boolean", name)),
+            Some(b) if !b.value() => return Ok((a, i)),
+            Some(_) => (),
+        }
+    }
+    Ok((a, a.elements().len()))
+}
+
+//rounds `x` half away from zero to `digits` decimal places
+//A negative `digits` rounds to a power of ten (e.g. `round_to_digits(1250.0, -2)` is `1300.0`).
+fn round_to_digits(x: f64, digits: i64) -> f64 {
+    let factor = 10f64.powi(digits.clamp(-400, 400) as i32);
+    if factor == 0.0 {
+        return 0.0;
+    }
+    //`x` has no more digits than `digits`
+    if !(x * factor).is_finite() {
+        return x;
     }
+    (x * factor).round() / factor
+}
+
+//joins the arguments received by the rest parameter `args` (e.g. of `print()`), each converted by `Display`
+fn join_arguments(env: &Environment, separator: &str) -> String {
+    let args = env.get("args").unwrap();
+    let args = args.as_any().downcast_ref::<Array>().unwrap().elements();
+    args.iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
 }
 
 //Never embed this function in `Builtin::new()`; it'll increase the indent level by one to decrease readability.
-fn initialize_builtin() -> Builtin {
+fn initialize_builtin(out: OutputSink, err: OutputSink) -> Builtin {
     let mut m = HashMap::new();
 
     /*-------------------------------------*/
 
-    let print = BuiltinFunction::new(
-        Rc::new(vec![IdentifierNode::new(Token::Ident("o".to_string()))]),
+    //`print(a, b, ...)` prints the arguments separated by a space, followed by a newline
+    let print = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("args".to_string()))]),
+        {
+            let out = out.clone();
+            Rc::new(move |_: &Evaluator, env: &Environment| -> EvalResult {
+                writeln!(out.borrow_mut(), "{}", join_arguments(env, " "))
+                    .map_err(|e| e.to_string())?;
+                Ok(Rc::new(Null::new()))
+            })
+        },
+    );
+
+    //same as `print()` but prints neither a separator nor a newline (e.g. for a progress output)
+    //The output is flushed immediately as it is not terminated by a newline.
+    let print_raw = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("args".to_string()))]),
+        {
+            let out = out.clone();
+            Rc::new(move |_: &Evaluator, env: &Environment| -> EvalResult {
+                let mut out = out.borrow_mut();
+                write!(out, "{}", join_arguments(env, ""))
+                    .and_then(|_| out.flush())
+                    .map_err(|e| e.to_string())?;
+                Ok(Rc::new(Null::new()))
+            })
+        },
+    );
+
+    //prints the representation for debugging (see `repr()`)
+    let print_debug = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("o".to_string()))]),
+        Rc::new(move |env: &Environment| -> EvalResult {
+            writeln!(out.borrow_mut(), "{}", repr(env.get("o").unwrap().as_ref()))
+                .map_err(|e| e.to_string())?;
+            Ok(Rc::new(Null::new()))
+        }),
+    );
+
+    //same as `print()` but prints to the error output
+    let eprint = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("args".to_string()))]),
+        Rc::new(move |_: &Evaluator, env: &Environment| -> EvalResult {
+            writeln!(err.borrow_mut(), "{}", join_arguments(env, " "))
+                .map_err(|e| e.to_string())?;
+            Ok(Rc::new(Null::new()))
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    //doesn't terminate the process by itself but stops the evaluation, whose result is `Exit` handled by the host
+    //`exit()` is the same as `exit(0)`.
+    let exit = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("i".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let i = env.get("i").unwrap();
+            match i
+                .as_any()
+                .downcast_ref::<Array>()
+                .unwrap()
+                .elements()
+                .as_slice()
+            {
+                [] => Err(evaluator.exit(0)),
+                [i] => match i.as_any().downcast_ref::<Int>() {
+                    None => Err("argument type mismatch".to_string()),
+                    Some(i) => match i32::try_from(i.value()) {
+                        Ok(code) => Err(evaluator.exit(code)),
+                        Err(_) => Err(format!("exit code `{}` out of range", i.value())),
+                    },
+                },
+                _ => Err("argument number mismatch".to_string()),
+            }
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    let assert = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("b".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let b = env.get("b").unwrap();
+            if let Some(b) = b.as_any().downcast_ref::<Bool>() {
+                if !b.value() {
+                    return Err("assertion failed".to_string());
+                }
+                return Ok(Rc::new(Null::new()));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //Values which cannot be compared (e.g. `1` and `"a"`) are regarded as different.
+    let assert_eq = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("expected".to_string())),
+            IdentifierNode::new(Token::Ident("actual".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let expected = env.get("expected").unwrap();
+            let actual = env.get("actual").unwrap();
+            if !operator::is_equal(expected.as_ref(), actual.as_ref()) {
+                return Err(format!(
+                    "assertion failed: expected {} but got {}",
+                    repr(expected.as_ref()),
+                    repr(actual.as_ref())
+                ));
+            }
+            Ok(Rc::new(Null::new()))
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    //delays the call of `f`, which takes no argument, until `force()`
+    let lazy = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("f".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let f = env.get("f").unwrap();
+            match as_function(f.as_ref()) {
+                Some(function) if function.accepts(0) => Ok(Rc::new(Thunk::new(f.clone()))),
+                _ => Err("argument type mismatch".to_string()),
+            }
+        }),
+    );
+
+    //calls the function of the thunk only on the first call, and returns the cached result after that
+    let force = BuiltinFunction::with_evaluator(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("t".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let t = env.get("t").unwrap();
+            if let Some(t) = t.as_any().downcast_ref::<Thunk>() {
+                if let Some(value) = t.value() {
+                    return Ok(value);
+                }
+                let value = evaluator.call_function(t.function().as_ref(), vec![], env)?;
+                t.set_value(value.clone());
+                return Ok(value);
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    let len = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(c) = as_collection(l.as_ref()) {
+                //only a huge range (e.g. `-1..9223372036854775807`) can overflow
+                return match i64::try_from(c.len()?) {
+                    Ok(n) => Ok(Rc::new(Int::new(n))),
+                    Err(_) => Err("length overflow".to_string()),
+                };
+            }
+            Err(format!("object of type {} has no length", l.type_name()))
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    //appends any value to an array, or a character or a string to a string
+    let append = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("v".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let v = env.get("v").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let mut elements = a.elements().clone();
+                elements.push(v.clone());
+                return Ok(Rc::new(Array::new(elements)));
+            }
+            if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                if let Some(c) = v.as_any().downcast_ref::<Char>() {
+                    return Ok(format!("{}{}", s.value(), c.value()).into_object());
+                }
+                if let Some(t) = v.as_any().downcast_ref::<Str>() {
+                    return Ok(format!("{}{}", s.value(), t.value()).into_object());
+                }
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //splits a string into lines, each of which is without its line ending (`\n` or `\r\n`)
+    //A final line ending doesn't produce an empty line at the end (e.g. `lines("a\n")` is `["a"]`).
+    let lines = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("s".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let s = env.get("s").unwrap();
+            if let Some(s) = s.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().lines().collect::<Vec<_>>().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //splits a string by a string or a character
+    //An empty separator splits the string into characters (each of which is a string).
+    let split = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("s".to_string())),
+            IdentifierNode::new(Token::Ident("sep".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let s = env.get("s").unwrap();
+            let sep = env.get("sep").unwrap();
+            let s = match s.as_any().downcast_ref::<Str>() {
+                None => return Err("argument type mismatch".to_string()),
+                Some(s) => s.value(),
+            };
+            let pieces: Vec<String> = if let Some(sep) = sep.as_any().downcast_ref::<Char>() {
+                s.split(sep.value()).map(|e| e.to_string()).collect()
+            } else if let Some(sep) = sep.as_any().downcast_ref::<Str>() {
+                if sep.value().is_empty() {
+                    s.chars().map(|c| c.to_string()).collect()
+                } else {
+                    s.split(sep.value()).map(|e| e.to_string()).collect()
+                }
+            } else {
+                return Err("argument type mismatch".to_string());
+            };
+            Ok(pieces.into_object())
+        }),
+    );
+
+    //the inverse of `split()`
+    //Elements other than strings are converted as `print()` does (e.g. `join([1, 'a'], "-")` is `"1-a"`).
+    let join = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("sep".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let sep = env.get("sep").unwrap();
+            let l = match l.as_any().downcast_ref::<Array>() {
+                None => return Err("argument type mismatch".to_string()),
+                Some(l) => l.elements(),
+            };
+            let sep = if let Some(sep) = sep.as_any().downcast_ref::<Char>() {
+                sep.value().to_string()
+            } else if let Some(sep) = sep.as_any().downcast_ref::<Str>() {
+                sep.value().to_string()
+            } else {
+                return Err("argument type mismatch".to_string());
+            };
+            let l: Vec<String> = l.iter().map(|e| e.to_string()).collect();
+            //the separators alone may be far longer than the elements
+            let len = l
+                .iter()
+                .map(|e| e.chars().count())
+                .sum::<usize>()
+                .saturating_add(
+                    sep.chars()
+                        .count()
+                        .saturating_mul(l.len().saturating_sub(1)),
+                );
+            evaluator.check_size(len)?;
+            Ok(l.join(&sep).into_object())
+        }),
+    );
+
+    //`help()` lists the signatures of all the built-in identifiers (one per line in alphabetical order), and
+    // `help("len")` returns the signature of `len`
+    let help = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("name".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let name = env.get("name").unwrap();
+            match name
+                .as_any()
+                .downcast_ref::<Array>()
+                .unwrap()
+                .elements()
+                .as_slice()
+            {
+                [] => {
+                    let mut l: Vec<String> = evaluator
+                        .builtin()
+                        .iter()
+                        .map(|(name, o)| signature(name, o.as_ref()))
+                        .collect();
+                    l.sort();
+                    Ok(l.join("\n").into_object())
+                }
+                [name] => match name.as_any().downcast_ref::<Str>() {
+                    None => Err("argument type mismatch".to_string()),
+                    Some(name) => match evaluator.builtin().lookup_builtin_identifier(name.value())
+                    {
+                        None => Err(format!("`{}` is not a built-in identifier", name.value())),
+                        Some(o) => Ok(signature(name.value(), o.as_ref()).into_object()),
+                    },
+                },
+                _ => Err("argument number mismatch".to_string()),
+            }
+        }),
+    );
+
+    //`concat(a, b, c)` is the same as `a + b + c` for arrays or strings but copies the elements only once
+    //`concat()` returns `[]`.
+    let concat = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("args".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let args = env.get("args").unwrap();
+            let args = args.as_any().downcast_ref::<Array>().unwrap().elements();
+            let mismatch = |i: usize, expected: &str| {
+                format!(
+                    "argument {} of `concat`: expected {}, got {}",
+                    i + 1,
+                    expected,
+                    args[i].type_name()
+                )
+            };
+            if args.first().is_some_and(|e| e.as_any().is::<Str>()) {
+                let mut l = Vec::with_capacity(args.len());
+                for (i, e) in args.iter().enumerate() {
+                    match e.as_any().downcast_ref::<Str>() {
+                        None => return Err(mismatch(i, "string")),
+                        Some(s) => l.push(s.value()),
+                    }
+                }
+                evaluator.check_size(l.iter().map(|s| s.chars().count()).sum())?;
+                return Ok(l.concat().into_object());
+            }
+            let mut l = Vec::with_capacity(args.len());
+            for (i, e) in args.iter().enumerate() {
+                match e.as_any().downcast_ref::<Array>() {
+                    None => return Err(mismatch(i, "array")),
+                    Some(a) => l.push(a.elements()),
+                }
+            }
+            let len = l.iter().map(|a| a.len()).sum();
+            evaluator.check_size(len)?;
+            let mut v = Vec::with_capacity(len);
+            for a in l {
+                v.extend(a.iter().cloned());
+            }
+            Ok(Rc::new(Array::new(v)))
+        }),
+    );
+
+    //`format("x = {}, y = {}", x, y)` replaces each `{}` with the corresponding argument converted as `print()` does
+    //`{{` and `}}` are a literal `{` and `}` respectively.
+    let format = BuiltinFunction::variadic(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("fmt".to_string())),
+            IdentifierNode::new(Token::Ident("args".to_string())),
+        ]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            let fmt = env.get("fmt").unwrap();
+            let args = env.get("args").unwrap();
+            let args = args.as_any().downcast_ref::<Array>().unwrap().elements();
+            let fmt = match fmt.as_any().downcast_ref::<Str>() {
+                None => return Err("argument type mismatch".to_string()),
+                Some(fmt) => fmt.value(),
+            };
+            //the literal parts between the placeholders
+            let mut segments = vec![String::new()];
+            let mut chars = fmt.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (c, chars.peek()) {
+                    ('{', Some('{')) | ('}', Some('}')) => {
+                        chars.next();
+                        segments.last_mut().unwrap().push(c);
+                    }
+                    ('{', Some('}')) => {
+                        chars.next();
+                        segments.push(String::new());
+                    }
+                    ('{', _) | ('}', _) => {
+                        return Err(format!("unmatched `{}` in the format string", c));
+                    }
+                    (c, _) => segments.last_mut().unwrap().push(c),
+                }
+            }
+            if segments.len() - 1 != args.len() {
+                return Err(format!(
+                    "the number of placeholders ({}) doesn't match the number of arguments ({})",
+                    segments.len() - 1,
+                    args.len()
+                ));
+            }
+            let mut ret = segments[0].clone();
+            for (arg, segment) in args.iter().zip(&segments[1..]) {
+                ret += &arg.to_string();
+                ret += segment;
+            }
+            Ok(ret.into_object())
+        }),
+    );
+
+    //`chars("あa")` is `['あ', 'a']`
+    let chars = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("s".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let s = env.get("s").unwrap();
+            if let Some(s) = s.as_any().downcast_ref::<Str>() {
+                return Ok(Rc::new(Array::new(
+                    s.value().chars().map(|c| c.into_object()).collect(),
+                )));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //returns the index of the first occurrence of `v`, or `-1` if not found
+    //`v` is an element of an array (compared by `==`), or a substring or a character of a string.
+    //The index of a string is counted in characters as in `s[i]`.
+    let index_of = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("v".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let v = env.get("v").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let i = a
+                    .elements()
+                    .iter()
+                    .position(|e| operator::is_equal(e.as_ref(), v.as_ref()));
+                return Ok(Rc::new(Int::new(i.map_or(-1, |i| i as i64))));
+            }
+            if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                let byte_index = if let Some(c) = v.as_any().downcast_ref::<Char>() {
+                    s.value().find(c.value())
+                } else if let Some(t) = v.as_any().downcast_ref::<Str>() {
+                    s.value().find(t.value())
+                } else {
+                    return Err("argument type mismatch".to_string());
+                };
+                let i = byte_index.map(|i| s.value()[..i].chars().count());
+                return Ok(Rc::new(Int::new(i.map_or(-1, |i| i as i64))));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //returns the number of the elements equal to `v` in an array, or the number of the non-overlapping occurrences of
+    // a substring or a character in a string (e.g. `count("aaaa", "aa")` is `2`, not `3`)
+    //If `v` is a function, it is called as a predicate for each element (or each character of a string) instead. Thus
+    // `count(l, f)` never counts the occurrences of the function `f` itself.
+    let count = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("v".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let v = env.get("v").unwrap();
+            let elements: Vec<Rc<dyn Object>> = if let Some(a) = l.as_any().downcast_ref::<Array>()
+            {
+                if as_function(v.as_ref()).is_none() {
+                    let n = a
+                        .elements()
+                        .iter()
+                        .filter(|e| operator::is_equal(e.as_ref(), v.as_ref()))
+                        .count();
+                    return Ok(Rc::new(Int::new(n as i64)));
+                }
+                a.elements().clone()
+            } else if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                if as_function(v.as_ref()).is_none() {
+                    let n = if let Some(c) = v.as_any().downcast_ref::<Char>() {
+                        s.value().matches(c.value()).count()
+                    } else if let Some(t) = v.as_any().downcast_ref::<Str>() {
+                        if t.value().is_empty() {
+                            return Err("cannot count empty strings".to_string());
+                        }
+                        s.value().matches(t.value()).count()
+                    } else {
+                        return Err(format!(
+                            "argument 2 of `count`: expected char/string/function, got {}",
+                            v.type_name()
+                        ));
+                    };
+                    return Ok(Rc::new(Int::new(n as i64)));
+                }
+                s.value().chars().map(|c| c.into_object()).collect()
+            } else {
+                return Err(format!(
+                    "argument 1 of `count`: expected array/string, got {}",
+                    l.type_name()
+                ));
+            };
+            let mut n = 0;
+            for e in elements {
+                let b = evaluator.call_function(v.as_ref(), vec![e], env)?;
+                match b.as_any().downcast_ref::<Bool>() {
+                    None => return Err("predicate of `count` returned a non-boolean".to_string()),
+                    Some(b) if b.value() => n += 1,
+                    Some(_) => (),
+                }
+            }
+            Ok(Rc::new(Int::new(n)))
+        }),
+    );
+
+    //returns a new array without the duplicates (by `==`), keeping the first occurrences in the order
+    let unique = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let mut elements: Vec<Rc<dyn Object>> = vec![];
+                for e in a.elements() {
+                    let mut is_duplicate = false;
+                    for x in &elements {
+                        let b = operator::binary_eq(x.as_ref(), e.as_ref()).map_err(|_| {
+                            format!("cannot compare {} with {}", e.type_name(), x.type_name())
+                        })?;
+                        if b.as_any().downcast_ref::<Bool>().unwrap().value() {
+                            is_duplicate = true;
+                            break;
+                        }
+                    }
+                    if !is_duplicate {
+                        elements.push(e.clone());
+                    }
+                }
+                return Ok(Rc::new(Array::new(elements)));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //returns a hash from each element of an array to the number of its occurrences
+    //The keys are in the order of the first occurrences.
+    let frequency = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let mut h = Hash::new();
+                for e in a.elements() {
+                    let n = match h.get(e.as_ref())? {
+                        None => 1,
+                        Some(n) => n.as_any().downcast_ref::<Int>().unwrap().value() + 1,
+                    };
+                    h.insert(e.clone(), Rc::new(Int::new(n)))?;
+                }
+                return Ok(Rc::new(h));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //`first()` and `last()` return `null` for an empty array or string
+    let first = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                return Ok(a.elements().first().cloned().into_object());
+            }
+            if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().chars().next().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    let last = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                return Ok(a.elements().last().cloned().into_object());
+            }
+            if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().chars().next_back().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //returns a new array (or string) without the first element
+    //The rest of an empty array (or string) is an empty one, so that a recursion on `rest()` terminates with the
+    // ordinary emptiness check.
+    let rest = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                return Ok(Rc::new(Array::new(
+                    a.elements().iter().skip(1).cloned().collect(),
+                )));
+            }
+            if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().chars().skip(1).collect::<String>().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //`flatten(l)` flattens one level of nesting (e.g. `[[1], [2, [3]], 4]` to `[1, 2, [3], 4]`), and `flatten(l, depth)`
+    // flattens `depth` levels, where `-1` means all the levels
+    //This doesn't recurse so that a deeply nested array never overflows the stack.
+    let flatten = BuiltinFunction::variadic(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("depth".to_string())),
+        ]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let depth = env.get("depth").unwrap();
+            let l = match l.as_any().downcast_ref::<Array>() {
+                None => return Err("argument type mismatch".to_string()),
+                Some(l) => l,
+            };
+            let depth = match depth
+                .as_any()
+                .downcast_ref::<Array>()
+                .unwrap()
+                .elements()
+                .as_slice()
+            {
+                [] => 1,
+                [depth] => match depth.as_any().downcast_ref::<Int>() {
+                    None => return Err("argument type mismatch".to_string()),
+                    Some(depth) if (depth.value() < -1) => {
+                        return Err(format!("invalid depth `{}`", depth.value()))
+                    }
+                    Some(depth) => depth.value(),
+                },
+                _ => return Err("argument number mismatch".to_string()),
+            };
+            let mut ret = vec![];
+            let mut stack = vec![(l.elements().iter(), 0)];
+            while let Some((iter, d)) = stack.last_mut() {
+                let d = *d;
+                match iter.next() {
+                    None => {
+                        stack.pop();
+                    }
+                    Some(e) => match e.as_any().downcast_ref::<Array>() {
+                        Some(a) if (depth == -1) || (d < depth) => {
+                            stack.push((a.elements().iter(), d + 1))
+                        }
+                        _ => ret.push(e.clone()),
+                    },
+                }
+            }
+            Ok(Rc::new(Array::new(ret)))
+        }),
+    );
+
+    //reverses an array, or a string by character (not by byte)
+    let reverse = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                return Ok(Rc::new(Array::new(
+                    a.elements().iter().rev().cloned().collect(),
+                )));
+            }
+            if let Some(s) = l.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().chars().rev().collect::<String>().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //`keys(h)`, `values(h)` and `items(h)` return the keys, the values and the `[key, value]` pairs respectively, in
+    // the insertion order
+    //The returned arrays are new ones, so they never alias the internal storage of `h`.
+    let keys = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("h".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let h = hash_argument("keys", env)?;
+            Ok(Rc::new(Array::new(
+                h.entries().iter().map(|(k, _)| k.clone()).collect(),
+            )))
+        }),
+    );
+    let values = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("h".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let h = hash_argument("values", env)?;
+            Ok(Rc::new(Array::new(
+                h.entries().iter().map(|(_, v)| v.clone()).collect(),
+            )))
+        }),
+    );
+    let items = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("h".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let h = hash_argument("items", env)?;
+            Ok(Rc::new(Array::new(
+                h.entries()
+                    .iter()
+                    .map(|(k, v)| Rc::new(Array::new(vec![k.clone(), v.clone()])) as _)
+                    .collect(),
+            )))
+        }),
+    );
+
+    //returns a copy of `h` without `key` (or just a copy if `h` doesn't have `key`)
+    let delete = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("h".to_string())),
+            IdentifierNode::new(Token::Ident("key".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let h = env.get("h").unwrap();
+            let key = env.get("key").unwrap();
+            if let Some(h) = h.as_any().downcast_ref::<Hash>() {
+                let mut h = h.clone();
+                h.remove(key.as_ref())?;
+                return Ok(Rc::new(h));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //same as `key in h`
+    let has_key = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("h".to_string())),
+            IdentifierNode::new(Token::Ident("key".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let h = env.get("h").unwrap();
+            let key = env.get("key").unwrap();
+            if let Some(h) = h.as_any().downcast_ref::<Hash>() {
+                return Ok(Rc::new(Bool::new(h.get(key.as_ref())?.is_some())));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //same as `a + b` for hashes
+    let merge = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("a".to_string())),
+            IdentifierNode::new(Token::Ident("b".to_string())),
+        ]),
         Rc::new(|env: &Environment| -> EvalResult {
-            println!("{}", env.get("o").unwrap());
-            Ok(Rc::new(Null::new()))
+            let a = env.get("a").unwrap();
+            let b = env.get("b").unwrap();
+            if a.as_any().downcast_ref::<Hash>().is_none()
+                || b.as_any().downcast_ref::<Hash>().is_none()
+            {
+                return Err("argument type mismatch".to_string());
+            }
+            operator::binary_plus(a.as_ref(), b.as_ref())
         }),
     );
 
-    let eprint = BuiltinFunction::new(
-        Rc::new(vec![IdentifierNode::new(Token::Ident("o".to_string()))]),
+    //returns a new array sorted in the ascending order by `<`
+    //The sort is stable, and elements which cannot be compared by `<` (e.g. `1` and `"a"`) cause an error.
+    let sort = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
         Rc::new(|env: &Environment| -> EvalResult {
-            eprintln!("{}", env.get("o").unwrap());
-            Ok(Rc::new(Null::new()))
+            let l = env.get("l").unwrap();
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let sorted = merge_sort(a.elements(), &mut |x, y| {
+                    let b = operator::binary_lt(x.as_ref(), y.as_ref()).map_err(|_| {
+                        format!("cannot compare {} with {}", x.type_name(), y.type_name())
+                    })?;
+                    Ok(b.as_any().downcast_ref::<Bool>().unwrap().value())
+                })?;
+                return Ok(Rc::new(Array::new(sorted)));
+            }
+            Err("argument type mismatch".to_string())
         }),
     );
 
-    /*-------------------------------------*/
-
-    let exit = BuiltinFunction::new(
-        Rc::new(vec![IdentifierNode::new(Token::Ident("i".to_string()))]),
-        Rc::new(|env: &Environment| -> EvalResult {
-            let i = env.get("i").unwrap();
-            if let Some(i) = i.as_any().downcast_ref::<Int>() {
-                process::exit(i.value() as i32);
+    //returns a new array sorted by `f`, where `f(a, b)` returns `true` if `a` should come before `b`
+    //The sort is stable (i.e. `a` and `b` for which both `f(a, b)` and `f(b, a)` are `false` keep their order), and an
+    // error in `f` stops the sort.
+    let sort_by = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("f".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let f = env.get("f").unwrap();
+            if as_function(f.as_ref()).is_none() {
+                return Err("argument `f` of `sort_by` is not a function".to_string());
+            }
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let sorted = merge_sort(a.elements(), &mut |x, y| {
+                    let b = evaluator.call_function(f.as_ref(), vec![x.clone(), y.clone()], env)?;
+                    match b.as_any().downcast_ref::<Bool>() {
+                        Some(b) => Ok(b.value()),
+                        None => Err("comparator of `sort_by` returned a non-boolean".to_string()),
+                    }
+                })?;
+                return Ok(Rc::new(Array::new(sorted)));
             }
             Err("argument type mismatch".to_string())
         }),
     );
 
-    /*-------------------------------------*/
+    //applies `f` to each element
+    let map = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("f".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let f = env.get("f").unwrap();
+            if as_function(f.as_ref()).is_none() {
+                return Err("argument `f` of `map` is not a function".to_string());
+            }
+            if let Some(a) = l.as_any().downcast_ref::<Array>() {
+                let mut elements = Vec::with_capacity(a.elements().len());
+                for e in a.elements() {
+                    elements.push(evaluator.call_function(f.as_ref(), vec![e.clone()], env)?);
+                }
+                return Ok(Rc::new(Array::new(elements)));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
 
-    let len = BuiltinFunction::new(
-        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+    //`take(l, n)` returns the first `n` elements and `drop(l, n)` returns the rest
+    //`n` is clamped to the range `0..=len(l)`.
+    let take = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("n".to_string())),
+        ]),
         Rc::new(|env: &Environment| -> EvalResult {
             let l = env.get("l").unwrap();
-            if let Some(s) = l.as_any().downcast_ref::<Str>() {
-                return Ok(Rc::new(Int::new(s.value().chars().count() as i64)));
+            let n = env.get("n").unwrap();
+            if let (Some(a), Some(n)) = (
+                l.as_any().downcast_ref::<Array>(),
+                n.as_any().downcast_ref::<Int>(),
+            ) {
+                let n = n.value().clamp(0, a.elements().len() as i64) as usize;
+                return Ok(Rc::new(Array::new(a.elements()[..n].to_vec())));
             }
-            if let Some(s) = l.as_any().downcast_ref::<Array>() {
-                return Ok(Rc::new(Int::new(s.elements().len() as i64)));
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    let drop = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("n".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let l = env.get("l").unwrap();
+            let n = env.get("n").unwrap();
+            if let (Some(a), Some(n)) = (
+                l.as_any().downcast_ref::<Array>(),
+                n.as_any().downcast_ref::<Int>(),
+            ) {
+                let n = n.value().clamp(0, a.elements().len() as i64) as usize;
+                return Ok(Rc::new(Array::new(a.elements()[n..].to_vec())));
             }
             Err("argument type mismatch".to_string())
         }),
     );
 
-    /*-------------------------------------*/
+    //`take_while(l, f)` returns the longest prefix whose elements satisfy `f` and `drop_while(l, f)` returns the rest
+    let take_while = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("l".to_string())),
+            IdentifierNode::new(Token::Ident("f".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let (a, n) = prefix_length("take_while", evaluator, env)?;
+            Ok(Rc::new(Array::new(a.elements()[..n].to_vec())))
+        }),
+    );
 
-    let append = BuiltinFunction::new(
+    let drop_while = BuiltinFunction::with_evaluator(
         Rc::new(vec![
             IdentifierNode::new(Token::Ident("l".to_string())),
-            IdentifierNode::new(Token::Ident("v".to_string())),
+            IdentifierNode::new(Token::Ident("f".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let (a, n) = prefix_length("drop_while", evaluator, env)?;
+            Ok(Rc::new(Array::new(a.elements()[n..].to_vec())))
+        }),
+    );
+
+    //`partial(f, a, b)` returns a function which takes the rest of the parameters `x, y, ...` of `f` and calls
+    // `f(a, b, x, y, ...)`
+    let partial = BuiltinFunction::variadic(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("f".to_string())),
+            IdentifierNode::new(Token::Ident("args".to_string())),
+        ]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            let f = env.get("f").unwrap().clone();
+            let bound = env.get("args").unwrap();
+            let bound = bound
+                .as_any()
+                .downcast_ref::<Array>()
+                .unwrap()
+                .elements()
+                .clone();
+            let function = match as_function(f.as_ref()) {
+                None => return Err("argument `f` of `partial` is not a function".to_string()),
+                Some(function) => function,
+            };
+            let variadic = function.is_variadic();
+            let num_fixed = function.num_parameter() - usize::from(variadic);
+            if !variadic && (bound.len() > num_fixed) {
+                return Err("too many arguments are given to `partial`".to_string());
+            }
+            //the extra arguments for a variadic function go to its last parameter
+            let parameters = copy_parameters(&function.parameters()[bound.len().min(num_fixed)..]);
+            Ok(Rc::new(forwarding_function(
+                parameters,
+                variadic,
+                move |evaluator, env, arguments| {
+                    let arguments = bound.iter().cloned().chain(arguments).collect();
+                    evaluator.call_function(f.as_ref(), arguments, env)
+                },
+            )))
+        }),
+    );
+
+    //`compose(f, g)` is equivalent to `fn(x) { f(g(x)) }`
+    //The returned function takes the same parameters as `g`.
+    let compose = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("f".to_string())),
+            IdentifierNode::new(Token::Ident("g".to_string())),
         ]),
         Rc::new(|env: &Environment| -> EvalResult {
+            let f = env.get("f").unwrap().clone();
+            let g = env.get("g").unwrap().clone();
+            if as_function(f.as_ref()).is_none() {
+                return Err("argument `f` of `compose` is not a function".to_string());
+            }
+            let (parameters, variadic) = match as_function(g.as_ref()) {
+                None => return Err("argument `g` of `compose` is not a function".to_string()),
+                Some(function) => (
+                    copy_parameters(function.parameters()),
+                    function.is_variadic(),
+                ),
+            };
+            Ok(Rc::new(forwarding_function(
+                parameters,
+                variadic,
+                move |evaluator, env, arguments| {
+                    let y = evaluator.call_function(g.as_ref(), arguments, env)?;
+                    evaluator.call_function(f.as_ref(), vec![y], env)
+                },
+            )))
+        }),
+    );
+
+    //calls `f` with the elements of `args` as the arguments (e.g. `apply(f, [1, 2])` is `f(1, 2)`)
+    let apply = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("f".to_string())),
+            IdentifierNode::new(Token::Ident("args".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let f = env.get("f").unwrap();
+            let args = env.get("args").unwrap();
+            if as_function(f.as_ref()).is_none() {
+                return Err("argument `f` of `apply` is not a function".to_string());
+            }
+            if let Some(args) = args.as_any().downcast_ref::<Array>() {
+                return evaluator.call_function(f.as_ref(), args.elements().clone(), env);
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //`and_then(v, f)` is `f(v)` unless `v` is `null`, in which case `f` is not called and `null` is returned
+    //This chains the operations which may fail (e.g. `and_then(and_then(v, f), g)`).
+    let and_then = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("v".to_string())),
+            IdentifierNode::new(Token::Ident("f".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let v = env.get("v").unwrap();
+            let f = env.get("f").unwrap();
+            if as_function(f.as_ref()).is_none() {
+                return Err("argument `f` of `and_then` is not a function".to_string());
+            }
+            if v.as_any().downcast_ref::<Null>().is_some() {
+                return Ok(v.clone());
+            }
+            evaluator.call_function(f.as_ref(), vec![v.clone()], env)
+        }),
+    );
+
+    let to_array = BuiltinFunction::with_evaluator(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("r".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let r = env.get("r").unwrap();
+            if let Some(r) = r.as_any().downcast_ref::<Range>() {
+                evaluator.check_size(r.len()?)?;
+                return Ok(Rc::new(Array::new(
+                    r.iter().map(|i| Rc::new(Int::new(i)) as _).collect(),
+                )));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    /*-------------------------------------*/
+    //pseudo-random numbers
+    //The state belongs to the evaluator (see `Evaluator::with_rng()`), and `seed(n)` makes the subsequent results
+    // reproducible.
+
+    let seed = BuiltinFunction::with_evaluator(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("n".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let n = env.get("n").unwrap();
+            if let Some(n) = n.as_any().downcast_ref::<Int>() {
+                evaluator.seed(n.value() as u64);
+                return Ok(Rc::new(Null::new()));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //returns a float in `[0, 1)`
+    let random = BuiltinFunction::with_evaluator(
+        Rc::new(vec![]),
+        Rc::new(|evaluator: &Evaluator, _: &Environment| -> EvalResult {
+            Ok(Rc::new(Float::new(
+                evaluator.with_rng(|rng| rng.next_f64()),
+            )))
+        }),
+    );
+
+    //returns an integer in `[a, b]` (both inclusive)
+    let random_int = BuiltinFunction::with_evaluator(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("a".to_string())),
+            IdentifierNode::new(Token::Ident("b".to_string())),
+        ]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+            let a = env.get("a").unwrap();
+            let b = env.get("b").unwrap();
+            match (
+                a.as_any().downcast_ref::<Int>(),
+                b.as_any().downcast_ref::<Int>(),
+            ) {
+                (Some(a), Some(b)) if (a.value() > b.value()) => Err("empty range".to_string()),
+                (Some(a), Some(b)) => Ok(Rc::new(Int::new(
+                    evaluator.with_rng(|rng| rng.next_range(a.value(), b.value())),
+                ))),
+                _ => Err("argument type mismatch".to_string()),
+            }
+        }),
+    );
+
+    //returns a shuffled copy of the array
+    let shuffle = BuiltinFunction::with_evaluator(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
             let l = env.get("l").unwrap();
-            if let Some(a) = l.as_any().downcast_ref::<Array>() {
-                let mut elements = a.elements().clone();
-                elements.push(env.get("v").cloned().unwrap());
-                return Ok(Rc::new(Array::new(elements)));
+            if let Some(l) = l.as_any().downcast_ref::<Array>() {
+                let mut v = l.elements().to_vec();
+                //Fisher-Yates
+                evaluator.with_rng(|rng| {
+                    for i in (1..v.len()).rev() {
+                        v.swap(i, rng.next_range(0, i as i64) as usize);
+                    }
+                });
+                return Ok(Rc::new(Array::new(v)));
             }
             Err("argument type mismatch".to_string())
         }),
@@ -114,6 +1628,15 @@ fn initialize_builtin() -> Builtin {
         }),
     );
 
+    let repr_ = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("o".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            Ok(Rc::new(Str::new(Rc::new(repr(
+                env.get("o").unwrap().as_ref(),
+            )))))
+        }),
+    );
+
     let str_ = BuiltinFunction::new(
         Rc::new(vec![IdentifierNode::new(Token::Ident("v".to_string()))]),
         Rc::new(|env: &Environment| -> EvalResult {
@@ -121,17 +1644,65 @@ fn initialize_builtin() -> Builtin {
             if let Some(c) = v.as_any().downcast_ref::<Char>() {
                 return Ok(Rc::new(Str::new(Rc::new(c.to_string()))));
             }
+            //the inverse of `chars()`
+            if let Some(a) = v.as_any().downcast_ref::<Array>() {
+                let mut s = String::with_capacity(a.elements().len());
+                for e in a.elements() {
+                    match e.as_any().downcast_ref::<Char>() {
+                        None => return Err("argument type mismatch".to_string()),
+                        Some(c) => s.push(c.value()),
+                    }
+                }
+                return Ok(s.into_object());
+            }
+            //the inverse of `int()` and `float()`
+            if v.as_any().downcast_ref::<Int>().is_some()
+                || v.as_any().downcast_ref::<BigInt>().is_some()
+                || v.as_any().downcast_ref::<Float>().is_some()
+                || v.as_any().downcast_ref::<Bool>().is_some()
+            {
+                return Ok(v.to_string().into_object());
+            }
+            if v.as_any().downcast_ref::<Str>().is_some() {
+                return Ok(v.clone());
+            }
             Err("argument type mismatch".to_string())
         }),
     );
 
-    let int_ = BuiltinFunction::new(
-        Rc::new(vec![IdentifierNode::new(Token::Ident("v".to_string()))]),
-        Rc::new(|env: &Environment| -> EvalResult {
+    //`int(s, radix)` parses a string in base `radix` instead of `10`
+    let int_ = BuiltinFunction::variadic(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("v".to_string())),
+            IdentifierNode::new(Token::Ident("radix".to_string())),
+        ]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
             let v = env.get("v").unwrap();
+            let radix = env.get("radix").unwrap();
+            let radix = radix.as_any().downcast_ref::<Array>().unwrap().elements();
+            let radix = match radix.as_slice() {
+                [] => None,
+                [radix] => Some(radix),
+                _ => return Err("argument number mismatch".to_string()),
+            };
+            if let Some(v) = v.as_any().downcast_ref::<Str>() {
+                return match radix {
+                    None => parse_integer(v.value(), 10),
+                    Some(radix) => match radix.as_any().downcast_ref::<Int>() {
+                        None => Err("argument type mismatch".to_string()),
+                        Some(radix) => parse_integer(v.value(), radix.value()),
+                    },
+                };
+            }
+            if radix.is_some() {
+                return Err("argument type mismatch".to_string());
+            }
             if let Some(v) = v.as_any().downcast_ref::<Float>() {
                 return Ok(Rc::new(Int::new(v.value() as i64)));
             }
+            if let Some(v) = v.as_any().downcast_ref::<Bool>() {
+                return Ok(Rc::new(Int::new(v.value() as i64)));
+            }
             Err("argument type mismatch".to_string())
         }),
     );
@@ -143,26 +1714,472 @@ fn initialize_builtin() -> Builtin {
             if let Some(v) = v.as_any().downcast_ref::<Int>() {
                 return Ok(Rc::new(Float::new(v.value() as f64)));
             }
+            if let Some(v) = v.as_any().downcast_ref::<BigInt>() {
+                return Ok(Rc::new(Float::new(v.value().to_f64())));
+            }
+            if let Some(v) = v.as_any().downcast_ref::<Str>() {
+                return parse_float(v.value());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //`abs(i)` for the minimum integer is an integer overflow (or a big integer with `bignum` feature) as `-i` is
+    let abs = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("x".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let x = env.get("x").unwrap();
+            let is_negative = if let Some(i) = x.as_any().downcast_ref::<Int>() {
+                i.value() < 0
+            } else if let Some(i) = x.as_any().downcast_ref::<BigInt>() {
+                i.value().is_negative()
+            } else if let Some(f) = x.as_any().downcast_ref::<Float>() {
+                //also clears the sign of `-0.0`
+                return Ok(Rc::new(Float::new(f.value().abs())));
+            } else {
+                return Err("argument type mismatch".to_string());
+            };
+            if is_negative {
+                operator::unary_minus(x.as_ref())
+            } else {
+                Ok(x.clone())
+            }
+        }),
+    );
+
+    //`min(a, b, ...)` or `min(array)` returns the first minimum value by `<`
+    let min = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("args".to_string()))]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            extremum("min", env.get("args").unwrap().as_ref(), |x, y| {
+                operator::binary_lt(x, y)
+            })
+        }),
+    );
+
+    //`max(a, b, ...)` or `max(array)` returns the first maximum value by `>`
+    let max = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("args".to_string()))]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            extremum("max", env.get("args").unwrap().as_ref(), |x, y| {
+                operator::binary_gt(x, y)
+            })
+        }),
+    );
+
+    //returns the sum of the numbers in an array (`0` if empty)
+    let sum = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            fold_numbers(
+                "sum",
+                env.get("l").unwrap().as_ref(),
+                0,
+                operator::binary_plus,
+            )
+        }),
+    );
+
+    //returns the product of the numbers in an array (`1` if empty)
+    let product = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("l".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            fold_numbers(
+                "product",
+                env.get("l").unwrap().as_ref(),
+                1,
+                operator::binary_asterisk,
+            )
+        }),
+    );
+
+    //`floor(x)`, `ceil(x)` and `round(x)` convert a float to an integer (and return an integer as is)
+    //`round()` rounds half away from zero (e.g. `round(2.5)` is `3` and `round(-2.5)` is `-3`), not half to even.
+    let floor = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("x".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            round_to_integer("floor", env.get("x").unwrap(), f64::floor)
+        }),
+    );
+
+    let ceil = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("x".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            round_to_integer("ceil", env.get("x").unwrap(), f64::ceil)
+        }),
+    );
+
+    //`round(x, digits)` returns the float `x` rounded to `digits` decimal places instead (e.g. `round(3.14159, 2)` is
+    // `3.14`)
+    let round = BuiltinFunction::variadic(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("x".to_string())),
+            IdentifierNode::new(Token::Ident("digits".to_string())),
+        ]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            let x = env.get("x").unwrap();
+            let digits = env.get("digits").unwrap();
+            let digits = digits.as_any().downcast_ref::<Array>().unwrap().elements();
+            let digits = match digits.as_slice() {
+                [] => return round_to_integer("round", x, f64::round),
+                [digits] => digits,
+                _ => return Err("argument number mismatch".to_string()),
+            };
+            if let (Some(x), Some(digits)) = (
+                x.as_any().downcast_ref::<Float>(),
+                digits.as_any().downcast_ref::<Int>(),
+            ) {
+                return Ok(Rc::new(Float::new(round_to_digits(
+                    x.value(),
+                    digits.value(),
+                ))));
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //same as `round()` but returns a string with exactly `digits` decimal places (e.g. `format_float(2.5, 3)` is
+    // `"2.500"`)
+    let format_float = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("x".to_string())),
+            IdentifierNode::new(Token::Ident("digits".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let x = env.get("x").unwrap();
+            let digits = env.get("digits").unwrap();
+            if let (Some(x), Some(digits)) = (
+                x.as_any().downcast_ref::<Float>(),
+                digits.as_any().downcast_ref::<Int>(),
+            ) {
+                let rounded = round_to_digits(x.value(), digits.value());
+                let precision = digits.value().clamp(0, 400) as usize;
+                return Ok(format!("{:.*}", precision, rounded).into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //returns the Unicode code point of a character (or a string consisting of a single character)
+    let ord = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("c".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let c = env.get("c").unwrap();
+            if let Some(c) = c.as_any().downcast_ref::<Char>() {
+                return Ok(Rc::new(Int::new(c.value() as i64)));
+            }
+            if let Some(s) = c.as_any().downcast_ref::<Str>() {
+                let mut it = s.value().chars();
+                if let (Some(c), None) = (it.next(), it.next()) {
+                    return Ok(Rc::new(Int::new(c as i64)));
+                }
+                return Err("argument of `ord` is not a single character".to_string());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //the inverse of `ord()`
+    let chr = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("n".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let n = env.get("n").unwrap();
+            if let Some(n) = n.as_any().downcast_ref::<Int>() {
+                //`char::from_u32()` rejects surrogates and values above `0x10FFFF`
+                return match u32::try_from(n.value()).ok().and_then(char::from_u32) {
+                    None => Err(format!("`{}` is not a valid code point", n.value())),
+                    Some(c) => Ok(c.into_object()),
+                };
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //converts a string or a character to upper case
+    //A character whose upper case consists of more than one character (e.g. `ß` to `SS`) is returned unchanged so that
+    // the result is always a character.
+    let upper = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("v".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let v = env.get("v").unwrap();
+            if let Some(c) = v.as_any().downcast_ref::<Char>() {
+                let mut it = c.value().to_uppercase();
+                return Ok(match (it.next(), it.next()) {
+                    (Some(u), None) => u.into_object(),
+                    _ => v.clone(),
+                });
+            }
+            if let Some(s) = v.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().to_uppercase().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //the counterpart of `upper()`
+    let lower = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("v".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let v = env.get("v").unwrap();
+            if let Some(c) = v.as_any().downcast_ref::<Char>() {
+                let mut it = c.value().to_lowercase();
+                return Ok(match (it.next(), it.next()) {
+                    (Some(l), None) => l.into_object(),
+                    _ => v.clone(),
+                });
+            }
+            if let Some(s) = v.as_any().downcast_ref::<Str>() {
+                return Ok(s.value().to_lowercase().into_object());
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    //character classes
+    //`is_digit()` accepts only ASCII digits (`0` to `9`) while the others follow Unicode (e.g. `is_alpha('あ')` is `true`).
+    let is_digit = (|c: char| c.is_ascii_digit()).into_builtin_function("is_digit");
+    let is_alpha = (|c: char| c.is_alphabetic()).into_builtin_function("is_alpha");
+    let is_alnum = (|c: char| c.is_alphanumeric()).into_builtin_function("is_alnum");
+    let is_space = (|c: char| c.is_whitespace()).into_builtin_function("is_space");
+    let is_upper = (|c: char| c.is_uppercase()).into_builtin_function("is_upper");
+    let is_lower = (|c: char| c.is_lowercase()).into_builtin_function("is_lower");
+
+    /*-------------------------------------*/
+
+    let read_file = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("path".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let path = env.get("path").unwrap();
+            if let Some(path) = path.as_any().downcast_ref::<Str>() {
+                return match fs::read_to_string(path.value()) {
+                    Err(e) => Err(format!("failed to read `{}`: {}", path.value(), e)),
+                    Ok(s) => Ok(s.into_object()),
+                };
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    //creates the file or overwrites the existing one
+    let write_file = BuiltinFunction::new(
+        Rc::new(vec![
+            IdentifierNode::new(Token::Ident("path".to_string())),
+            IdentifierNode::new(Token::Ident("contents".to_string())),
+        ]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let path = env.get("path").unwrap();
+            let contents = env.get("contents").unwrap();
+            if let (Some(path), Some(contents)) = (
+                path.as_any().downcast_ref::<Str>(),
+                contents.as_any().downcast_ref::<Str>(),
+            ) {
+                return match fs::write(path.value(), contents.value()) {
+                    Err(e) => Err(format!("failed to write `{}`: {}", path.value(), e)),
+                    Ok(()) => Ok(Rc::new(Null::new())),
+                };
+            }
+            Err("argument type mismatch".to_string())
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    #[cfg(feature = "json")]
+    let json_parse = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("s".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let s = env.get("s").unwrap();
+            if let Some(s) = s.as_any().downcast_ref::<Str>() {
+                return match serde_json::from_str(s.value()) {
+                    Err(e) => Err(format!("invalid JSON: {}", e)),
+                    Ok(v) => from_json(v),
+                };
+            }
             Err("argument type mismatch".to_string())
         }),
     );
 
+    #[cfg(feature = "json")]
+    let json_stringify = BuiltinFunction::new(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("v".to_string()))]),
+        Rc::new(|env: &Environment| -> EvalResult {
+            let v = to_json(env.get("v").unwrap().as_ref())?;
+            Ok(Rc::new(Str::new(Rc::new(v.to_string()))))
+        }),
+    );
+
+    /*-------------------------------------*/
+
+    //`time()` returns the current Unix time in seconds, and `clock()` returns the seconds elapsed since an arbitrary
+    // (but fixed) point, which is monotonic and meant for measuring a duration (e.g. `clock() - t`)
+    //Either can be replaced via `Builtin::register_fn()` for a deterministic run.
+    let time = (|| match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    })
+    .into_builtin_function("time");
+    let start = Instant::now();
+    let clock = (move || start.elapsed().as_secs_f64()).into_builtin_function("clock");
+
+    //see `Builtin::set_argv()`
+    let argv = (Vec::<String>::new).into_builtin_function("argv");
+
+    //`env(name)` returns the value of the environment variable `name`, or `null` if it is not set (or not valid Unicode)
+    //`env()` returns a hash of all the environment variables sorted by name, skipping those not valid Unicode.
+    let env = BuiltinFunction::variadic(
+        Rc::new(vec![IdentifierNode::new(Token::Ident("name".to_string()))]),
+        Rc::new(|_: &Evaluator, env: &Environment| -> EvalResult {
+            let name = env.get("name").unwrap();
+            match name
+                .as_any()
+                .downcast_ref::<Array>()
+                .unwrap()
+                .elements()
+                .as_slice()
+            {
+                [] => {
+                    let mut vars = std::env::vars_os()
+                        .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+                        .collect::<Vec<_>>();
+                    vars.sort();
+                    let mut h = Hash::new();
+                    for (k, v) in vars {
+                        h.insert(k.into_object(), v.into_object())?;
+                    }
+                    Ok(Rc::new(h))
+                }
+                [name] => match name.as_any().downcast_ref::<Str>() {
+                    None => Err(format!(
+                        "argument 1 of `env`: expected string, got {}",
+                        name.type_name()
+                    )),
+                    Some(name) => Ok(std::env::var(name.value()).ok().into_object()),
+                },
+                _ => Err("argument number mismatch".to_string()),
+            }
+        }),
+    );
+
     /*-------------------------------------*/
 
     let pi = Float::new(std::f64::consts::PI);
 
+    //As there is no NaN or infinity in this language, an argument out of the domain (e.g. `sqrt(-1.0)` or `log(0.0)`)
+    // is an error, and so is a result too large for a float (e.g. `exp(1000.0)`).
+    let sqrt = math_function("sqrt", |x| x >= 0.0, f64::sqrt);
+    let exp = math_function("exp", |_| true, f64::exp);
+    let log = math_function("log", |x| x > 0.0, f64::ln);
+    let log10 = math_function("log10", |x| x > 0.0, f64::log10);
+    let sin = math_function("sin", |_| true, f64::sin);
+    let cos = math_function("cos", |_| true, f64::cos);
+    let tan = math_function("tan", |_| true, f64::tan);
+
     /*-------------------------------------*/
 
     m.insert("print".to_string(), Rc::new(print) as _);
+    m.insert("print_raw".to_string(), Rc::new(print_raw) as _);
+    m.insert("print_debug".to_string(), Rc::new(print_debug) as _);
     m.insert("eprint".to_string(), Rc::new(eprint) as _);
     m.insert("exit".to_string(), Rc::new(exit) as _);
+    m.insert("assert".to_string(), Rc::new(assert) as _);
+    m.insert("assert_eq".to_string(), Rc::new(assert_eq) as _);
+    m.insert("lazy".to_string(), Rc::new(lazy) as _);
+    m.insert("force".to_string(), Rc::new(force) as _);
     m.insert("len".to_string(), Rc::new(len) as _);
     m.insert("append".to_string(), Rc::new(append) as _);
+    m.insert("lines".to_string(), Rc::new(lines) as _);
+    m.insert("split".to_string(), Rc::new(split) as _);
+    m.insert("join".to_string(), Rc::new(join) as _);
+    m.insert("concat".to_string(), Rc::new(concat) as _);
+    m.insert("help".to_string(), Rc::new(help) as _);
+    m.insert("format".to_string(), Rc::new(format) as _);
+    m.insert("chars".to_string(), Rc::new(chars) as _);
+    m.insert("index_of".to_string(), Rc::new(index_of) as _);
+    m.insert("count".to_string(), Rc::new(count) as _);
+    m.insert("unique".to_string(), Rc::new(unique) as _);
+    m.insert("frequency".to_string(), Rc::new(frequency) as _);
+    m.insert("first".to_string(), Rc::new(first) as _);
+    m.insert("last".to_string(), Rc::new(last) as _);
+    m.insert("rest".to_string(), Rc::new(rest) as _);
+    m.insert("flatten".to_string(), Rc::new(flatten) as _);
+    m.insert("reverse".to_string(), Rc::new(reverse) as _);
+    m.insert("keys".to_string(), Rc::new(keys) as _);
+    m.insert("values".to_string(), Rc::new(values) as _);
+    m.insert("items".to_string(), Rc::new(items) as _);
+    m.insert("delete".to_string(), Rc::new(delete) as _);
+    m.insert("has_key".to_string(), Rc::new(has_key) as _);
+    m.insert("merge".to_string(), Rc::new(merge) as _);
+    m.insert("sort".to_string(), Rc::new(sort) as _);
+    m.insert("sort_by".to_string(), Rc::new(sort_by) as _);
+    m.insert("map".to_string(), Rc::new(map) as _);
+    m.insert("take".to_string(), Rc::new(take) as _);
+    m.insert("drop".to_string(), Rc::new(drop) as _);
+    m.insert("take_while".to_string(), Rc::new(take_while) as _);
+    m.insert("drop_while".to_string(), Rc::new(drop_while) as _);
+    m.insert("partial".to_string(), Rc::new(partial) as _);
+    m.insert("compose".to_string(), Rc::new(compose) as _);
+    m.insert("apply".to_string(), Rc::new(apply) as _);
+    m.insert("and_then".to_string(), Rc::new(and_then) as _);
+    m.insert("to_array".to_string(), Rc::new(to_array) as _);
     m.insert("bool".to_string(), Rc::new(bool_) as _);
+    m.insert("repr".to_string(), Rc::new(repr_) as _);
     m.insert("str".to_string(), Rc::new(str_) as _);
     m.insert("int".to_string(), Rc::new(int_) as _);
     m.insert("float".to_string(), Rc::new(float_) as _);
+    m.insert("abs".to_string(), Rc::new(abs) as _);
+    m.insert("min".to_string(), Rc::new(min) as _);
+    m.insert("max".to_string(), Rc::new(max) as _);
+    m.insert("sum".to_string(), Rc::new(sum) as _);
+    m.insert("product".to_string(), Rc::new(product) as _);
+    m.insert("floor".to_string(), Rc::new(floor) as _);
+    m.insert("ceil".to_string(), Rc::new(ceil) as _);
+    m.insert("round".to_string(), Rc::new(round) as _);
+    m.insert("format_float".to_string(), Rc::new(format_float) as _);
+    #[cfg(feature = "json")]
+    m.insert("json_parse".to_string(), Rc::new(json_parse) as _);
+    #[cfg(feature = "json")]
+    m.insert("json_stringify".to_string(), Rc::new(json_stringify) as _);
+    m.insert("ord".to_string(), Rc::new(ord) as _);
+    m.insert("chr".to_string(), Rc::new(chr) as _);
+    //`to_upper()` and `to_lower()` are the aliases of `upper()` and `lower()`
+    let upper: Rc<dyn Object> = Rc::new(upper);
+    let lower: Rc<dyn Object> = Rc::new(lower);
+    m.insert("upper".to_string(), upper.clone());
+    m.insert("to_upper".to_string(), upper);
+    m.insert("lower".to_string(), lower.clone());
+    m.insert("to_lower".to_string(), lower);
+    m.insert("is_digit".to_string(), Rc::new(is_digit) as _);
+    m.insert("is_alpha".to_string(), Rc::new(is_alpha) as _);
+    m.insert("is_alnum".to_string(), Rc::new(is_alnum) as _);
+    m.insert("is_space".to_string(), Rc::new(is_space) as _);
+    m.insert("is_upper".to_string(), Rc::new(is_upper) as _);
+    m.insert("is_lower".to_string(), Rc::new(is_lower) as _);
+    m.insert("read_file".to_string(), Rc::new(read_file) as _);
+    m.insert("write_file".to_string(), Rc::new(write_file) as _);
+    m.insert("time".to_string(), Rc::new(time) as _);
+    m.insert("clock".to_string(), Rc::new(clock) as _);
+    //`args()` is the alias of `argv()`
+    let argv: Rc<dyn Object> = Rc::new(argv);
+    m.insert("argv".to_string(), argv.clone());
+    m.insert("args".to_string(), argv);
+    m.insert("seed".to_string(), Rc::new(seed) as _);
+    m.insert("random".to_string(), Rc::new(random) as _);
+    m.insert("random_int".to_string(), Rc::new(random_int) as _);
+    m.insert("shuffle".to_string(), Rc::new(shuffle) as _);
+    m.insert("env".to_string(), Rc::new(env) as _);
     m.insert("pi".to_string(), Rc::new(pi) as _);
+    m.insert("sqrt".to_string(), Rc::new(sqrt) as _);
+    m.insert("exp".to_string(), Rc::new(exp) as _);
+    m.insert("log".to_string(), Rc::new(log) as _);
+    m.insert("log10".to_string(), Rc::new(log10) as _);
+    m.insert("sin".to_string(), Rc::new(sin) as _);
+    m.insert("cos".to_string(), Rc::new(cos) as _);
+    m.insert("tan".to_string(), Rc::new(tan) as _);
 
-    Builtin { m }
+    Builtin {
+        m,
+        reserved: RESERVED_BUILTINS.iter().map(|s| s.to_string()).collect(),
+    }
 }
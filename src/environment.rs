@@ -1,13 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
-use super::object::Object;
+use itertools::Itertools;
+
+use super::object::{IntoObject, Object};
 
 //This struct is used as a function table, a variable table, etc.
 #[derive(Clone)]
 pub struct Environment {
     m: HashMap<String, Rc<dyn Object>>, //current scope (inner-most scope)
     outer: Option<Rc<Environment>>,     //enclosing scope (parent or outer scope)
+    parameters: HashSet<String>, //keys of `m` which a `let` can still shadow (see `set_parameter()`)
+}
+
+//bindings of the current scope of an `Environment` at some point (see `Environment::snapshot()`)
+#[derive(Clone)]
+pub struct Snapshot {
+    m: HashMap<String, Rc<dyn Object>>,
 }
 
 impl Environment {
@@ -15,9 +24,15 @@ impl Environment {
         Self {
             m: HashMap::new(),
             outer,
+            parameters: HashSet::new(),
         }
     }
 
+    //whether this is the outer-most scope
+    pub fn is_root(&self) -> bool {
+        self.outer.is_none()
+    }
+
     pub fn get(&self, key: &str) -> Option<&Rc<dyn Object>> {
         match self.m.get(key) {
             Some(e) => Some(e),
@@ -32,16 +47,41 @@ impl Environment {
         self.m.insert(key.to_string(), value);
     }
 
+    //convenience for embedders to inject Rust values (e.g. `env.define("port", 8080)`)
+    pub fn define<T: IntoObject>(&mut self, key: &str, value: T) {
+        self.set(key, value.into_object());
+    }
+
+    //binds a parameter of a function
+    //A function body shares the scope with the parameters, but a `let` in the body can still shadow a parameter (only
+    // once, as if the body were an inner scope), which `try_set()` allows.
+    pub fn set_parameter(&mut self, key: &str, value: Rc<dyn Object>) {
+        self.set(key, value);
+        self.parameters.insert(key.to_string());
+    }
+
     pub fn try_set(&mut self, key: &str, value: Rc<dyn Object>) -> Result<(), String> {
         match self.m.get(key) {
-            None => {
+            Some(_) if !self.parameters.remove(key) => Err(format!("`{}` is already defined", key)),
+            _ => {
                 self.m.insert(key.to_string(), value);
                 Ok(())
             }
-            Some(_) => Err(format!("`{}` is already defined", key)),
         }
     }
 
+    //takes a snapshot of the bindings of the current scope, to which the scope can be rolled back by `restore()`
+    //This is cheap as only `Rc`s are cloned. The enclosing scopes are not included as they are never modified through
+    // this environment.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot { m: self.m.clone() }
+    }
+
+    //discards the bindings made (or overwritten) in the current scope since `snapshot` was taken
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.m = snapshot.m;
+    }
+
     //We perform recursive calls to guarantee `outer` is added as the outer-most environment.
     //The performance is not optimized well as we have to call `Rc.as_ref().clone()` multiple times to extract value from `Rc`.
     pub fn set_outer(&mut self, outer: Option<Rc<Environment>>) {
@@ -55,15 +95,95 @@ impl Environment {
         }
     }
 
-    #[allow(dead_code)]
-    fn to_debug_string(&self) -> String {
+    //Dumps the keys of this scope followed by those of at most `max_depth` enclosing scopes.
+    //Scopes deeper than `max_depth` are elided as `...`, so `debug_dump(0)` lists only the current scope.
+    //Keys are sorted to make the output deterministic.
+    pub fn debug_dump(&self, max_depth: usize) -> String {
+        let mut keys = self.m.keys().collect_vec();
+        keys.sort();
         format!(
             "Environment {{\n    m: {:?},\n    outer: {}\n}}",
-            self.m.keys(),
-            match self.outer {
+            keys,
+            match &self.outer {
                 None => "None".to_string(),
-                Some(ref e) => e.to_debug_string(),
+                Some(_) if (max_depth == 0) => "...".to_string(),
+                Some(e) => e.debug_dump(max_depth - 1).replace('\n', "\n    "),
             }
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::evaluator::Evaluator;
+    use super::super::object::Int;
+    use super::super::program::Program;
+    use super::*;
+
+    #[test]
+    fn test_debug_dump() {
+        let mut root = Environment::new(None);
+        root.set("b", Rc::new(Int::new(1)));
+        root.set("a", Rc::new(Int::new(2)));
+        let mut middle = Environment::new(Some(Rc::new(root)));
+        middle.set("c", Rc::new(Int::new(3)));
+        let mut inner = Environment::new(Some(Rc::new(middle)));
+        inner.set("d", Rc::new(Int::new(4)));
+
+        assert_eq!(
+            "Environment {\n    m: [\"d\"],\n    outer: ...\n}",
+            inner.debug_dump(0)
+        );
+        assert_eq!(
+            "Environment {\n    m: [\"d\"],\n    outer: Environment {\n        m: [\"c\"],\n        outer: ...\n    }\n}",
+            inner.debug_dump(1)
+        );
+        assert_eq!(inner.debug_dump(2), inner.debug_dump(100));
+        assert!(inner.debug_dump(2).contains(r#"m: ["a", "b"],"#));
+        assert!(inner
+            .debug_dump(2)
+            .ends_with("outer: None\n        }\n    }\n}"));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let evaluator = Evaluator::new();
+        let eval = |env: &mut Environment, s: &str| {
+            evaluator
+                .eval(Program::parse(s).unwrap().root(), env)
+                .map(|o| o.to_string())
+        };
+
+        let mut outer = Environment::new(None);
+        outer.define("o", 0);
+        let mut env = Environment::new(Some(Rc::new(outer)));
+        eval(&mut env, "let a = 1; let b = 2;").unwrap();
+        env.define("c", 3);
+
+        let snapshot = env.snapshot();
+        eval(&mut env, "let d = 4; let e = a + d;").unwrap();
+        env.define("c", 30);
+        assert_eq!(Ok("30".to_string()), eval(&mut env, "c"));
+        assert_eq!(Ok("5".to_string()), eval(&mut env, "e"));
+
+        env.restore(snapshot.clone());
+        assert_eq!(
+            Ok("[0, 1, 2, 3]".to_string()),
+            eval(&mut env, "[o, a, b, c]")
+        );
+        assert!(env.get("d").is_none());
+        assert!(env.get("e").is_none());
+        assert_eq!(
+            "Environment {\n    m: [\"a\", \"b\", \"c\"],\n    outer: ...\n}",
+            env.debug_dump(0)
+        );
+
+        //the names are available again, and the same snapshot can be restored any number of times
+        eval(&mut env, "let d = 40;").unwrap();
+        assert_eq!(Ok("40".to_string()), eval(&mut env, "d"));
+        env.restore(snapshot);
+        assert!(env.get("d").is_none());
+        assert!(eval(&mut env, "let a = 10;").is_err());
+    }
+}
@@ -1,27 +1,172 @@
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use super::ast::*;
-use super::builtin::Builtin;
+use super::bignum::BigNum;
+use super::builtin::{Builtin, TIME_BUILTINS};
 use super::environment::Environment;
 use super::object::*;
 use super::operator;
+use super::random::Rng;
 use super::token::Token;
 
 pub type EvalResult = Result<Rc<dyn Object>, String>;
 
+//capabilities given to the evaluated code
+//A disabled built-in function is still defined but always fails with "`exit` is disabled in this sandbox" or alike.
+#[derive(Debug, Clone)]
+pub struct EvaluatorConfig {
+    pub allow_io: bool, //access to the host such as the file system (see `builtin::IO_BUILTINS`)
+    pub allow_exit: bool, //`exit()`
+    pub allow_time: bool, //`time()` and `clock()` (see `builtin::TIME_BUILTINS`)
+    //the maximum number of evaluation steps (i.e. calls of `Evaluator::eval()`) summed over the lifetime of the
+    // evaluator, after which the evaluation fails with "execution limit exceeded"
+    //`None` means no limit.
+    pub max_steps: Option<u64>,
+    //the maximum length of an array or a string (in characters) produced by an operator or a built-in function, beyond
+    // which the evaluation fails with "result too large"
+    //`None` means no limit.
+    pub max_size: Option<usize>,
+}
+
+impl Default for EvaluatorConfig {
+    fn default() -> Self {
+        Self {
+            allow_io: true,
+            allow_exit: true,
+            allow_time: true,
+            max_steps: None,
+            max_size: None,
+        }
+    }
+}
+
+impl EvaluatorConfig {
+    //only pure built-in functions (and `print()` and alike, whose output can be redirected) are allowed
+    pub fn sandboxed() -> Self {
+        Self {
+            allow_io: false,
+            allow_exit: false,
+            allow_time: false,
+            max_steps: None,
+            max_size: None,
+        }
+    }
+}
+
 pub struct Evaluator {
     builtin: Builtin,
+    max_steps: Option<u64>,
+    steps: Cell<u64>,
+    max_size: Option<usize>,
+    rng: Cell<Rng>,                     //state of `random()` and alike
+    exit_code: Cell<Option<i32>>, //set by `exit()` while the error it returns propagates up to the root
+    initializing: RefCell<Vec<String>>, //names of the `let`s whose initializers are being evaluated
 }
 
 impl Evaluator {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
+        Self::with_builtin(Builtin::new())
+    }
+
+    //uses `builtin` extended with custom built-in identifiers (see `Builtin::register()`)
+    pub fn with_builtin(builtin: Builtin) -> Self {
+        Self::with_config(builtin, EvaluatorConfig::default())
+    }
+
+    //disables the built-in functions in `builtin` which `config` doesn't allow
+    pub fn with_config(mut builtin: Builtin, config: EvaluatorConfig) -> Self {
+        if !config.allow_io {
+            builtin.disable_io();
+        }
+        if !config.allow_exit {
+            builtin.disable("exit");
+        }
+        if !config.allow_time {
+            for name in TIME_BUILTINS {
+                builtin.disable(name);
+            }
+        }
         Self {
-            builtin: Builtin::new(),
+            builtin,
+            max_steps: config.max_steps,
+            steps: Cell::new(0),
+            max_size: config.max_size,
+            //seeded by the current time unless `seed()` is called
+            rng: Cell::new(Rng::new(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or_default(),
+            )),
+            exit_code: Cell::new(None),
+            initializing: RefCell::new(vec![]),
+        }
+    }
+
+    //resets the state of `random()` and alike so that the same sequence is produced for the same `seed`
+    pub fn seed(&self, seed: u64) {
+        self.rng.set(Rng::new(seed));
+    }
+
+    //calls `f` with the pseudo-random number generator shared by `random()` and alike
+    pub fn with_rng<T>(&self, f: impl FnOnce(&mut Rng) -> T) -> T {
+        let mut rng = self.rng.get();
+        let ret = f(&mut rng);
+        self.rng.set(rng);
+        ret
+    }
+
+    //called by `exit()` to stop the evaluation
+    //The returned error propagates like any other one (e.g. through `let`, arguments and the callbacks of `map()`), and
+    // `eval_root_node()` turns it into `Exit` so that the host can tell it from a failure.
+    pub fn exit(&self, code: i32) -> String {
+        self.exit_code.set(Some(code));
+        format!("exited with code {}", code)
+    }
+
+    pub fn builtin(&self) -> &Builtin {
+        &self.builtin
+    }
+
+    //fails if an array or a string of length `len` exceeds `EvaluatorConfig::max_size`
+    //A built-in function which may produce a large array or string from small arguments (e.g. `to_array(0..n)`) calls
+    // this before allocating it.
+    pub fn check_size(&self, len: usize) -> Result<(), String> {
+        match self.max_size {
+            Some(max_size) if (len > max_size) => Err("result too large".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    //same as `check_size()` but checks the result `o` after the fact
+    fn check_result_size(&self, o: Rc<dyn Object>) -> EvalResult {
+        if self.max_size.is_some() {
+            if let Some(a) = o.as_any().downcast_ref::<Array>() {
+                self.check_size(a.elements().len())?;
+            } else if let Some(s) = o.as_any().downcast_ref::<Str>() {
+                self.check_size(s.value().chars().count())?;
+            }
         }
+        Ok(o)
+    }
+
+    //for untrusted code (see `EvaluatorConfig::sandboxed()`)
+    pub fn sandboxed() -> Self {
+        Self::with_config(Builtin::new(), EvaluatorConfig::sandboxed())
     }
 
     pub fn eval(&self, node: &dyn Node, env: &mut Environment) -> EvalResult {
+        if let Some(max_steps) = self.max_steps {
+            let steps = self.steps.get() + 1;
+            if steps > max_steps {
+                return Err("execution limit exceeded".to_string());
+            }
+            self.steps.set(steps);
+        }
+
         if let Some(n) = node.as_any().downcast_ref::<RootNode>() {
             return self.eval_root_node(n, env);
         }
@@ -50,10 +195,18 @@ impl Evaluator {
             return self.eval_binary_expression_node(n, env);
         }
 
+        if let Some(n) = node.as_any().downcast_ref::<RangeExpressionNode>() {
+            return self.eval_range_expression_node(n, env);
+        }
+
         if let Some(n) = node.as_any().downcast_ref::<IndexExpressionNode>() {
             return self.eval_index_expression_node(n, env);
         }
 
+        if let Some(n) = node.as_any().downcast_ref::<FieldExpressionNode>() {
+            return self.eval_field_expression_node(n, env);
+        }
+
         if let Some(n) = node.as_any().downcast_ref::<CallExpressionNode>() {
             return self.eval_call_expression_node(n, env);
         }
@@ -62,10 +215,18 @@ impl Evaluator {
             return self.eval_if_expression_node(n, env);
         }
 
+        if let Some(n) = node.as_any().downcast_ref::<MatchExpressionNode>() {
+            return self.eval_match_expression_node(n, env);
+        }
+
         if let Some(n) = node.as_any().downcast_ref::<IntegerLiteralNode>() {
             return self.eval_integer_literal_node(n, env);
         }
 
+        if let Some(n) = node.as_any().downcast_ref::<BigIntegerLiteralNode>() {
+            return self.eval_big_integer_literal_node(n, env);
+        }
+
         if let Some(n) = node.as_any().downcast_ref::<FloatLiteralNode>() {
             return self.eval_float_literal_node(n, env);
         }
@@ -86,6 +247,10 @@ impl Evaluator {
             return self.eval_array_literal_node(n, env);
         }
 
+        if let Some(n) = node.as_any().downcast_ref::<HashLiteralNode>() {
+            return self.eval_hash_literal_node(n, env);
+        }
+
         if let Some(n) = node.as_any().downcast_ref::<FunctionLiteralNode>() {
             return self.eval_function_literal_node(n, env);
         }
@@ -98,9 +263,19 @@ impl Evaluator {
     }
 
     fn eval_root_node(&self, n: &RootNode, env: &mut Environment) -> EvalResult {
+        //discards the code left by `exit()` called directly by the host via `call_function()`
+        self.exit_code.set(None);
         let mut ret = Rc::new(Null::new()) as _;
         for statement in n.statements() {
-            ret = self.eval(statement.as_node(), env)?;
+            ret = match self.eval(statement.as_node(), env) {
+                Ok(o) => o,
+                Err(e) => {
+                    return match self.exit_code.take() {
+                        Some(code) => Ok(Rc::new(Exit::new(code))),
+                        None => Err(e),
+                    }
+                }
+            };
             //early return at the first `return` statement
             //Note the returned value is the content of `ReturnValue`; not the `ReturnValue` itself.
             if let Some(e) = ret.as_any().downcast_ref::<ReturnValue>() {
@@ -126,9 +301,16 @@ impl Evaluator {
     // }
     fn eval_block_expression_node(&self, n: &BlockExpressionNode, env: &Environment) -> EvalResult {
         let mut block_env = Environment::new(Some(Rc::new(env.clone())));
+        self.eval_block_in(n, &mut block_env)
+    }
+
+    //same as `eval_block_expression_node()` but doesn't create a new scope
+    //This is used for a function body so that the parameters and the body share one scope (see
+    // `Environment::set_parameter()` for a `let` which shadows a parameter).
+    fn eval_block_in(&self, n: &BlockExpressionNode, env: &mut Environment) -> EvalResult {
         let mut ret = Rc::new(Null::new()) as _;
         for statement in n.statements() {
-            ret = self.eval(statement.as_node(), &mut block_env)?;
+            ret = self.eval(statement.as_node(), env)?;
             if ret.as_any().downcast_ref::<ReturnValue>().is_some() {
                 break;
             }
@@ -137,18 +319,27 @@ impl Evaluator {
     }
 
     fn eval_let_statement_node(&self, n: &LetStatementNode, env: &mut Environment) -> EvalResult {
-        if self
-            .builtin
-            .lookup_builtin_identifier(n.identifier().get_name())
-            .is_some()
-        {
+        //A reserved built-in identifier can be shadowed only in an inner scope (e.g. a block or a function body) so that
+        // the top-level one is always available.
+        if env.is_root() && self.builtin.is_reserved(n.identifier().get_name()) {
             return Err(format!(
                 "`{}` is a built-in identifier",
                 n.identifier().get_name(),
             ));
         }
-        let o = self.eval(n.expression().as_node(), env)?;
-        env.try_set(n.identifier().get_name(), o)?;
+        let name = n.identifier().get_name();
+        //Without this, `let a = a;` fails with "`a` is not defined", which is confusing (see `eval_identifier_node()`).
+        self.initializing.borrow_mut().push(name.to_string());
+        let o = self.eval(n.expression().as_node(), env);
+        self.initializing.borrow_mut().pop();
+        let o = o?;
+        //The annotation is one of `TYPE_NAMES`, which is checked by the parser.
+        if let Some(t) = n.annotation() {
+            if !has_type_name(o.as_ref(), t) {
+                return Err(format!("expected {}, got {}", t, o.type_name()));
+            }
+        }
+        env.try_set(name, o)?;
         Ok(Rc::new(Null::new()))
     }
 
@@ -191,7 +382,23 @@ impl Evaluator {
     ) -> EvalResult {
         let left = self.eval(n.left().as_node(), env)?;
         let right = self.eval(n.right().as_node(), env)?;
-        match n.operator() {
+        //A hash as the left operand can overload the operator by a function at the special key (e.g. `__add__` for
+        // `+`), which is called with the right operand.
+        if let Some(f) =
+            special_method_name(n.operator()).and_then(|name| special_method(left.as_ref(), name))
+        {
+            let result = self.call_function(f.as_ref(), vec![right], env)?;
+            return match n.operator() {
+                Token::Eq | Token::NotEq => match result.as_any().downcast_ref::<Bool>() {
+                    None => Err("`__eq__` returned a non-boolean value".to_string()),
+                    Some(b) => Ok(Rc::new(Bool::new(
+                        b.value() == (n.operator() == &Token::Eq),
+                    ))),
+                },
+                _ => Ok(result),
+            };
+        }
+        let result = match n.operator() {
             Token::Plus => operator::binary_plus(left.as_ref(), right.as_ref()),
             Token::Minus => operator::binary_minus(left.as_ref(), right.as_ref()),
             Token::Asterisk => operator::binary_asterisk(left.as_ref(), right.as_ref()),
@@ -206,7 +413,29 @@ impl Evaluator {
             Token::GtEq => operator::binary_gteq(left.as_ref(), right.as_ref()),
             Token::And => operator::binary_and(left.as_ref(), right.as_ref()),
             Token::Or => operator::binary_or(left.as_ref(), right.as_ref()),
+            Token::In => operator::binary_in(left.as_ref(), right.as_ref()),
             _ => unreachable!(),
+        };
+        self.check_result_size(result?)
+    }
+
+    fn eval_range_expression_node(
+        &self,
+        n: &RangeExpressionNode,
+        env: &mut Environment,
+    ) -> EvalResult {
+        let start = self.eval(n.start().as_node(), env)?;
+        let end = self.eval(n.end().as_node(), env)?;
+        match (
+            start.as_any().downcast_ref::<Int>(),
+            end.as_any().downcast_ref::<Int>(),
+        ) {
+            (Some(start), Some(end)) => Ok(Rc::new(Range::new(
+                start.value(),
+                end.value(),
+                n.inclusive(),
+            ))),
+            _ => Err("bounds of a range are not integers".to_string()),
         }
     }
 
@@ -215,47 +444,25 @@ impl Evaluator {
         n: &IndexExpressionNode,
         env: &mut Environment,
     ) -> EvalResult {
-        //Note an index expression is of the form
-        //- `<identifier>[<index>]`
-        //- `<array literal>[<index>]`
-        //- `<string literal>[<index>]`
-        //
-        //`loop { }` here is a loop hack (ref: |https://stackoverflow.com/a/66629605/8776746|)
-        #[allow(clippy::never_loop)]
-        let array: Rc<dyn Indexable> = loop {
-            if let Some(a) = n.array().as_any().downcast_ref::<ArrayLiteralNode>() {
-                let a = self.eval(a, env)?;
-                if let Some(a) = a.as_any().downcast_ref::<Array>() {
-                    break Rc::new(a.clone());
-                }
-                unreachable!();
-            };
-
-            if let Some(a) = n.array().as_any().downcast_ref::<StringLiteralNode>() {
-                let a = self.eval(a, env)?;
-                if let Some(a) = a.as_any().downcast_ref::<Str>() {
-                    break Rc::new(a.clone());
-                }
-                unreachable!();
-            };
-
-            if let Some(identifier) = n.array().as_any().downcast_ref::<IdentifierNode>() {
-                let a = self.eval_identifier_node(identifier, env)?;
-                if let Some(a) = a.as_any().downcast_ref::<Array>() {
-                    break Rc::new(a.clone());
-                }
-                if let Some(a) = a.as_any().downcast_ref::<Str>() {
-                    break Rc::new(a.clone());
-                }
-                return Err(format!(
-                    "`{}` is not an array nor a string",
-                    identifier.get_name()
-                ));
-            }
-
-            return Err(
-                "only identifier, array literal or string literal can be indexed".to_string(),
-            );
+        //Note the indexed expression can be any expression (e.g. `a[0][1]` and `f()[0]`) which evaluates to an array, a
+        // string or a hash.
+        let a = self.eval(n.array().as_node(), env)?;
+        let array: Rc<dyn Collection> = if let Some(a) = a.as_any().downcast_ref::<Array>() {
+            Rc::new(a.clone())
+        } else if let Some(a) = a.as_any().downcast_ref::<Str>() {
+            Rc::new(a.clone())
+        } else if let Some(h) = a.as_any().downcast_ref::<Hash>() {
+            return self.eval_hash_index(h, n, env);
+        } else if let Some(identifier) = n.array().as_any().downcast_ref::<IdentifierNode>() {
+            return Err(format!(
+                "`{}` is not an array, a string nor a hash",
+                identifier.get_name()
+            ));
+        } else {
+            return Err(format!(
+                "`{}` is not an array, a string nor a hash",
+                a.type_name()
+            ));
         };
 
         let index = self.eval(n.index().as_node(), env)?;
@@ -266,7 +473,7 @@ impl Evaluator {
         if index.value() < 0 {
             return Err("negative array index not allowed".to_string());
         }
-        if (index.value() as usize) >= array.len() {
+        if (index.value() as usize) >= array.len()? {
             return Err("array index out of bounds".to_string());
         }
 
@@ -282,56 +489,93 @@ impl Evaluator {
         unreachable!();
     }
 
+    //`p.x` is the same as `p["x"]` except for the error messages
+    fn eval_field_expression_node(
+        &self,
+        n: &FieldExpressionNode,
+        env: &mut Environment,
+    ) -> EvalResult {
+        let record = self.eval(n.record().as_node(), env)?;
+        let h = match record.as_any().downcast_ref::<Hash>() {
+            Some(h) => h,
+            None => {
+                return Err(match n.record().as_any().downcast_ref::<IdentifierNode>() {
+                    Some(identifier) => format!("`{}` is not a hash", identifier.get_name()),
+                    None => format!("`{}` is not a hash", record.type_name()),
+                })
+            }
+        };
+        let field = n.field().get_name();
+        match h.get(&Str::new(Rc::new(field.to_string())))? {
+            Some(v) => Ok(v.clone()),
+            None => Err(format!("no field `{}`", field)),
+        }
+    }
+
     fn eval_call_expression_node(
         &self,
         n: &CallExpressionNode,
         env: &mut Environment,
     ) -> EvalResult {
-        //Note a function call is of the form `<identifier>(<arg(s)>)` or `<function literal>(<arg(s)>)`.
-        //`loop { }` here is a loop hack (ref: |https://stackoverflow.com/a/66629605/8776746|)
-        #[allow(clippy::never_loop)]
-        let function: Rc<dyn FunctionBase> = loop {
-            if let Some(f) = n.function().as_any().downcast_ref::<FunctionLiteralNode>() {
-                let f = self.eval(f, env)?;
-                if let Some(f) = f.as_any().downcast_ref::<Function>() {
-                    break Rc::new(f.clone());
-                }
-                unreachable!();
-            };
-
+        //Note the callee can be any expression (e.g. `f()()` and `m["k"]()`) which evaluates to a function.
+        let function = self.eval(n.function().as_node(), env)?;
+        if as_function(function.as_ref()).is_none() {
             if let Some(identifier) = n.function().as_any().downcast_ref::<IdentifierNode>() {
-                let f = self.eval_identifier_node(identifier, env)?;
-                if let Some(f) = f.as_any().downcast_ref::<Function>() {
-                    break Rc::new(f.clone());
-                }
-                if let Some(f) = f.as_any().downcast_ref::<BuiltinFunction>() {
-                    break Rc::new(f.clone());
-                }
                 return Err(format!("`{}` is not a function", identifier.get_name()));
             }
+            return Err(format!("`{}` is not a function", function.type_name()));
+        }
 
-            return Err("only identifier or function literal can be called".to_string());
-        };
+        if !as_function(function.as_ref())
+            .unwrap()
+            .accepts(n.arguments().len())
+        {
+            return Err("argument number mismatch".to_string());
+        }
+
+        let mut arguments = Vec::with_capacity(n.arguments().len());
+        for argument in n.arguments() {
+            arguments.push(self.eval(argument.as_node(), env)?);
+        }
+
+        self.call_function(function.as_ref(), arguments, env)
+    }
 
-        if n.arguments().len() != function.num_parameter() {
+    //calls `function` (`Function` or `BuiltinFunction`) with the already evaluated `arguments`
+    //This is also used by built-in functions which take a function as an argument.
+    pub fn call_function(
+        &self,
+        function: &dyn Object,
+        arguments: Vec<Rc<dyn Object>>,
+        env: &Environment,
+    ) -> EvalResult {
+        let f = match as_function(function) {
+            None => return Err(format!("`{}` is not a function", function.type_name())),
+            Some(f) => f,
+        };
+        if !f.accepts(arguments.len()) {
             return Err("argument number mismatch".to_string());
         }
 
         //constructs the following nested environment
         // { //outer
         //     { //function capture
-        //         { //arguments
+        //         { //arguments and the body
         //         }
         //     }
         // }
         let mut function_env = Environment::new(None);
 
-        let parameters = function.parameters();
-        for (i, param) in parameters.iter().enumerate() {
-            function_env.set(
-                param.get_name(),
-                self.eval(n.arguments()[i].as_node(), env)?,
-            )
+        let mut arguments = arguments.into_iter();
+        for (i, param) in f.parameters().iter().enumerate() {
+            if f.is_variadic() && (i + 1 == f.num_parameter()) {
+                function_env.set_parameter(
+                    param.get_name(),
+                    Rc::new(Array::new(arguments.by_ref().collect())),
+                );
+            } else {
+                function_env.set_parameter(param.get_name(), arguments.next().unwrap());
+            }
         }
 
         if let Some(function) = function.as_any().downcast_ref::<Function>() {
@@ -339,7 +583,7 @@ impl Evaluator {
             e.set_outer(Some(Rc::new(env.clone())));
             function_env.set_outer(Some(Rc::new(e)));
 
-            let result = self.eval_block_expression_node(function.body(), &function_env)?;
+            let result = self.eval_block_in(function.body(), &mut function_env)?;
 
             //Extracts the value of `ReturnValue` as in `eval_root_node()`.
             //Without this, `let f = fn() { return 3; 4 }; let a = f(); f(); return 100;` returns `3` (not `100`).
@@ -351,7 +595,7 @@ impl Evaluator {
         }
         if let Some(function) = function.as_any().downcast_ref::<BuiltinFunction>() {
             function_env.set_outer(Some(Rc::new(env.clone())));
-            return function.call(&function_env);
+            return self.check_result_size(function.call(self, &function_env)?);
         }
 
         unreachable!();
@@ -373,10 +617,41 @@ impl Evaluator {
         }
     }
 
+    //evaluates the first arm whose pattern matches, or returns `null` if none matches
+    fn eval_match_expression_node(
+        &self,
+        n: &MatchExpressionNode,
+        env: &mut Environment,
+    ) -> EvalResult {
+        let scrutinee = self.eval(n.scrutinee().as_node(), env)?;
+        for arm in n.arms() {
+            let is_matched = match arm.pattern() {
+                MatchPattern::Wildcard => true,
+                MatchPattern::Type(s) => has_type_name(scrutinee.as_ref(), s),
+                MatchPattern::Value(e) => {
+                    let value = self.eval(e.as_node(), env)?;
+                    operator::is_equal(scrutinee.as_ref(), value.as_ref())
+                }
+            };
+            if is_matched {
+                return self.eval(arm.value().as_node(), env);
+            }
+        }
+        Ok(Rc::new(Null::new()))
+    }
+
     fn eval_integer_literal_node(&self, n: &IntegerLiteralNode, _env: &Environment) -> EvalResult {
         Ok(Rc::new(Int::new(n.get_value())))
     }
 
+    fn eval_big_integer_literal_node(
+        &self,
+        n: &BigIntegerLiteralNode,
+        _env: &Environment,
+    ) -> EvalResult {
+        Ok(new_integer(BigNum::parse(n.get_value()).unwrap()))
+    }
+
     fn eval_float_literal_node(&self, n: &FloatLiteralNode, _env: &Environment) -> EvalResult {
         Ok(Rc::new(Float::new(n.get_value())))
     }
@@ -397,6 +672,25 @@ impl Evaluator {
         Ok(Rc::new(Str::new(Rc::new(n.get_value().to_string()))))
     }
 
+    //`h[<key>]`, which is an error if the key doesn't exist
+    fn eval_hash_index(
+        &self,
+        h: &Hash,
+        n: &IndexExpressionNode,
+        env: &mut Environment,
+    ) -> EvalResult {
+        let key = self.eval(n.index().as_node(), env)?;
+        match h.get(key.as_ref())? {
+            Some(v) => Ok(v.clone()),
+            //`__index__` is consulted only for a missing key so that the ordinary keys (and `__index__` itself) are
+            // still accessible
+            None => match special_method(h, "__index__") {
+                Some(f) => self.call_function(f.as_ref(), vec![key], env),
+                None => Err(format!("key {} not found in hash", repr(key.as_ref()))),
+            },
+        }
+    }
+
     fn eval_array_literal_node(&self, n: &ArrayLiteralNode, env: &mut Environment) -> EvalResult {
         let mut v = vec![];
         for e in n.elements() {
@@ -405,6 +699,17 @@ impl Evaluator {
         Ok(Rc::new(Array::new(v)))
     }
 
+    //When the same key appears more than once, the last value wins (while the position is that of the first one).
+    fn eval_hash_literal_node(&self, n: &HashLiteralNode, env: &mut Environment) -> EvalResult {
+        let mut h = Hash::new();
+        for (k, v) in n.pairs() {
+            let k = self.eval(k.as_node(), env)?;
+            let v = self.eval(v.as_node(), env)?;
+            h.insert(k, v)?;
+        }
+        Ok(Rc::new(h))
+    }
+
     fn eval_function_literal_node(
         &self,
         n: &FunctionLiteralNode,
@@ -417,20 +722,50 @@ impl Evaluator {
         )))
     }
 
+    //A variable takes precedence over the built-in identifier of the same name (see `eval_let_statement_node()`).
     fn eval_identifier_node(&self, n: &IdentifierNode, env: &Environment) -> EvalResult {
-        if let Some(e) = self.builtin.lookup_builtin_identifier(n.get_name()) {
-            return Ok(e);
+        if let Some(e) = env.get(n.get_name()) {
+            return Ok(e.clone());
         }
-        match env.get(n.get_name()) {
+        match self.builtin.lookup_builtin_identifier(n.get_name()) {
+            //Only the evaluation (not the mere appearance) of the variable in its initializer is an error, so e.g.
+            // `let a = if (false) { a } else { 1 };` is fine.
+            None if self.initializing.borrow().iter().any(|s| s == n.get_name()) => Err(format!(
+                "cannot use `{}` in its own initializer",
+                n.get_name()
+            )),
             None => Err(format!("`{}` is not defined", n.get_name())),
-            Some(e) => Ok(e.clone()),
+            Some(e) => Ok(e),
         }
     }
 }
 
+//the special key of a hash which overloads the binary `operator` (see `Evaluator::eval_binary_expression_node()`)
+//`!=` is the negation of `__eq__`.
+fn special_method_name(operator: &Token) -> Option<&'static str> {
+    match operator {
+        Token::Plus => Some("__add__"),
+        Token::Minus => Some("__sub__"),
+        Token::Asterisk => Some("__mul__"),
+        Token::Slash => Some("__div__"),
+        Token::Percent => Some("__mod__"),
+        Token::Eq | Token::NotEq => Some("__eq__"),
+        _ => None,
+    }
+}
+
+//returns the function at the special key `name` of `o` if `o` is a hash which has one
+fn special_method(o: &dyn Object, name: &str) -> Option<Rc<dyn Object>> {
+    let h = o.as_any().downcast_ref::<Hash>()?;
+    let f = h.get(&Str::new(Rc::new(name.to_string()))).ok()??;
+    as_function(f.as_ref()).map(|_| f.clone())
+}
+
 #[cfg(test)]
+#[allow(clippy::approx_constant)]
 mod tests {
 
+    use std::cell::RefCell;
     use std::rc::Rc;
 
     use super::super::environment::Environment;
@@ -440,7 +775,7 @@ mod tests {
     use super::super::token::Token;
     use super::*;
 
-    fn __eval(s: &str) -> EvalResult {
+    fn parse(s: &str) -> RootNode {
         let mut lexer = Lexer::new(s);
         let mut v = Vec::new();
         loop {
@@ -453,9 +788,20 @@ mod tests {
         v.push(Token::Eof);
         let root = Parser::new(v).parse();
         assert!(root.is_ok());
-        let mut env = Environment::new(None);
-        let evaluator = Evaluator::new();
-        evaluator.eval(&root.unwrap(), &mut env)
+        root.unwrap()
+    }
+
+    fn __eval_in(s: &str, env: &mut Environment) -> EvalResult {
+        Evaluator::new().eval(&parse(s), env)
+    }
+
+    fn __eval(s: &str) -> EvalResult {
+        __eval_in(s, &mut Environment::new(None))
+    }
+
+    //the output and the result of `s` as shown in a playground
+    fn run(s: &str) -> String {
+        crate::run(s).to_string()
     }
 
     fn read_and_eval(s: &str) -> Rc<dyn Object> {
@@ -516,16 +862,16 @@ mod tests {
         assert_eq!(v, o.unwrap().value());
     }
 
-    fn assert_array(s: &str, v: &Vec<i64>) {
+    fn assert_array(s: &str, v: &[i64]) {
         let o = read_and_eval(s);
         let o = o.as_any().downcast_ref::<Array>();
         assert!(o.is_some());
         let o = o.unwrap();
         assert_eq!(v.len(), o.elements().len());
-        for i in 0..v.len() {
-            let e = o.elements()[i].as_any().downcast_ref::<Int>();
+        for (e, v) in o.elements().iter().zip(v) {
+            let e = e.as_any().downcast_ref::<Int>();
             assert!(e.is_some());
-            assert_eq!(e.unwrap().value(), v[i]);
+            assert_eq!(e.unwrap().value(), *v);
         }
     }
 
@@ -566,8 +912,8 @@ mod tests {
         assert_float(r#" 3.14 * 2.0 "#, 6.28);
         assert_float(r#" 3.14 / 2.0 "#, 1.57);
         assert_string(r#" "hello" + "world" "#, "helloworld");
-        assert_array(r#" [1, 2] + [] "#, &vec![1, 2]);
-        assert_array(r#" [1, 2] + [3] "#, &vec![1, 2, 3]);
+        assert_array(r#" [1, 2] + [] "#, &[1, 2]);
+        assert_array(r#" [1, 2] + [3] "#, &[1, 2, 3]);
 
         //binary == != < >
         assert_boolean(r#" true == false "#, false);
@@ -795,7 +1141,7 @@ mod tests {
         assert_error(r#" let f = 3; f(3) "#, "not a function");
         assert_error(r#" g(3) "#, "not defined");
         assert_error(r#" let f = fn(x) { x; }; f(5, 10) "#, "number mismatch");
-        assert_error(r#" 1(3) "#, "can be called");
+        assert_error(r#" 1(3) "#, "`int` is not a function");
     }
 
     #[test]
@@ -806,12 +1152,22 @@ mod tests {
         assert_integer(r#" len("あいう") "#, 3);
         assert_integer(r#" len([]) "#, 0);
         assert_integer(r#" len([1, 2,]) "#, 2);
-
-        assert_array(r#" let a = [1, 2]; let b = append(a, 3); a "#, &vec![1, 2]);
-        assert_array(
-            r#" let a = [1, 2]; let b = append(a, 3); b "#,
-            &vec![1, 2, 3],
+        assert_integer(r#" len({:}) "#, 0);
+        assert_integer(r#" len({"a": 1, "b": 2, "a": 3}) "#, 2);
+        assert_error(r#" len(5) "#, "object of type int has no length");
+        assert_error(r#" len(fn() {}) "#, "object of type function has no length");
+
+        assert_array(r#" let a = [1, 2]; let b = append(a, 3); a "#, &[1, 2]);
+        assert_array(r#" let a = [1, 2]; let b = append(a, 3); b "#, &[1, 2, 3]);
+        assert_string(r#" append("ab", 'c') "#, "abc");
+        assert_string(r#" append("", 'あ') "#, "あ");
+        assert_string(
+            r#" let a = "ab"; let b = append(a, "cd"); a + b "#,
+            "ababcd",
         );
+        assert_boolean(r#" append("ab", 'c') == "abc" "#, true);
+        assert_error(r#" append("ab", 1) "#, "argument type mismatch");
+        assert_error(r#" append('a', 'b') "#, "argument type mismatch");
 
         assert_boolean(r#" bool("") "#, false);
         assert_boolean(r#" bool("hello") "#, true);
@@ -834,15 +1190,15 @@ mod tests {
 
     #[test]
     fn test09() {
-        assert_array(r#" [] "#, &vec![]);
-        assert_array(r#" [1] "#, &vec![1]);
-        assert_array(r#" [1, 2 * 3] "#, &vec![1, 6]);
+        assert_array(r#" [] "#, &[]);
+        assert_array(r#" [1] "#, &[1]);
+        assert_array(r#" [1, 2 * 3] "#, &[1, 6]);
         assert_character(r#"let a = ['a', 'b', 'c']; a[0]"#, 'a');
         assert_error(r#" b[0] "#, "not defined");
         assert_error(r#" let b = 3; b[0] "#, "not an array");
         assert_error(
             r#" 3.14[0] "#,
-            "only identifier, array literal or string literal can be indexed",
+            "`float` is not an array, a string nor a hash",
         );
         assert_character(r#" ['a', 'b', 'c'][0] "#, 'a');
         assert_error(r#" [][3.14] "#, "non-integer");
@@ -852,4 +1208,2441 @@ mod tests {
         assert_character(r#" let a = "abc"; a[0] "#, 'a');
         assert_character(r#" "あいうえお"[1] "#, 'い');
     }
+
+    #[test]
+    fn test10() {
+        let o = read_and_eval(r#" [[1, 2], [3], []] "#);
+        let v: Vec<Vec<i64>> = o.as_ref().try_into().unwrap();
+        assert_eq!(vec![vec![1, 2], vec![3], vec![]], v);
+
+        let o = read_and_eval(r#" ["a", 'b', 3.14] "#);
+        let v: Vec<Rc<dyn Object>> = o.as_ref().try_into().unwrap();
+        assert_eq!(3, v.len());
+        assert_eq!(Ok("a".to_string()), String::try_from(v[0].as_ref()));
+        assert_eq!(Ok('b'), char::try_from(v[1].as_ref()));
+        assert_eq!(Ok(3.14), f64::try_from(v[2].as_ref()));
+        assert_eq!(
+            Err(ConversionError::new("int", "string")),
+            i64::try_from(v[0].as_ref())
+        );
+        assert_eq!(
+            "expected int, got string",
+            Vec::<i64>::try_from(o.as_ref()).unwrap_err().to_string()
+        );
+
+        assert_eq!(
+            Ok(true),
+            bool::try_from(read_and_eval(r#" 1 < 2 "#).as_ref())
+        );
+        assert_eq!(
+            Err(ConversionError::new("array", "null")),
+            Vec::<i64>::try_from(read_and_eval(r#" return; "#).as_ref())
+        );
+    }
+
+    #[test]
+    fn test11() {
+        assert_boolean(r#" 3 in [1, 2, 3] "#, true);
+        assert_boolean(r#" 4 in [1, 2, 3] "#, false);
+        assert_boolean(r#" 1 in [] "#, false);
+        assert_boolean(r#" "a" in [1, "a"] "#, true);
+        assert_boolean(r#" 1 + 2 in [3] "#, true);
+        assert_boolean(r#" "ell" in "hello" "#, true);
+        assert_boolean(r#" "" in "hello" "#, true);
+        assert_boolean(r#" "elo" in "hello" "#, false);
+        assert_boolean(r#" 'い' in "あいう" "#, true);
+        assert_boolean(r#" 'x' in "あいう" "#, false);
+        assert_boolean(r#" 1 in [1] && !(2 in [1]) "#, true);
+        assert_error(r#" 1 in 1 "#, "unsupported operand type for binary `in`");
+        assert_error(r#" 1 in "1" "#, "unsupported operand type for binary `in`");
+    }
+
+    #[test]
+    fn test12() {
+        let mut env = Environment::new(None);
+        env.define("port", 8080);
+        env.define("ratio", 0.5);
+        env.define("verbose", true);
+        env.define("separator", ',');
+        env.define("name", "monkey");
+        env.define(
+            "names",
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        );
+        env.define("matrix", vec![vec![1, 2], vec![3]]);
+        env.define("missing", None::<i64>);
+        env.define("present", Some(1));
+
+        let o = __eval_in(
+            r#"
+                let join = fn(l, i) {
+                    if (i == len(l)) {
+                        return "";
+                    }
+                    if (i == 0) {
+                        return l[i] + join(l, i + 1);
+                    }
+                    str(separator) + l[i] + join(l, i + 1)
+                };
+                join(names, 0)
+            "#,
+            &mut env,
+        )
+        .unwrap();
+        assert_eq!(Ok("a,b,c".to_string()), String::try_from(o.as_ref()));
+
+        let o = __eval_in(
+            r#" [port + 1, ratio * 2.0, !verbose, name, len(matrix) + present] "#,
+            &mut env,
+        )
+        .unwrap();
+        assert_eq!("[8081, 1, false, monkey, 3]", o.to_string());
+
+        let o = __eval_in(r#" missing "#, &mut env).unwrap();
+        assert!(o.as_any().downcast_ref::<Null>().is_some());
+    }
+
+    #[test]
+    fn test13() {
+        assert_array(r#" to_array(1..5) "#, &[1, 2, 3, 4]);
+        assert_array(r#" to_array(1..=5) "#, &[1, 2, 3, 4, 5]);
+        assert_array(r#" to_array(5..1) "#, &[]);
+        assert_array(r#" to_array(-1..=-1) "#, &[-1]);
+        assert_array(r#" let n = 2; to_array(n * 2..n * 4) "#, &[4, 5, 6, 7]);
+        assert_boolean(r#" let r = 0..=10; 10 in r "#, true);
+        assert_boolean(r#" 3 in 1..5 "#, true);
+        assert_boolean(r#" 5 in 1..5 "#, false);
+        assert_boolean(r#" 5 in 1..=5 "#, true);
+        assert_error(r#" 1.0..2 "#, "bounds of a range are not integers");
+        assert_error(r#" to_array(1) "#, "argument type mismatch");
+
+        let o = read_and_eval(r#" 0..1000000000000 "#);
+        let r = o.as_any().downcast_ref::<Range>().unwrap();
+        assert_eq!(Ok(1000000000000), r.len());
+        assert_eq!(
+            Err("length overflow".to_string()),
+            Range::new(i64::MIN, i64::MAX, true).len()
+        );
+        assert_eq!(Ok(0), Range::new(1, 1, false).len());
+        assert_eq!(vec![0, 1, 2], r.iter().take(3).collect::<Vec<_>>());
+        assert_eq!("0..1000000000000", o.to_string());
+        assert_eq!("1..=2", read_and_eval(r#" 1..=2 "#).to_string());
+    }
+
+    #[test]
+    fn test14() {
+        assert_string(
+            r#" match 2 { 1 => "one", 2 => "two", _ => "many" } "#,
+            "two",
+        );
+        assert_string(
+            r#" match 5 { 1 => "one", 2 => "two", _ => "many" } "#,
+            "many",
+        );
+        assert_string(
+            r#" match 3.14 { int => "int", float => "float", } "#,
+            "float",
+        );
+        assert_string(
+            r#" match "a" { 'a' => "char", string => "string" } "#,
+            "string",
+        );
+        assert_integer(
+            r#" let x = 1; match x + 1 { (x) => 1, -2 => 2, 2 => 3 } "#,
+            3,
+        );
+        assert_integer(r#" let x = 2; match x { (x) => 1, _ => 2 } "#, 1);
+        assert_integer(r#" match 1 { _ => 1, 1 => 2 } "#, 1);
+        assert_null(r#" match 1 { 2 => 2 } "#);
+        assert_integer(
+            r#" let f = fn(x) { match x { 0 => { return 10; }, _ => 20 }; 30 }; f(0) + f(1) "#,
+            40,
+        );
+        assert_error(r#" match y { _ => 1 } "#, "not defined");
+        //`function` matches built-in functions too
+        assert_integer("match len { function => 1, _ => 2 }", 1);
+        assert_integer(
+            "let f = fn(x) { x }; match compose(f, f) { function => 1, _ => 2 }",
+            1,
+        );
+        assert_integer("match fn() {} { function => 1, _ => 2 }", 1);
+        assert_integer("match 1 { function => 1, _ => 2 }", 2);
+    }
+
+    #[test]
+    fn test15() {
+        let mut builtin = Builtin::new();
+        builtin.register("answer", Rc::new(Int::new(42)));
+        builtin.register_fn("zero", || 0);
+        builtin.register_fn("negate", |b: bool| !b);
+        builtin.register_fn("add", |a: i64, b: i64| a + b);
+        builtin.register_fn("repeat", |s: String, n: i64, sep: char| {
+            vec![s; n as usize].join(&sep.to_string())
+        });
+        builtin.register_fn("sum", |v: Vec<f64>| v.iter().sum::<f64>());
+        let evaluator = Evaluator::with_builtin(builtin);
+        let eval = |s: &str| evaluator.eval(&parse(s), &mut Environment::new(None));
+
+        assert_eq!("42", eval(r#" answer "#).unwrap().to_string());
+        assert_eq!("0", eval(r#" zero() "#).unwrap().to_string());
+        assert_eq!("false", eval(r#" negate(true) "#).unwrap().to_string());
+        assert_eq!("3", eval(r#" add(1, 2) "#).unwrap().to_string());
+        assert_eq!(
+            "ab-ab-ab",
+            eval(r#" repeat("ab", 3, '-') "#).unwrap().to_string()
+        );
+        assert_eq!("4.5", eval(r#" sum([1.5, 3.0]) "#).unwrap().to_string());
+        assert_eq!("2", eval(r#" len([1, 2]) "#).unwrap().to_string());
+
+        assert_eq!(
+            Err("argument 2 of `add`: expected int, got string".to_string()),
+            eval(r#" add(1, "2") "#).map(|o| o.to_string())
+        );
+        assert_eq!(
+            Err("argument 1 of `sum`: expected float, got int".to_string()),
+            eval(r#" sum([1]) "#).map(|o| o.to_string())
+        );
+        assert_eq!(
+            Err("argument number mismatch".to_string()),
+            eval(r#" add(1) "#).map(|o| o.to_string())
+        );
+        assert_eq!(
+            Err("`add` is a built-in identifier".to_string()),
+            eval(r#" let add = 1; "#).map(|o| o.to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test16() {
+        assert_string(
+            r#" json_stringify(json_parse("[1, -2.5, \"あ\\n\\\"\", true, null, [[], [3]]]")) "#,
+            r#"[1,-2.5,"あ\n\"",true,null,[[],[3]]]"#,
+        );
+        assert_string(r#" json_stringify(['c', "🐒"]) "#, r#"["c","🐒"]"#);
+        assert_integer(r#" let a = json_parse("[1, 2]"); a[1] "#, 2);
+        assert_float(r#" json_parse("1e3") "#, 1000.0);
+        assert_error(r#" json_parse("[1,") "#, "invalid JSON");
+        assert_string(
+            r#" json_stringify(json_parse("{\"b\": [1, {}], \"a\": null}")) "#,
+            r#"{"a":null,"b":[1,{}]}"#,
+        );
+        assert_error(
+            r#" json_stringify({1: 2}) "#,
+            "int key cannot be converted to JSON",
+        );
+        assert_error(
+            r#" json_stringify([len]) "#,
+            "built-in function cannot be converted",
+        );
+        assert_error(
+            r#" json_stringify((-1.0) ** 0.5) "#,
+            "`NaN` cannot be converted to JSON",
+        );
+        #[cfg(feature = "bignum")]
+        assert_error(
+            r#" json_stringify([2 ** 100]) "#,
+            "`1267650600228229401496703205376` is out of the range of JSON integers",
+        );
+
+        let v = serde_json::json!([1, 2.5, "x", [true, null]]);
+        let o = from_json(v.clone()).unwrap();
+        assert_eq!("[1, 2.5, x, [true, null]]", o.to_string());
+        assert_eq!(Ok(v), to_json(o.as_ref()));
+    }
+
+    #[test]
+    fn test17() {
+        //`exit()` is returned to the host instead of terminating the process
+        let o = read_and_eval("let f = fn() { exit(3); 100 }; f(); 200");
+        assert_eq!(3, o.as_any().downcast_ref::<Exit>().unwrap().code());
+        let o = read_and_eval("if (true) { if (true) { exit(1) } }; 200");
+        assert_eq!(1, o.as_any().downcast_ref::<Exit>().unwrap().code());
+        assert_error("exit('a')", "argument type mismatch");
+        let o = read_and_eval("exit(); 200");
+        assert_eq!(0, o.as_any().downcast_ref::<Exit>().unwrap().code());
+        assert_error("exit(1, 2)", "argument number mismatch");
+        assert_error("exit(4294967296)", "exit code `4294967296` out of range");
+        assert_error("exit(-2147483649)", "exit code `-2147483649` out of range");
+        let o = read_and_eval("exit(-2147483648)");
+        assert_eq!(i32::MIN, o.as_any().downcast_ref::<Exit>().unwrap().code());
+
+        //`exit()` stops the evaluation wherever it is called
+        let assert_exit = |s: &str, code: i32, stdout: &str| {
+            let o = crate::run(s);
+            assert_eq!(
+                (Some(code), None, None, stdout),
+                (o.exit_code, o.value, o.error, o.stdout.as_str()),
+                "{}",
+                s
+            );
+        };
+        assert_exit("let x = exit(3); print(1)", 3, "");
+        assert_exit("print(1); print(exit(5), 2); print(3)", 5, "1\n");
+        assert_exit("let f = fn(x) { print(x) }; f(exit(6)); f(1)", 6, "");
+        assert_exit("[1, exit(4), print(2)]", 4, "");
+        assert_exit("map([1, 2], fn(x) { exit(7) }); print(1)", 7, "");
+        assert_exit(
+            "take_while([1, 2], fn(x) { print(x); if (x == 1) { exit(8) } else { true } })",
+            8,
+            "1\n",
+        );
+        assert_exit("count([1, 2], fn(x) { exit(9) })", 9, "");
+        assert_exit(
+            "let f = fn() { let g = fn() { exit(2) }; let a = g(); print(a) }; f(); print(0)",
+            2,
+            "",
+        );
+        assert_exit("let f = fn() { return exit(1); }; f() + 1", 1, "");
+        assert_exit(
+            r#" let v = {"__add__": fn(other) { exit(10) }}; let w = v + 1; print(w) "#,
+            10,
+            "",
+        );
+        assert_exit("exit(11) + 1", 11, "");
+
+        //the caller can keep using the evaluator and the environment after `exit()`
+        let evaluator = Evaluator::new();
+        let mut env = Environment::new(None);
+        let o = evaluator
+            .eval(&parse("let a = 1; exit(4); let b = 2;"), &mut env)
+            .unwrap();
+        assert_eq!(4, o.as_any().downcast_ref::<Exit>().unwrap().code());
+        assert_eq!(
+            "2",
+            evaluator
+                .eval(&parse("a + 1"), &mut env)
+                .unwrap()
+                .to_string()
+        );
+        assert!(env.get("b").is_none());
+
+        //output sink
+        let out = Rc::new(RefCell::new(Vec::new()));
+        let err = Rc::new(RefCell::new(Vec::new()));
+        let evaluator = Evaluator::with_builtin(Builtin::with_output(out.clone(), err.clone()));
+        let mut env = Environment::new(None);
+        evaluator
+            .eval(
+                &parse(r#"print("abc"); eprint(1); print([1, 'x'])"#),
+                &mut env,
+            )
+            .unwrap();
+        assert_eq!("abc\n[1, x]\n", String::from_utf8_lossy(&out.borrow()));
+        assert_eq!("1\n", String::from_utf8_lossy(&err.borrow()));
+
+        assert_eq!("hello\n3", run(r#"print("hello"); 1 + 2"#));
+        assert_eq!("1\nexited with code 2", run("print(1); exit(2); print(3)"));
+        assert_eq!("`x` is not defined", run("x"));
+    }
+
+    #[test]
+    fn test18() {
+        assert_integer("9223372036854775806 + 1", i64::MAX);
+        assert_integer("-9223372036854775807 - 1", i64::MIN);
+        assert_integer("(-2) ** 63", i64::MIN);
+        assert_error("4 ** 4294967296", "too large exponent");
+        assert_error("1 / 0", "zero division");
+        assert_integer("0 / 1", 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "bignum"))]
+    fn test19() {
+        assert_error("9223372036854775807 + 1", "integer overflow");
+        assert_error("-(-9223372036854775807 - 1)", "integer overflow");
+        assert_error("2 ** 64", "integer overflow");
+        assert_error("(-9223372036854775807 - 1) / -1", "integer overflow");
+        assert!(Lexer::new("9223372036854775808").tokenize().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "bignum")]
+    fn test19() {
+        let input = r#"
+            let factorial = fn(n) { if (n == 0) { return 1; } n * factorial(n - 1) };
+            factorial(41)
+        "#;
+        assert_eq!(
+            "33452526613163807108170062053440751665152000000000",
+            read_and_eval(input).to_string()
+        );
+        let input = r#"
+            let factorial = fn(n) { if (n == 0) { return 1; } n * factorial(n - 1) };
+            factorial(50)
+        "#;
+        assert_eq!(
+            "30414093201713378043612608166064768844377641568960512000000000000",
+            read_and_eval(input).to_string()
+        );
+
+        //big literals
+        assert_eq!(
+            "123456789012345678901234567890",
+            read_and_eval("123456789012345678901234567890").to_string()
+        );
+        assert_integer("-9223372036854775808", i64::MIN);
+        assert_integer("100000000000000000000 / 10000000000", 10000000000);
+        assert_integer("100000000000000000007 % 10", 7);
+        assert_integer("2 ** 64 - 2 ** 64", 0);
+        assert_eq!(
+            "-18446744073709551616",
+            read_and_eval("-(2 ** 64)").to_string()
+        );
+        assert_eq!(
+            "9223372036854775808",
+            read_and_eval("(-9223372036854775807 - 1) / -1").to_string()
+        );
+        assert_boolean("2 ** 64 > 1", true);
+        assert_boolean("2 ** 64 == 2 ** 64", true);
+        assert_boolean("-(2 ** 64) < -1", true);
+        assert_float("float(2 ** 64)", 18446744073709551616.0);
+        assert_error("2 ** 64 / 0", "zero division");
+        assert_boolean("match 2 ** 64 { int => true, _ => false }", true);
+        assert_error("2 ** 4294967295", "too large result");
+        assert_error("(-3) ** 300000", "too large result");
+        assert_integer("1 ** 4294967295 + (-1) ** 4294967295", 0);
+        assert_integer("len(str(10 ** 1000))", 1001);
+
+        //big integers as hash keys
+        assert_integer(
+            "let h = {2 ** 64: 1, 2 ** 63 - 1: 2}; h[2 ** 64] + h[9223372036854775807]",
+            3,
+        );
+        assert_boolean("2 ** 64 + 1 in {18446744073709551617: true}", true);
+    }
+
+    #[test]
+    fn test20() {
+        let out = Rc::new(RefCell::new(Vec::new()));
+        let evaluator = Evaluator::with_builtin(Builtin::with_output(out.clone(), out.clone()));
+        let mut env = Environment::new(None);
+        evaluator
+            .eval(&parse(r#"print_debug("a\tb"); print("a\tb")"#), &mut env)
+            .unwrap();
+        assert_eq!("\"a\\tb\"\na\tb\n", String::from_utf8_lossy(&out.borrow()));
+
+        assert_string(r#" repr("a\"'\\\n\0") "#, r#""a\"'\\\n\0""#);
+        assert_string(r#" repr('\'') "#, r#"'\''"#);
+        assert_string(r#" repr('"') "#, r#"'"'"#);
+        assert_string(
+            r#" repr([1, 'a', "b", [2.5, true]]) "#,
+            r#"[1, 'a', "b", [2.5, true]]"#,
+        );
+        assert_string(r#" repr(len) "#, "built-in function");
+    }
+
+    #[test]
+    fn test21() {
+        assert_boolean("[1, [2, 'a']] == [1, [2, 'a']]", true);
+        assert_boolean("[1, [2, 'a']] == [1, [2, 'b']]", false);
+        assert_boolean("[1, 2] != [1]", true);
+        assert_boolean("[] == []", true);
+        assert_error("[1] == ['a']", "unsupported operand type for binary `==`");
+        assert_error("[1] != ['a']", "unsupported operand type for binary `!=`");
+
+        assert_null("assert(1 < 2)");
+        assert_error("assert(1 > 2)", "assertion failed");
+        assert_error("assert(1)", "argument type mismatch");
+
+        assert_null("assert_eq(3, 1 + 2)");
+        assert_null(r#" assert_eq([1, "a"], [1] + ["a"]) "#);
+        assert_error("assert_eq(3, 4)", "assertion failed: expected 3 but got 4");
+        assert_error(
+            r#" assert_eq("a\tb", ['a']) "#,
+            r#"assertion failed: expected "a\tb" but got ['a']"#,
+        );
+    }
+
+    #[test]
+    fn test22() {
+        //the body runs at most once
+        assert_eq!(
+            "run\n84",
+            run(r#"let t = lazy(fn() { print("run"); 42 }); force(t) + force(t)"#)
+        );
+        assert_eq!("", run(r#"let t = lazy(fn() { print("run"); 42 }); """#));
+
+        //the cached value is returned even if the captured environment differs
+        assert_integer(
+            "let make = fn(x) { lazy(fn() { x * 2 }) }; let t = make(5); let x = 100; [force(t), force(t)][1]",
+            10,
+        );
+        assert_integer(
+            "let g = fn(t) { force(t) + 1 }; let t = lazy(fn() { 1 }); g(t) + g(t)",
+            4,
+        );
+
+        assert_error("force(lazy(fn() { x }))", "`x` is not defined");
+        assert_error("lazy(fn(x) { x })", "argument type mismatch");
+        assert_error("lazy(1)", "argument type mismatch");
+        assert_error("force(1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test23() {
+        assert_eq!(
+            "{a: 1, b: 2.5, 3: true}",
+            run(r#" let h = {"a": 1, 'b': 2.5, 3: true}; h "#)
+        );
+        assert_string(
+            r#" repr({"a": ['b'], "c": {:}}) "#,
+            r#"{"a": ['b'], "c": {:}}"#,
+        );
+        assert_integer(r#" let h = {"a": 1, "b": 2}; h["b"] "#, 2);
+        assert_integer(r#" {true: 1}[1 == 1] "#, 1);
+        assert_integer(r#" let k = "x"; {k: 1, "y": 2}["x"] "#, 1);
+        assert_error(
+            r#" let h = {"a": 1}; h["b"] "#,
+            r#"key "b" not found in hash"#,
+        );
+        assert_error(
+            r#" {1.5: 1} "#,
+            "unhashable type `float` used as a hash key",
+        );
+        assert_error(
+            r#" let h = {:}; h[[1]] "#,
+            "unhashable type `array` used as a hash key",
+        );
+        assert_boolean(r#" "a" in {"a": 1} "#, true);
+        assert_boolean(r#" 'a' in {"a": 1} "#, false);
+
+        //a block is still a block
+        assert_integer("{ 1; 2 }", 2);
+        assert_integer("let a = 3; { a }", 3);
+
+        //iteration is in the insertion order (rather than the sorted order), which is stable across runs
+        assert_string(
+            r#" repr(keys({"b": 1, "a": 2, "c": 3})) "#,
+            r#"["b", "a", "c"]"#,
+        );
+        //overwriting keeps the position
+        assert_string(
+            r#" repr(keys({"z": 1, "a": 2, 10: 3, "m": 4, "a": 5})) "#,
+            r#"["z", "a", 10, "m"]"#,
+        );
+        assert_integer(r#" {"z": 1, "a": 2, "a": 5}["a"] "#, 5);
+        assert_error(
+            "keys([1])",
+            "argument 1 of `keys`: expected hash, got array",
+        );
+
+        //`values()` and `items()` follow the same order as `keys()`
+        let h = r#"let h = {"z": 1, "a": [2], 10: 3, "m": 4, "a": 5};"#;
+        assert_eq!("[1, 5, 3, 4]", run(&format!("{} values(h)", h)));
+        assert_eq!(
+            r#"[["z", 1], ["a", 5], [10, 3], ["m", 4]]"#,
+            run(&format!("{} repr(items(h))", h))
+        );
+        assert_eq!("[]", run("values({:})"));
+        assert_eq!("[]", run("items({:})"));
+        //the returned arrays are new ones, which are independent of `h`
+        assert_eq!(
+            "[{a: 1, b: 2}, [a, b, c], [1, 2]]",
+            run(r#" let h = {"a": 1, "b": 2}; let k = keys(h) + ["c"]; [h, k, values(h)] "#)
+        );
+        assert_boolean(
+            r#" let h = {"a": [1]}; let v = values(h); v[0] == h["a"] "#,
+            true,
+        );
+        assert_error(
+            "values(1)",
+            "argument 1 of `values`: expected hash, got int",
+        );
+        assert_error(
+            r#" items("a") "#,
+            "argument 1 of `items`: expected hash, got string",
+        );
+        assert_error("items({:}, 1)", "argument number mismatch");
+
+        //equality ignores the order
+        assert_boolean(r#" {"a": 1, "b": [2]} == {"b": [2], "a": 1} "#, true);
+        assert_boolean(r#" {"a": 1, "b": 2} == {"a": 1, "b": 3} "#, false);
+        assert_boolean(r#" {"a": 1} == {"a": 1, "b": 2} "#, false);
+        assert_boolean(r#" {"a": 1} != {"b": 1} "#, true);
+        assert_boolean(r#" {:} == {:} "#, true);
+    }
+
+    #[test]
+    fn test24() {
+        assert_integer("first([1, 2, 3])", 1);
+        assert_integer("last([1, 2, 3])", 3);
+        assert_array("rest([1, 2, 3])", &[2, 3]);
+        assert_array("rest([1])", &[]);
+        assert_null("first([])");
+        assert_null("last([])");
+        assert_array("rest([])", &[]);
+
+        assert_character(r#" first("aあ") "#, 'a');
+        assert_character(r#" last("aあ") "#, 'あ');
+        assert_string(r#" rest("aあい") "#, "あい");
+        assert_null(r#" first("") "#);
+        assert_null(r#" last("") "#);
+        assert_string(r#" rest("") "#, "");
+
+        assert_error("first(1)", "argument type mismatch");
+        assert_error("last(1..3)", "argument type mismatch");
+        assert_error("rest('a')", "argument type mismatch");
+
+        //recursive list processing
+        assert_integer(
+            "let sum = fn(l) { if (len(l) == 0) { 0 } else { first(l) + sum(rest(l)) } }; sum([1, 2, 3, 4])",
+            10,
+        );
+    }
+
+    #[test]
+    fn test25() {
+        assert_string(
+            r#" repr(merge({"a": 1}, {"b": 2})) "#,
+            r#"{"a": 1, "b": 2}"#,
+        );
+        //the right one wins while the position of the key is kept
+        assert_string(
+            r#" repr(merge({"a": 1, "b": 2}, {"c": 3, "a": 4})) "#,
+            r#"{"a": 4, "b": 2, "c": 3}"#,
+        );
+        assert_string(
+            r#" let a = {"a": 1}; let b = a + {"a": [2], 3: 'c'}; repr([a, b]) "#,
+            r#"[{"a": 1}, {"a": [2], 3: 'c'}]"#,
+        );
+        assert_boolean(r#" {:} + {"a": 1} == {"a": 1} + {:} "#, true);
+        assert_error(r#" merge({:}, [1]) "#, "argument type mismatch");
+        assert_error(
+            r#" {:} + [1] "#,
+            "operand of binary `+` is not a number, a string, an array nor a hash",
+        );
+    }
+
+    #[test]
+    fn test26() {
+        assert_array("map([1, 2, 3], fn(x) { x * 2 })", &[2, 4, 6]);
+        assert_array("map([], fn(x) { x * 2 })", &[]);
+        assert_array("let n = 10; map([1, 2], fn(x) { x + n })", &[11, 12]);
+        assert_array(r#" map(["a", "bc"], len) "#, &[1, 2]);
+        assert_array("map(map([1, 2], fn(x) { [x] }), first)", &[1, 2]);
+
+        //an error in the callback propagates
+        assert_error("map([1, 0], fn(x) { 1 / x })", "zero division");
+        assert_error("map([1], fn(x, y) { x })", "argument number mismatch");
+
+        assert_error("map([1], 2)", "argument `f` of `map` is not a function");
+        assert_error("map(1, fn(x) { x })", "argument type mismatch");
+    }
+
+    #[test]
+    fn test27() {
+        assert_eq!("fn(x) {\n    x + 1\n}", run("let f = fn(x){x+1}; f"));
+        assert_eq!(
+            "fn(a, b) {\n    let c = a * (b + 1);\n    if (c > 0) {\n        c\n    }\n}",
+            run("fn(a,b){let c=a*(b+1); if(c>0){c}}")
+        );
+        assert_eq!("fn() {}", run("let make = fn(n) { fn() {} }; make(1)"));
+        assert_eq!("[fn() {\n    1\n}]", run("[fn() { 1 }]"));
+        assert_eq!("built-in function", run("len"));
+    }
+
+    #[test]
+    fn test28() {
+        assert_array("sort([3, 1, 2, 1])", &[1, 1, 2, 3]);
+        assert_array("sort([])", &[]);
+        assert_array("let a = [2, 1]; let b = sort(a); a + b", &[2, 1, 1, 2]);
+        assert_string("repr(sort([2.5, -1.0, 0.5]))", "[-1, 0.5, 2.5]");
+        assert_string("repr(sort(['c', 'a', 'b']))", "['a', 'b', 'c']");
+        assert_string(
+            r#" repr(sort(["banana", "apple", "cherry", "app"])) "#,
+            r#"["app", "apple", "banana", "cherry"]"#,
+        );
+
+        //stability: `0.0` and `-0.0` are equal but distinguishable
+        assert_string("repr(sort([0.0, -1.0, -0.0, 0.0]))", "[-1, 0, -0, 0]");
+
+        assert_error(r#" sort([1, "a"]) "#, "cannot compare string with int");
+        assert_error("sort([[1], [2]])", "cannot compare array with array");
+        assert_error("sort(1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test29() {
+        assert_array(
+            "sort_by([3, 1, 4, 1, 5], fn(a, b) { a > b })",
+            &[5, 4, 3, 1, 1],
+        );
+        assert_array("sort_by([], fn(a, b) { a < b })", &[]);
+
+        //records sorted by the second element (stably)
+        assert_string(
+            r#"
+            let records = [["c", 2], ["a", 3], ["b", 1], ["d", 2]];
+            repr(sort_by(records, fn(a, b) { a[1] < b[1] }))
+            "#,
+            r#"[["b", 1], ["c", 2], ["d", 2], ["a", 3]]"#,
+        );
+
+        assert_error(
+            "sort_by([1, 0], fn(a, b) { 1 / a < 1 / b })",
+            "zero division",
+        );
+        assert_error(
+            "sort_by([1, 2], fn(a, b) { a - b })",
+            "comparator of `sort_by` returned a non-boolean",
+        );
+        assert_error(
+            "sort_by([1, 2], 1)",
+            "argument `f` of `sort_by` is not a function",
+        );
+        assert_error("sort_by(1, fn(a, b) { a < b })", "argument type mismatch");
+    }
+
+    #[test]
+    fn test30() {
+        assert_boolean("round(3.14159, 2) == 3.14", true);
+        assert_float("round(2.5, 0)", 3.0);
+        assert_float("round(-2.5, 0)", -3.0);
+        assert_float("round(1250.0, -2)", 1300.0);
+        assert_float("round(1250.0, -400)", 0.0);
+        assert_float("round(0.1, 400)", 0.1);
+
+        assert_string("format_float(2.5, 0)", "3");
+        assert_string("format_float(2.5, 3)", "2.500");
+        assert_string("format_float(-3.14159, 2)", "-3.14");
+        assert_string("format_float(1250.0, -2)", "1300");
+
+        assert_error("round(3, 1)", "argument type mismatch");
+        assert_error("format_float(3.0, 1.0)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test31() {
+        assert_string(r#" repr(lines("a\nb\nc")) "#, r#"["a", "b", "c"]"#);
+        assert_string(r#" repr(lines("a\nb\n")) "#, r#"["a", "b"]"#);
+        assert_string(r#" repr(lines("a\r\nb\r\n")) "#, r#"["a", "b"]"#);
+        assert_string(r#" repr(lines("a\n\nb")) "#, r#"["a", "", "b"]"#);
+        assert_string(r#" repr(lines("\n")) "#, r#"[""]"#);
+        assert_string(r#" repr(lines("")) "#, "[]");
+        assert_error("lines(['a'])", "argument type mismatch");
+    }
+
+    #[test]
+    fn test32() {
+        assert_string(r#" repr(split("a,b,,c", ",")) "#, r#"["a", "b", "", "c"]"#);
+        assert_string(r#" repr(split("a,b", ',')) "#, r#"["a", "b"]"#);
+        assert_string(r#" repr(split("abc", "")) "#, r#"["a", "b", "c"]"#);
+        assert_string(r#" repr(split("", "")) "#, "[]");
+        assert_string(r#" repr(split("abc", "x")) "#, r#"["abc"]"#);
+        assert_string(r#" repr(split("", ",")) "#, r#"[""]"#);
+        assert_string(r#" repr(split(",a,", ",")) "#, r#"["", "a", ""]"#);
+        assert_string(r#" repr(split("a::b::c", "::")) "#, r#"["a", "b", "c"]"#);
+        assert_string(r#" repr(split("あ,い", ",")) "#, r#"["あ", "い"]"#);
+        assert_string(r#" repr(split("あい", "")) "#, r#"["あ", "い"]"#);
+        assert_error(r#" split(['a'], ",") "#, "argument type mismatch");
+        assert_error(r#" split("a", 1) "#, "argument type mismatch");
+    }
+
+    #[test]
+    fn test33() {
+        let path =
+            std::env::temp_dir().join(format!("monkey_lang_test33_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        assert_null(&format!(r#" write_file("{}", "あ\nb") "#, path));
+        assert_string(&format!(r#" read_file("{}") "#, path), "あ\nb");
+        assert_string(
+            &format!(
+                r#" write_file("{0}", "x"); read_file("{0}") + read_file("{0}") "#,
+                path
+            ),
+            "xx",
+        );
+        std::fs::remove_file(path).unwrap();
+
+        assert_error(&format!(r#" read_file("{}") "#, path), "failed to read");
+        assert_error(
+            r#" write_file("/nonexistent/a.txt", "") "#,
+            "failed to write",
+        );
+        assert_error("read_file(1)", "argument type mismatch");
+        assert_error(r#" write_file("a.txt", 1) "#, "argument type mismatch");
+
+        //capability
+        let mut builtin = Builtin::new();
+        builtin.disable_io();
+        let evaluator = Evaluator::with_builtin(builtin);
+        let mut env = Environment::new(None);
+        assert_eq!(
+            Err("`read_file` is disabled in this sandbox".to_string()),
+            evaluator
+                .eval(&parse(r#" read_file("a.txt") "#), &mut env)
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err("argument number mismatch".to_string()),
+            evaluator
+                .eval(&parse(r#" write_file("a.txt") "#), &mut env)
+                .map(|_| ())
+        );
+        assert!(evaluator.eval(&parse("len([1])"), &mut env).is_ok());
+    }
+
+    #[test]
+    fn test34() {
+        let evaluator = Evaluator::sandboxed();
+        let eval = |s: &str| {
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+        assert_eq!(
+            Err("`exit` is disabled in this sandbox".to_string()),
+            eval("exit(1)")
+        );
+        assert_eq!(
+            Err("`read_file` is disabled in this sandbox".to_string()),
+            eval(r#" read_file("a.txt") "#)
+        );
+        assert_eq!(
+            Err("`write_file` is disabled in this sandbox".to_string()),
+            eval(r#" let f = fn() { write_file("a.txt", "") }; f() "#)
+        );
+        assert_eq!(Ok("[1, 2, 3]".to_string()), eval("sort([3, 1, 2])"));
+        assert_eq!(Ok("3".to_string()), eval("len(\"abc\")"));
+
+        //each capability can be given separately
+        let config = EvaluatorConfig {
+            allow_exit: true,
+            ..EvaluatorConfig::sandboxed()
+        };
+        let evaluator = Evaluator::with_config(Builtin::new(), config);
+        let o = evaluator
+            .eval(&parse("exit(2)"), &mut Environment::new(None))
+            .unwrap();
+        assert_eq!(2, o.as_any().downcast_ref::<Exit>().unwrap().code());
+        assert_eq!(
+            Err("`read_file` is disabled in this sandbox".to_string()),
+            evaluator
+                .eval(&parse(r#" read_file("a") "#), &mut Environment::new(None))
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test35() {
+        assert_integer("index_of([1, 2, 3, 2], 2)", 1);
+        assert_integer("index_of([1, 2, 3], 4)", -1);
+        assert_integer("index_of([], 1)", -1);
+        assert_integer(r#" index_of([1, "a", [2]], [2]) "#, 2);
+        assert_integer(r#" index_of([1, "a"], "a") "#, 1);
+
+        assert_integer(r#" index_of("hello", "l") "#, 2);
+        assert_integer(r#" index_of("hello", 'o') "#, 4);
+        assert_integer(r#" index_of("hello", "") "#, 0);
+        assert_integer(r#" index_of("hello", "x") "#, -1);
+        //counted in characters rather than bytes
+        assert_integer(r#" index_of("あいう", "う") "#, 2);
+        assert_integer(r#" index_of("aあb", 'b') "#, 2);
+        assert_character(r#" let s = "あいう"; s[index_of(s, 'い')] "#, 'い');
+
+        assert_error(r#" index_of("abc", 1) "#, "argument type mismatch");
+        assert_error("index_of(1, 1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test36() {
+        let eval = |max_steps: u64, s: &str| {
+            let config = EvaluatorConfig {
+                max_steps: Some(max_steps),
+                ..EvaluatorConfig::default()
+            };
+            Evaluator::with_config(Builtin::new(), config)
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+
+        //This covers an unbounded recursion and an iteration in a built-in function.
+        //A runaway `while` loop is not tested as the language has no loop construct yet (see `memo.txt`).
+        assert_eq!(
+            Err("execution limit exceeded".to_string()),
+            eval(200, "let f = fn(n) { f(n + 1) }; f(0)")
+        );
+        //steps taken inside built-in functions are also counted
+        assert_eq!(
+            Err("execution limit exceeded".to_string()),
+            eval(100, "map(to_array(0..100), fn(x) { x })")
+        );
+        assert_eq!(Ok("3".to_string()), eval(1000, "1 + 2"));
+        assert_eq!(
+            Err("execution limit exceeded".to_string()),
+            eval(2, "1 + 2")
+        );
+
+        //the steps are counted over the lifetime of the evaluator
+        let config = EvaluatorConfig {
+            max_steps: Some(20),
+            ..EvaluatorConfig::default()
+        };
+        let evaluator = Evaluator::with_config(Builtin::new(), config);
+        let mut env = Environment::new(None);
+        let results: Vec<bool> = (0..10)
+            .map(|_| evaluator.eval(&parse("1 + 2"), &mut env).is_ok())
+            .collect();
+        assert!(results[0]);
+        assert!(!results[9]);
+    }
+
+    #[test]
+    fn test37() {
+        let output = crate::run(r#"print("hello"); eprint('x'); [1 + 2, 4]"#);
+        assert_eq!(Some("[3, 4]".to_string()), output.value);
+        assert_eq!(None, output.error);
+        assert_eq!(None, output.exit_code);
+        assert_eq!("hello\n", output.stdout);
+        assert_eq!("x\n", output.stderr);
+
+        let output = crate::run(r#"print("a"); 1 + "b""#);
+        assert_eq!(None, output.value);
+        assert!(output.error.is_some());
+        assert_eq!(None, output.error_position);
+        assert_eq!("a\n", output.stdout);
+
+        let output = crate::run("let");
+        assert_eq!(None, output.value);
+        assert!(output.error.is_some());
+        assert_eq!("", output.stdout);
+
+        //the position of a lexer or parser error
+        let position = |s: &str| crate::run(s).error_position.map(|p| (p.line, p.column));
+        assert_eq!(Some((2, 9)), position("let a = 1;\nlet b = ;"));
+        assert_eq!(Some((1, 10)), position("[1, 2] + \"abc"));
+        assert_eq!(Some((2, 7)), position("1;\n'あ' + )")); //counted in characters
+        assert_eq!(Some((2, 9)), position("let a = 1;\rlet b = ;"));
+        assert_eq!(Some((3, 9)), position("let a = 1;\r\n\rlet b = ;"));
+        assert_eq!(
+            "1:7: `=` missing in `let`",
+            crate::run("let a 1;").to_string()
+        );
+
+        let output = crate::run("print(1); exit(2); print(3)");
+        assert_eq!(None, output.value);
+        assert_eq!(None, output.error);
+        assert_eq!(Some(2), output.exit_code);
+        assert_eq!("1\n", output.stdout);
+    }
+
+    #[test]
+    fn test38() {
+        assert_boolean("is_digit('0')", true);
+        assert_boolean("is_digit('9')", true);
+        assert_boolean("is_digit('a')", false);
+        assert_boolean("is_digit('٣')", false); //non-ASCII digits are rejected
+        assert_boolean("is_alpha('a')", true);
+        assert_boolean("is_alpha('Z')", true);
+        assert_boolean("is_alpha('あ')", true);
+        assert_boolean("is_alpha('1')", false);
+        assert_boolean("is_alpha('_')", false);
+        assert_boolean("is_alnum('a')", true);
+        assert_boolean("is_alnum('1')", true);
+        assert_boolean("is_alnum('-')", false);
+        assert_boolean("is_space(' ')", true);
+        assert_boolean(r#" is_space('\t') "#, true);
+        assert_boolean(r#" is_space('\n') "#, true);
+        assert_boolean("is_space('　')", true); //ideographic space
+        assert_boolean("is_space('a')", false);
+        assert_boolean("is_upper('A')", true);
+        assert_boolean("is_upper('a')", false);
+        assert_boolean("is_upper('É')", true);
+        assert_boolean("is_lower('a')", true);
+        assert_boolean("is_lower('A')", false);
+        assert_boolean("is_lower('あ')", false);
+        assert_boolean(r#" let s = "Ab"; is_upper(s[0]) "#, true);
+
+        assert_error(
+            r#" is_digit("1") "#,
+            "argument 1 of `is_digit`: expected char, got string",
+        );
+        assert_error("is_alpha(1)", "expected char, got int");
+    }
+
+    #[test]
+    fn test39() {
+        assert_boolean("upper('a') == 'A'", true);
+        assert_boolean("lower('Z') == 'z'", true);
+        assert_character("upper('5')", '5');
+        assert_character("lower('5')", '5');
+        assert_character("upper('A')", 'A');
+        assert_character("upper('é')", 'É');
+        assert_character("lower('Σ')", 'σ');
+        assert_character("upper('あ')", 'あ');
+        //the upper case of `ß` is `SS`
+        assert_character("upper('ß')", 'ß');
+
+        assert_string(r#" upper("Hello, world!") "#, "HELLO, WORLD!");
+        assert_string(r#" lower("Hello, World!") "#, "hello, world!");
+        assert_string(r#" upper("straße") "#, "STRASSE");
+        assert_string(r#" upper("") "#, "");
+
+        assert_error("upper(1)", "argument type mismatch");
+        assert_error("lower([])", "argument type mismatch");
+    }
+
+    #[test]
+    fn test40() {
+        assert_eq!(
+            "{a: 1, c: 3}",
+            run(r#" delete({"a": 1, "b": 2, "c": 3}, "b") "#)
+        );
+        assert_eq!("{a: 1}", run(r#" delete({"a": 1}, "x") "#));
+        assert_eq!("{:}", run(r#" delete({"a": 1}, "a") "#));
+        assert_eq!("{:}", run(r#" delete({:}, 1) "#));
+
+        //the original hash is unchanged
+        assert_eq!(
+            "[{a: 1, b: 2}, {a: 1}]",
+            run(r#" let h = {"a": 1, "b": 2}; let g = delete(h, "b"); [h, g] "#)
+        );
+
+        //the remaining keys keep their order, and a re-inserted key goes to the end
+        assert_eq!(
+            "[1, 3, 4, 2]",
+            run("let h = delete({1: 1, 2: 2, 3: 3, 4: 4}, 2); keys(h + {2: 0})")
+        );
+        assert_eq!(
+            "[3, 1]",
+            run("let h = delete(delete({1: 1, 2: 2, 3: 3}, 1), 2); keys(h + {1: 0})")
+        );
+        assert_integer("let h = delete({1: 10, 2: 20, 3: 30}, 1); h[3]", 30);
+
+        assert_boolean(r#" has_key({"a": 1}, "a") "#, true);
+        assert_boolean(r#" has_key({"a": 1}, "b") "#, false);
+        assert_boolean(r#" has_key({"a": 1}, 'a') "#, false);
+        assert_boolean("has_key(delete({1: 1}, 1), 1)", false);
+
+        assert_error(
+            "delete({1: 1}, 1.5)",
+            "unhashable type `float` used as a hash key",
+        );
+        assert_error(
+            "has_key({1: 1}, [1])",
+            "unhashable type `array` used as a hash key",
+        );
+        assert_error("delete([1], 0)", "argument type mismatch");
+        assert_error("has_key(1, 1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test41() {
+        assert_boolean("let f = fn() {}; f == f", true);
+        assert_boolean("let f = fn() {}; f != f", false);
+        assert_boolean("let f = fn() {}; let g = f; f == g", true);
+        //not structural equality
+        assert_boolean("let f = fn() {}; let g = fn() {}; f == g", false);
+        assert_boolean("let f = fn() {}; let g = fn() {}; f != g", true);
+        assert_boolean("let make = fn() { fn(x) { x } }; make() == make()", false);
+        assert_boolean(
+            "let f = fn(x) { x }; let id = fn(x) { x }; id(f) == f",
+            true,
+        );
+        assert_boolean("let f = fn() {}; [1, f] == [1, f]", true);
+        assert_boolean("let f = fn() {}; {1: f} == {1: fn() {}}", false);
+
+        assert_boolean("len == len", true);
+        assert_boolean("let l = len; l == len", true);
+        assert_boolean("len != first", true);
+        assert_boolean("let f = fn(x) { len(x) }; f == len", false);
+        assert_integer("index_of([first, len, last], len)", 1);
+
+        assert_error(
+            "let f = fn() {}; f == 1",
+            "unsupported operand type for binary `==`",
+        );
+    }
+
+    #[test]
+    fn test42() {
+        assert_integer(r#" len("aあ") "#, 2);
+        assert_integer("len([1, [2, 3]])", 2);
+        assert_integer("len({1: 2, 3: 4})", 2);
+        assert_integer("len({:})", 0);
+        assert_integer("len(1..5)", 4);
+        assert_integer("len(1..=5)", 5);
+        assert_integer("len(5..1)", 0);
+        assert_integer("len(-5..5)", 10);
+        assert_integer("len(0..9223372036854775807)", i64::MAX);
+        assert_error("len(-1..9223372036854775807)", "length overflow");
+        assert_error(
+            "len((-9223372036854775807 - 1)..=9223372036854775807)",
+            "length overflow",
+        );
+
+        assert_error("len(1)", "object of type int has no length");
+        assert_error("len('a')", "object of type char has no length");
+        assert_error("len(fn() {})", "object of type function has no length");
+    }
+
+    #[test]
+    fn test43() {
+        assert_boolean(
+            "let add = fn(a, b) { a + b }; let add5 = partial(add, 5); add5(3) == 8",
+            true,
+        );
+        assert_integer(
+            "let f = fn(a, b, c) { a * 100 + b * 10 + c }; let g = partial(f, 1, 2); g(3)",
+            123,
+        );
+        assert_integer(
+            "let f = fn(a, b, c) { a * 100 + b * 10 + c }; let g = partial(f, 1); let h = partial(g, 2); h(3)",
+            123,
+        );
+        //all or none of the arguments can be bound
+        assert_integer(
+            "let f = fn(a, b) { a - b }; let g = partial(f, 5, 3); g()",
+            2,
+        );
+        assert_integer("let f = fn(a, b) { a - b }; let g = partial(f); g(5, 3)", 2);
+        //the bound arguments are evaluated only once
+        assert_eq!(
+            "x\n[2, 2]",
+            run(
+                r#" let one = fn() { print("x"); 1 }; let g = partial(fn(a, b) { a + b }, one()); [g(1), g(1)] "#
+            )
+        );
+        //built-in functions, including `partial()` itself, can be partially applied
+        assert_integer(r#" let f = partial(len, "abc"); f() "#, 3);
+        assert_integer(
+            "let p = partial(partial, fn(a, b) { a * b }); let double = p(2); double(21)",
+            42,
+        );
+        assert_integer("let f = partial(index_of, [3, 4, 5]); f(5)", 2);
+        assert_error(
+            "let f = partial(fn(a, b) { a + b }, 1); f(1, 2)",
+            "argument number mismatch",
+        );
+        assert_error(
+            "partial(fn(a) { a }, 1, 2)",
+            "too many arguments are given to `partial`",
+        );
+        assert_error(
+            "partial(1, 2)",
+            "argument `f` of `partial` is not a function",
+        );
+        assert_error("partial()", "argument number mismatch");
+    }
+
+    #[test]
+    fn test44() {
+        let defs = "let inc = fn(x) { x + 1 }; let double = fn(x) { x * 2 };";
+        assert_integer(&format!("{} let h = compose(inc, double); h(5)", defs), 11);
+        assert_integer(&format!("{} let h = compose(double, inc); h(5)", defs), 12);
+        assert_integer(
+            &format!("{} let h = compose(inc, compose(inc, double)); h(5)", defs),
+            12,
+        );
+        assert_eq!(
+            "[3, 5, 7]",
+            run(&format!("{} map([1, 2, 3], compose(inc, double))", defs))
+        );
+
+        //the composed function takes the same parameters as the inner one
+        assert_integer(
+            "let h = compose(fn(x) { -x }, fn(a, b) { a - b }); h(1, 3)",
+            2,
+        );
+        assert_integer(r#" let h = compose(len, rest); h("abc") "#, 2);
+        assert_integer(
+            r#" let h = compose(fn(f) { f() }, partial); h(len, "abc") "#,
+            3,
+        );
+
+        assert_error(
+            "let h = compose(fn(x) { x }, fn(x) { x }); h(1, 2)",
+            "argument number mismatch",
+        );
+        assert_error(
+            "let h = compose(fn(a, b) { a }, fn(x) { x }); h(1)",
+            "argument number mismatch",
+        );
+        assert_error(
+            "compose(1, fn(x) { x })",
+            "argument `f` of `compose` is not a function",
+        );
+        assert_error(
+            "compose(fn(x) { x }, [])",
+            "argument `g` of `compose` is not a function",
+        );
+    }
+
+    #[test]
+    fn test45() {
+        let dir = std::env::temp_dir().join(format!("monkey_lang_test45_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        std::fs::write(path("a.txt"), "abc\nあいう\n").unwrap();
+        assert_string(
+            &format!(r#" read_file("{}") "#, path("a.txt")),
+            "abc\nあいう\n",
+        );
+        std::fs::write(path("empty.txt"), "").unwrap();
+        assert_string(&format!(r#" read_file("{}") "#, path("empty.txt")), "");
+
+        //the error contains the path and the error message from the OS
+        let os_error = std::fs::read_to_string(path("missing.txt")).unwrap_err();
+        assert_error(
+            &format!(r#" read_file("{}") "#, path("missing.txt")),
+            &format!("failed to read `{}`: {}", path("missing.txt"), os_error),
+        );
+        std::fs::write(path("binary.dat"), [0x61, 0xff, 0xfe]).unwrap();
+        let utf8_error = std::fs::read_to_string(path("binary.dat")).unwrap_err();
+        assert_error(
+            &format!(r#" read_file("{}") "#, path("binary.dat")),
+            &format!("failed to read `{}`: {}", path("binary.dat"), utf8_error),
+        );
+        assert_error(
+            &format!(r#" read_file("{}") "#, dir.to_str().unwrap()),
+            "failed to read",
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test46() {
+        assert_integer("let sub = fn(a, b) { a - b }; apply(sub, [5, 3])", 2);
+        assert_integer(
+            "let sub = fn(a, b) { a - b }; let args = [5, 3]; apply(sub, args)",
+            2,
+        );
+        assert_integer("apply(fn() { 42 }, [])", 42);
+        assert_integer(r#" apply(len, ["abc"]) "#, 3);
+        //variadic
+        assert_integer(
+            "let f = apply(partial, [fn(a, b, c) { a + b + c }, 1, 2]); f(3)",
+            6,
+        );
+        assert_eq!("[[1, 2]]", run("apply(fn(x) { [x] }, [[1, 2]])"));
+
+        //arity is enforced
+        assert_error(
+            "let sub = fn(a, b) { a - b }; apply(sub, [5])",
+            "argument number mismatch",
+        );
+        assert_error(
+            "let sub = fn(a, b) { a - b }; apply(sub, [5, 3, 1])",
+            "argument number mismatch",
+        );
+        assert_error("apply(1, [])", "argument `f` of `apply` is not a function");
+        assert_error("apply(len, 1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test47() {
+        //a function body shares the scope with the parameters, while a parameter can be shadowed as before
+        assert_integer("let f = fn(x) { let x = x + 1; x }; f(1)", 2);
+        assert_integer(
+            "let f = fn(x, y) { let y = 2; let x = y * 10; x + y }; f(1, 1)",
+            22,
+        );
+        assert_error(
+            "let f = fn(x) { let x = 2; let x = 3; x }; f(1)",
+            "`x` is already defined",
+        );
+        assert_error(
+            "let f = fn(x) { let y = 1; let y = 2; y }; f(1)",
+            "`y` is already defined",
+        );
+        assert_integer("let f = fn(x) { let y = x * 2; y + x }; f(3)", 9);
+        //while a nested block is a new scope as usual
+        assert_integer("let f = fn(x) { if (true) { let x = 2; x } }; f(1)", 2);
+        assert_integer("let f = fn(x) { { let x = x + 1; x } }; f(1)", 2);
+        assert_integer(
+            "let f = fn(x) { let g = fn(x) { x * 10 }; g(x + 1) }; f(1)",
+            20,
+        );
+
+        //a variable defined in a function body doesn't leak
+        assert_error(
+            "let f = fn() { let y = 1; y }; f(); y",
+            "`y` is not defined",
+        );
+        assert_integer("let y = 5; let f = fn() { let y = 1; y }; f() + y", 6);
+        //each call has a fresh scope
+        assert_integer("let f = fn(x) { let y = x; y }; f(1) + f(2)", 3);
+        assert_integer(
+            "let fact = fn(n) { let m = n - 1; if (n == 0) { 1 } else { n * fact(m) } }; fact(5)",
+            120,
+        );
+    }
+
+    #[test]
+    fn test48() {
+        assert_eq!("[a, b, c]", run(r#" chars("abc") "#));
+        assert_eq!("[あ, a]", run(r#" chars("あa") "#));
+        assert_integer(r#" len(chars("あa")) "#, 2);
+        assert_eq!("[]", run(r#" chars("") "#));
+        assert_boolean(r#" chars("aあ") == ['a', 'あ'] "#, true);
+
+        assert_string("str(['a', 'b'])", "ab");
+        assert_string("str([])", "");
+        assert_string(r#" str(chars("あいうえお")) "#, "あいうえお");
+        assert_string(r#" str(chars("")) "#, "");
+        assert_string(r#" str(map(chars("abc"), fn(c) { upper(c) })) "#, "ABC");
+
+        assert_error("chars(1)", "argument type mismatch");
+        assert_error("chars(['a'])", "argument type mismatch");
+        assert_error(r#" str(['a', "b"]) "#, "argument type mismatch");
+    }
+
+    #[test]
+    fn test49() {
+        assert_error("let a = a;", "cannot use `a` in its own initializer");
+        assert_error("let a = a + 1;", "cannot use `a` in its own initializer");
+        assert_error("let a = [1, a];", "cannot use `a` in its own initializer");
+        assert_error(
+            "let a = { let b = a; b };",
+            "cannot use `a` in its own initializer",
+        );
+        assert_error(
+            "let f = fn() { let a = a; a }; f()",
+            "cannot use `a` in its own initializer",
+        );
+        //an outer variable is used
+        assert_integer("let a = 1; { let a = a + 1; a }", 2);
+        assert_integer("let a = 1; let f = fn() { let a = a * 10; a }; f()", 10);
+        assert_integer("let f = fn(a) { { let a = a; a } }; f(3)", 3);
+        //a function body is not evaluated in the initializer (this is how recursion works)
+        assert_integer(
+            "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(3)",
+            0,
+        );
+        //the inner `a` is a different variable
+        assert_integer("let a = { let a = 1; a + 1 }; a", 2);
+        //a use before `let` in the same scope is still just undefined
+        assert_error("a; let a = 1;", "`a` is not defined");
+        //only an evaluated self-reference is an error
+        assert_integer("let a = if (false) { a } else { 1 }; a", 1);
+    }
+
+    #[test]
+    fn test50() {
+        assert_integer("ord('a')", 97);
+        assert_boolean("ord('あ') == 12354", true);
+        assert_integer("ord('😀')", 0x1F600);
+        assert_integer(r#" ord("a") "#, 97);
+        assert_integer(r#" ord("あ") "#, 12354);
+        assert_character("chr(97)", 'a');
+        assert_character("chr(12354)", 'あ');
+        assert_character("chr(0)", '\0');
+        assert_character("chr(1114111)", '\u{10FFFF}');
+        assert_boolean("chr(ord('x')) == 'x'", true);
+        //Caesar cipher
+        assert_string(
+            r#" str(map(chars("abz"), fn(c) { chr((ord(c) - ord('a') + 1) % 26 + ord('a')) })) "#,
+            "bca",
+        );
+
+        assert_error("chr(-1)", "`-1` is not a valid code point");
+        assert_error("chr(55296)", "`55296` is not a valid code point"); //0xD800
+        assert_error("chr(57343)", "`57343` is not a valid code point"); //0xDFFF
+        assert_error("chr(1114112)", "`1114112` is not a valid code point"); //0x110000
+        assert_error("chr(4294967393)", "is not a valid code point"); //`97 + 2^32` doesn't wrap
+        assert_error(
+            r#" ord("ab") "#,
+            "argument of `ord` is not a single character",
+        );
+        assert_error(
+            r#" ord("") "#,
+            "argument of `ord` is not a single character",
+        );
+        assert_error("ord(1)", "argument type mismatch");
+        assert_error("chr('a')", "argument type mismatch");
+    }
+
+    #[test]
+    fn test51() {
+        assert_integer("abs(-3)", 3);
+        assert_integer("abs(3)", 3);
+        assert_integer("abs(0)", 0);
+        assert_integer("abs(9223372036854775807)", i64::MAX);
+        assert_integer("abs(-9223372036854775807)", i64::MAX);
+        assert_float("abs(-1.5)", 1.5);
+        assert_float("abs(2.5)", 2.5);
+        assert_eq!("0", run("abs(-0.0)"));
+        assert_error("abs('a')", "argument type mismatch");
+        assert_error("abs([-1])", "argument type mismatch");
+        if cfg!(feature = "bignum") {
+            assert_eq!("9223372036854775808", run("abs(-9223372036854775807 - 1)"));
+        } else {
+            assert_error("abs(-9223372036854775807 - 1)", "integer overflow");
+        }
+
+        assert_integer("min(3, 1)", 1);
+        assert_integer("max(3, 1)", 3);
+        assert_integer("min(3, 1, 2)", 1);
+        assert_integer("max(3, 5, 2, 5)", 5);
+        assert_integer("min(7)", 7);
+        assert_float("min(1.5, -2.5)", -2.5);
+        assert_float("max(1.5, -2.5)", 1.5);
+        assert_character("min('b', 'a')", 'a');
+        assert_character("max('b', 'あ')", 'あ');
+        assert_string(r#" min("abc", "abd") "#, "abc");
+        assert_string(r#" max("b", "abc") "#, "b");
+        //an array
+        assert_integer("min([3, 1, 2])", 1);
+        assert_integer("max([3, 1, 2])", 3);
+        assert_integer("max([4])", 4);
+        assert_integer("let l = [5, 9, 1]; max(l) - min(l)", 8);
+        //the first one wins a tie
+        assert_eq!("0", run("min(0.0, -0.0)"));
+        assert_eq!("-0", run("max(-0.0, 0.0)"));
+
+        assert_error("min()", "`min` requires at least one value");
+        assert_error("max([])", "`max` requires at least one value");
+        assert_error(r#" min(1, "a") "#, "cannot compare string with int");
+        assert_error("max([1, 2.5])", "cannot compare float with int");
+        assert_error("min([1], [2])", "cannot compare array with array");
+    }
+
+    #[test]
+    fn test52() {
+        //a top-level `return` stops the program
+        assert_eq!("1", run("return 1; print(2)"));
+        assert_eq!("a\n1", run(r#" print("a"); return 1; print("b"); 2 "#));
+        assert_null("return; 3");
+        assert_eq!("null", run("return; print(3)"));
+        assert_integer("if (true) { return 1; }; 2", 1);
+        assert_integer("{ { return 1; }; 2 }; 3", 1);
+        assert_integer("let f = fn() { return 1; 2 }; f(); 3", 3);
+        //`let` after `return` is not evaluated
+        assert_integer("return 1; let a = b;", 1);
+    }
+
+    #[test]
+    fn test53() {
+        assert_integer("sum([1, 2, 3])", 6);
+        assert_integer("product([2, 3, 4])", 24);
+        assert_integer("sum([-1])", -1);
+        assert_float("sum([1.5, 2.25])", 3.75);
+        assert_float("product([1.5, 2.0])", 3.0);
+        //mixed
+        assert_float("sum([1, 0.5, 2])", 3.5);
+        assert_float("product([2, 0.5])", 1.0);
+        assert_float("sum([0.5, 1])", 1.5);
+        //empty
+        assert_integer("sum([])", 0);
+        assert_integer("product([])", 1);
+        assert_integer("let l = to_array(1..=10); sum(l)", 55);
+
+        assert_error(
+            r#" sum([1, 2, "a"]) "#,
+            "element 2 of `sum` argument is a string",
+        );
+        assert_error(
+            "product([[1]])",
+            "element 0 of `product` argument is an array",
+        );
+        assert_error("sum(1)", "argument type mismatch");
+        if cfg!(feature = "bignum") {
+            assert_eq!("9223372036854775808", run("sum([9223372036854775807, 1])"));
+        } else {
+            assert_error("sum([9223372036854775807, 1])", "integer overflow");
+            assert_error("product([4294967296, 4294967296])", "integer overflow");
+        }
+    }
+
+    #[test]
+    fn test54() {
+        //a single array argument is reduced, while scalar arguments are compared with each other
+        assert_boolean("max([3, 1, 2]) == 3", true);
+        assert_boolean("min([3, 1, 2]) == 1", true);
+        assert_boolean("max(3, 1) == 3", true);
+        assert_boolean("min(3, 1) == 1", true);
+        assert_boolean(r#" max(["b", "c", "a"]) == "c" "#, true);
+        assert_integer("let l = [[3, 4], [2, 1]]; min(l[1])", 1);
+        assert_error("min([])", "`min` requires at least one value");
+        assert_error("max([1, 'a'])", "cannot compare char with int");
+        assert_error("max([1, [2]])", "cannot compare array with int");
+    }
+
+    #[test]
+    fn test55() {
+        assert_integer("floor(2.7)", 2);
+        assert_integer("floor(-2.2)", -3);
+        assert_integer("ceil(2.2)", 3);
+        assert_integer("ceil(-2.7)", -2);
+        assert_integer("floor(-0.5)", -1);
+        assert_integer("ceil(-0.5)", 0);
+        assert_integer("round(2.4)", 2);
+        assert_integer("round(-2.6)", -3);
+        //half away from zero (not half to even)
+        assert_integer("round(0.5)", 1);
+        assert_integer("round(1.5)", 2);
+        assert_integer("round(2.5)", 3);
+        assert_integer("round(-2.5)", -3);
+        //an integer is returned as is
+        assert_integer("floor(7)", 7);
+        assert_integer("ceil(-7)", -7);
+        assert_integer("round(9223372036854775807)", i64::MAX);
+
+        //the digits form returns a float
+        assert_float("round(3.14159, 2)", 3.14);
+        assert_float("round(-3.145, 1)", -3.1);
+        assert_float("round(2.5, 0)", 3.0);
+        assert_float("round(1250.0, -2)", 1300.0);
+
+        //out of the range of int
+        assert_integer("floor(-9223372036854775808.0)", i64::MIN);
+        assert_error(
+            "floor(9223372036854775808.0)",
+            "result of `floor` is out of the range of int",
+        );
+        assert_error(
+            "ceil(100000000000000000000.0)",
+            "result of `ceil` is out of the range of int",
+        );
+        assert_error(
+            "round(-10000000000000000000.0)",
+            "result of `round` is out of the range of int",
+        );
+
+        assert_error("floor('a')", "argument type mismatch");
+        assert_error(r#" round(1.5, "2") "#, "argument type mismatch");
+        assert_error("round(1.5, 1, 1)", "argument number mismatch");
+        assert_error("round()", "argument number mismatch");
+    }
+
+    #[test]
+    fn test56() {
+        assert_boolean("count([1, 2, 1, 1], 1) == 3", true);
+        assert_integer("count([1, 2, 1, 1], 3)", 0);
+        assert_integer("count([], 1)", 0);
+        assert_integer(r#" count([[1], 1, "1", [1]], [1]) "#, 2);
+        assert_boolean(r#" count("banana", "a") == 3 "#, true);
+        assert_integer(r#" count("banana", 'n') "#, 2);
+        assert_integer(r#" count("banana", "an") "#, 2);
+        //non-overlapping
+        assert_integer(r#" count("aaaa", "aa") "#, 2);
+        assert_integer(r#" count("あいあい", "あい") "#, 2);
+        assert_integer(r#" count("", "a") "#, 0);
+        assert_error(r#" count("abc", "") "#, "cannot count empty strings");
+        assert_error(
+            r#" count("abc", 1) "#,
+            "argument 2 of `count`: expected char/string/function, got int",
+        );
+        assert_error(
+            "count(1, 1)",
+            "argument 1 of `count`: expected array/string, got int",
+        );
+
+        //predicate
+        assert_integer("count([1, 2, 3, 4, 5], fn(x) { x % 2 == 1 })", 3);
+        assert_integer("count([], fn(x) { true })", 0);
+        assert_integer(r#" count([[1], [], [2, 3]], fn(a) { len(a) > 0 }) "#, 2);
+        assert_integer(r#" count("a1b22", fn(c) { c >= '0' && c <= '9' }) "#, 3);
+        assert_error(
+            "count([1], fn(x) { x })",
+            "predicate of `count` returned a non-boolean",
+        );
+        assert_error("count([1], fn(x) { y })", "`y` is not defined");
+        assert_error(
+            "count(1, fn(x) { true })",
+            "argument 1 of `count`: expected array/string, got int",
+        );
+
+        assert_eq!(
+            r#"{"b": 1, "a": 3, "n": 2}"#,
+            run(r#" repr(frequency(["b", "a", "n", "a", "n", "a"])) "#)
+        );
+        assert_eq!("{a: 3, b: 1}", run(r#" frequency(chars("abaa")) "#));
+        assert_integer("let h = frequency([1, 2, 1, true]); h[1]", 2);
+        assert_eq!("{:}", run("frequency([])"));
+        assert_error(
+            "frequency([1.5])",
+            "unhashable type `float` used as a hash key",
+        );
+        assert_error(r#" frequency("abc") "#, "argument type mismatch");
+    }
+
+    #[test]
+    fn test57() {
+        let assert_close = |s: &str, expected: f64| {
+            let o = read_and_eval(s);
+            let actual = o.as_any().downcast_ref::<Float>().unwrap().value();
+            assert!((actual - expected).abs() < 1e-12, "{}: {}", s, actual);
+        };
+
+        assert_close("sqrt(2.0)", std::f64::consts::SQRT_2);
+        assert_close("sqrt(16)", 4.0);
+        assert_close("sqrt(0)", 0.0);
+        assert_close("exp(0)", 1.0);
+        assert_close("exp(1.0)", std::f64::consts::E);
+        assert_close("log(exp(2.5))", 2.5);
+        assert_close("log(1)", 0.0);
+        assert_close("log10(1000)", 3.0);
+        assert_close("log10(0.01)", -2.0);
+        assert_close("sin(0)", 0.0);
+        assert_close("sin(pi / 2.0)", 1.0);
+        assert_close("cos(pi)", -1.0);
+        assert_close("tan(pi / 4.0)", 1.0);
+        assert_close("let x = 0.7; sin(x) * sin(x) + cos(x) * cos(x)", 1.0);
+
+        assert_error("sqrt(-1)", "`sqrt` is not defined for -1");
+        assert_error("sqrt(-0.5)", "`sqrt` is not defined for -0.5");
+        assert_error("log(0)", "`log` is not defined for 0");
+        assert_error("log(-1.0)", "`log` is not defined for -1");
+        assert_error("log10(0.0)", "`log10` is not defined for 0");
+        assert_error("exp(1000)", "result of `exp` is out of the range of float");
+        assert_error("sin('a')", "argument type mismatch");
+    }
+
+    #[test]
+    fn test58() {
+        assert_boolean("unique([1, 1, 2, 3, 3, 3]) == [1, 2, 3]", true);
+        //the first occurrences are kept in the order
+        assert_array("unique([3, 1, 3, 2, 1])", &[3, 1, 2]);
+        assert_array("unique([])", &[]);
+        assert_array("unique([5])", &[5]);
+        assert_eq!(r#"["b", "a"]"#, run(r#" repr(unique(["b", "a", "b"])) "#));
+        //structural equality
+        assert_eq!("[[1, 2], [2, 1]]", run("unique([[1, 2], [2, 1], [1, 2]])"));
+        assert_eq!("[{1: 2}]", run("unique([{1: 2}, {1: 2}])"));
+        assert_eq!("[1.5, 2]", run("unique([1.5, 2.0, 1.5])"));
+
+        assert_error(r#" unique([1, "a"]) "#, "cannot compare string with int");
+        assert_error("unique(1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test59() {
+        assert_array("take([1, 2, 3], 2)", &[1, 2]);
+        assert_array("take([1, 2, 3], 5)", &[1, 2, 3]);
+        assert_array("take([1, 2, 3], 0)", &[]);
+        assert_array("take([1, 2, 3], -1)", &[]);
+        assert_array("take([], 1)", &[]);
+        assert_array("drop([1, 2, 3], 2)", &[3]);
+        assert_array("drop([1, 2, 3], 5)", &[]);
+        assert_array("drop([1, 2, 3], 0)", &[1, 2, 3]);
+        assert_array("drop([1, 2, 3], -1)", &[1, 2, 3]);
+        assert_boolean("let l = [4, 5, 6]; take(l, 1) + drop(l, 1) == l", true);
+
+        assert_array("take_while([1, 2, 3, 1], fn(x) { x < 3 })", &[1, 2]);
+        assert_array("drop_while([1, 2, 3, 1], fn(x) { x < 3 })", &[3, 1]);
+        assert_array("take_while([1, 2], fn(x) { true })", &[1, 2]);
+        assert_array("drop_while([1, 2], fn(x) { true })", &[]);
+        assert_array("take_while([5, 1], fn(x) { x < 3 })", &[]);
+        assert_array("take_while([], fn(x) { 1 })", &[]);
+        //the predicate is not called after the first failure
+        assert_eq!(
+            "1\n2\n[1]",
+            run("take_while([1, 2, 3], fn(x) { print(x); x < 2 })")
+        );
+
+        assert_error("take([1], 1.5)", "argument type mismatch");
+        assert_error("drop(1, 1)", "argument type mismatch");
+        assert_error(
+            "take_while([1], fn(x) { x })",
+            "predicate of `take_while` returned a non-boolean",
+        );
+        assert_error(
+            "drop_while([1], 1)",
+            "argument `f` of `drop_while` is not a function",
+        );
+        assert_error(
+            "take_while([1], fn(x, y) { true })",
+            "argument number mismatch",
+        );
+    }
+
+    #[test]
+    fn test60() {
+        //the real ones
+        let t = read_and_eval("time()");
+        assert!(t.as_any().downcast_ref::<Int>().unwrap().value() > 1_600_000_000);
+        let d = read_and_eval("let t = clock(); clock() - t");
+        assert!(d.as_any().downcast_ref::<Float>().unwrap().value() >= 0.0);
+
+        //fake ones
+        let ticks = Rc::new(RefCell::new(vec![10.5, 12.0, 12.25].into_iter()));
+        let mut builtin = Builtin::new();
+        builtin.register_fn("clock", move || ticks.borrow_mut().next().unwrap());
+        builtin.register_fn("time", || 1_700_000_000);
+        let evaluator = Evaluator::with_builtin(builtin);
+        let eval = |s: &str| {
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+        assert_eq!(
+            Ok("1.5".to_string()),
+            eval("let t = clock(); let f = fn(n) { n * 2 }; f(21); clock() - t")
+        );
+        assert_eq!(Ok("12.25".to_string()), eval("clock()"));
+        assert_eq!(Ok("1700000000".to_string()), eval("time()"));
+
+        //successive calls are monotonically nondecreasing
+        let o =
+            read_and_eval("let a = clock(); let b = clock(); let c = clock(); [a <= b, b <= c]");
+        assert_eq!("[true, true]", o.to_string());
+
+        //capability
+        let evaluator = Evaluator::sandboxed();
+        for (s, name) in [("clock()", "clock"), ("time()", "time")] {
+            assert_eq!(
+                Err(format!("`{}` is disabled in this sandbox", name)),
+                evaluator
+                    .eval(&parse(s), &mut Environment::new(None))
+                    .map(|o| o.to_string())
+            );
+        }
+        let config = EvaluatorConfig {
+            allow_time: true,
+            ..EvaluatorConfig::sandboxed()
+        };
+        let evaluator = Evaluator::with_config(Builtin::new(), config);
+        assert!(evaluator
+            .eval(&parse("clock()"), &mut Environment::new(None))
+            .is_ok());
+    }
+
+    #[test]
+    fn test61() {
+        assert_array("reverse([1, 2, 3])", &[3, 2, 1]);
+        assert_array("reverse([])", &[]);
+        assert_eq!("[[2, 3], 1]", run("reverse([1, [2, 3]])"));
+        assert_array("let l = [1, 2]; reverse(l); l", &[1, 2]);
+        assert_boolean(r#" reverse("hello") == "olleh" "#, true);
+        assert_string(r#" reverse("") "#, "");
+        assert_string(r#" reverse("aあいb") "#, "bいあa");
+        assert_string(r#" reverse("😀é") "#, "é😀");
+        assert_boolean(r#" let s = "こんにちは"; reverse(reverse(s)) == s "#, true);
+        assert_error("reverse(1)", "argument type mismatch");
+        assert_error("reverse({1: 2})", "argument type mismatch");
+    }
+
+    #[test]
+    fn test62() {
+        assert_integer(r#" int("42") "#, 42);
+        assert_integer(r#" int("  -42\n") "#, -42);
+        assert_integer(r#" int("+7") "#, 7);
+        assert_integer(r#" int("-0") "#, 0);
+        assert_integer(r#" int("9223372036854775807") "#, i64::MAX);
+        assert_integer(r#" int("-9223372036854775808") "#, i64::MIN);
+        assert_integer(r#" int("ff", 16) "#, 255);
+        assert_integer(r#" int(" -FF ", 16) "#, -255);
+        assert_integer(r#" int("101", 2) "#, 5);
+        assert_integer(r#" int("zz", 36) "#, 1295);
+        assert_integer(r#" int("010") "#, 10);
+        assert_integer("int(true)", 1);
+        assert_integer("int(false)", 0);
+        assert_error(r#" int("12abc") "#, "cannot parse `12abc` as int");
+        assert_error(r#" int("") "#, "cannot parse `` as int");
+        assert_error(r#" int("1.5") "#, "cannot parse `1.5` as int");
+        assert_error(r#" int("0xff", 16) "#, "cannot parse `0xff` as int");
+        assert_error(r#" int("2", 2) "#, "cannot parse `2` as int");
+        assert_error(r#" int("1 2") "#, "cannot parse `1 2` as int");
+        assert_error(r#" int("1", 1) "#, "radix `1` is not between 2 and 36");
+        assert_error(r#" int("1", 37) "#, "radix `37` is not between 2 and 36");
+        assert_error(r#" int("1", "2") "#, "argument type mismatch");
+        assert_error("int(1.5, 2)", "argument type mismatch");
+        assert_error(r#" int("1", 2, 3) "#, "argument number mismatch");
+        if cfg!(feature = "bignum") {
+            assert_eq!(
+                "9223372036854775808",
+                run(r#" int("+9223372036854775808") "#)
+            );
+        } else {
+            assert_error(
+                r#" int("9223372036854775808") "#,
+                "`9223372036854775808` is out of the range of int",
+            );
+        }
+        assert_error(
+            r#" int("-1000000000000000000000", 16) "#,
+            "`-1000000000000000000000` is out of the range of int",
+        );
+
+        assert_float(r#" float("3.14") "#, 3.14);
+        assert_float(r#" float(" -2.5 ") "#, -2.5);
+        assert_float(r#" float("+1") "#, 1.0);
+        assert_float(r#" float("1e3") "#, 1000.0);
+        assert_float(r#" float(".5") "#, 0.5);
+        assert_error(r#" float("3.14abc") "#, "cannot parse `3.14abc` as float");
+        assert_error(r#" float("") "#, "cannot parse `` as float");
+        assert_error(r#" float("1..2") "#, "cannot parse `1..2` as float");
+        assert_error(r#" float("inf") "#, "cannot parse `inf` as float");
+        assert_error(r#" float("NaN") "#, "cannot parse `NaN` as float");
+        assert_error(
+            r#" float("1e400") "#,
+            "`1e400` is out of the range of float",
+        );
+
+        assert_string("str(42)", "42");
+        assert_string("str(-1)", "-1");
+        assert_string("str(true)", "true");
+        assert_string(r#" str("abc") "#, "abc");
+        assert_integer("int(str(-42))", -42);
+        assert_float("float(str(2.5))", 2.5);
+        assert_error("str({1: 2})", "argument type mismatch");
+    }
+
+    #[test]
+    fn test63() {
+        assert_string(r#" join(["a", "b", "c"], ", ") "#, "a, b, c");
+        assert_boolean(r#" join([1, 2, 3], "-") == "1-2-3" "#, true);
+        assert_string(r#" join([1, 2, 3], "") "#, "123");
+        assert_string("join([1, 2, 3], ',')", "1,2,3");
+        assert_string(
+            r#" join(["a", 'b', 1, 2.5, true, [1, "x"]], " ") "#,
+            "a b 1 2.5 true [1, x]",
+        );
+        assert_string(r#" join([], "-") "#, "");
+        assert_string(r#" join(["あ"], "-") "#, "あ");
+        assert_boolean(r#" let s = "a,b,,c"; join(split(s, ","), ",") == s "#, true);
+        assert_error(r#" join("abc", "-") "#, "argument type mismatch");
+        assert_error("join([1, 2], 0)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test64() {
+        assert_string(r#" to_upper("Hello, World!") "#, "HELLO, WORLD!");
+        assert_string(r#" to_lower("Hello, World!") "#, "hello, world!");
+        assert_string(r#" to_upper("") "#, "");
+        assert_character("to_upper('a')", 'A');
+        assert_character("to_lower('A')", 'a');
+        assert_character("to_upper('1')", '1');
+
+        //non-ASCII
+        assert_string(r#" to_upper("éàü") "#, "ÉÀÜ");
+        assert_string(r#" to_lower("ΑΒΓ") "#, "αβγ");
+        assert_string(r#" to_upper("あいう") "#, "あいう");
+        assert_character("to_upper('ж')", 'Ж');
+
+        //the length may change
+        assert_string(r#" to_upper("straße") "#, "STRASSE");
+        assert_integer(r#" len(to_upper("ß")) "#, 2);
+        assert_string(r#" to_lower("İ") "#, "i\u{307}");
+        //a character which maps to more than one character is returned unchanged
+        assert_character("to_upper('ß')", 'ß');
+
+        assert_boolean("to_upper == upper", true);
+        assert_boolean("to_lower == lower", true);
+        assert_error("to_upper(1)", "argument type mismatch");
+        assert_error(r#" to_lower(["A"]) "#, "argument type mismatch");
+    }
+
+    #[test]
+    fn test65() {
+        let null = "let null = if (false) { 1 };";
+        let inc = "let inc = fn(x) { x + 1 };";
+
+        assert_integer("and_then(5, fn(x) { x + 1 })", 6);
+        assert_null(&format!("{} and_then(null, fn(x) {{ x + 1 }})", null));
+        assert_boolean(&format!("{} and_then(5, inc) == 6", inc), true);
+
+        //`f` is not called for `null`
+        assert_eq!(
+            "null",
+            run(&format!(
+                r#"{} and_then(null, fn(x) {{ print("called") }})"#,
+                null
+            ))
+        );
+
+        //chaining
+        assert_integer(&format!("{} and_then(and_then(1, inc), inc)", inc), 3);
+        assert_null(&format!(
+            "{} {} let f = fn(x) {{ if (x > 1) {{ x }} }}; and_then(and_then(1, f), inc)",
+            null, inc
+        ));
+
+        //values other than `null` are passed as is, including falsy ones
+        assert_boolean("and_then(false, fn(x) { !x })", true);
+        assert_integer("and_then(0, fn(x) { x + 1 })", 1);
+        assert_integer("and_then([], len)", 0);
+
+        assert_error(
+            "and_then(1, 2)",
+            "argument `f` of `and_then` is not a function",
+        );
+        assert_error(
+            &format!("{} and_then(null, 2)", null),
+            "argument `f` of `and_then` is not a function",
+        );
+    }
+
+    #[test]
+    fn test66() {
+        assert_string(r#" format("x = {}, y = {}", 1, 2) "#, "x = 1, y = 2");
+        assert_string(r#" format("no placeholder") "#, "no placeholder");
+        assert_string(r#" format("") "#, "");
+        assert_string(r#" format("{}{}", "あ", 'い') "#, "あい");
+        assert_string(
+            r#" let x = 3; format("{} * 2 = {}", x, x * 2) "#,
+            "3 * 2 = 6",
+        );
+
+        //escapes
+        assert_string(r#" format("{{}}") "#, "{}");
+        assert_string(r#" format("{{{}}}", 1) "#, "{1}");
+        assert_string(r#" format("}}{{") "#, "}{");
+        assert_error(r#" format("{") "#, "unmatched `{` in the format string");
+        assert_error(r#" format("a } b") "#, "unmatched `}` in the format string");
+        assert_error(
+            r#" format("{{}", 1) "#,
+            "unmatched `}` in the format string",
+        );
+        assert_error(
+            r#" format("{ }", 1) "#,
+            "unmatched `{` in the format string",
+        );
+
+        //count mismatch
+        assert_error(
+            r#" format("{} {}", 1) "#,
+            "the number of placeholders (2) doesn't match the number of arguments (1)",
+        );
+        assert_error(
+            r#" format("{}", 1, 2) "#,
+            "the number of placeholders (1) doesn't match the number of arguments (2)",
+        );
+        assert_error(
+            r#" format("{{}}", 1) "#,
+            "the number of placeholders (0) doesn't match the number of arguments (1)",
+        );
+
+        //every type is converted as `print()` does
+        assert_string(
+            r#"
+                let null = if (false) { 1 };
+                let f = fn(x) { x };
+                format(
+                    "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+                    1, 2.5, true, 'c', "s", [1, "a"], {"k": 'v'}, 1..3, null, f, len
+                )
+            "#,
+            r#"1|2.5|true|c|s|[1, a]|{k: v}|1..3|null|fn(x) {
+    x
+}|built-in function"#,
+        );
+
+        assert_error("format(1)", "argument type mismatch");
+        assert_error("format()", "argument number mismatch");
+    }
+
+    #[test]
+    fn test67() {
+        let eval = |max_size: Option<usize>, s: &str| {
+            let config = EvaluatorConfig {
+                max_size,
+                ..EvaluatorConfig::default()
+            };
+            Evaluator::with_config(Builtin::new(), config)
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+        let too_large = Err("result too large".to_string());
+
+        //concatenation
+        assert_eq!(
+            Ok("abcdeabcde".to_string()),
+            eval(Some(10), r#" "abcde" + "abcde" "#)
+        );
+        assert_eq!(too_large, eval(Some(10), r#" "abcde" + "abcdef" "#));
+        assert_eq!(
+            too_large,
+            eval(Some(10), r#" let s = "abcde"; let t = s + s; t + t "#)
+        );
+        assert_eq!(Ok("[1, 2, 3]".to_string()), eval(Some(3), "[1, 2] + [3]"));
+        assert_eq!(too_large, eval(Some(3), "[1, 2] + [3, 4]"));
+        //in characters, not in bytes
+        assert_eq!(
+            Ok("あいうえおかきくけこ".to_string()),
+            eval(Some(10), r#" "あいうえお" + "かきくけこ" "#)
+        );
+
+        //built-in functions
+        assert_eq!(Ok("[0, 1, 2]".to_string()), eval(Some(3), "to_array(0..3)"));
+        assert_eq!(too_large, eval(Some(3), "to_array(0..=3)"));
+        //fails before allocating the array
+        assert_eq!(too_large, eval(Some(1000), "to_array(0..1000000000000)"));
+        //even if the length itself overflows
+        assert_eq!(
+            Err("length overflow".to_string()),
+            eval(
+                Some(1000),
+                "to_array((-9223372036854775807 - 1)..=9223372036854775807)"
+            )
+        );
+        assert_eq!(
+            Ok("[1, 2, 3]".to_string()),
+            eval(Some(3), "append([1, 2], 3)")
+        );
+        assert_eq!(too_large, eval(Some(3), "append([1, 2, 3], 4)"));
+        assert_eq!(too_large, eval(Some(3), r#" append("ab", "cd") "#));
+        assert_eq!(
+            Ok("a-b".to_string()),
+            eval(Some(3), r#" join(["a", "b"], "-") "#)
+        );
+        assert_eq!(too_large, eval(Some(3), r#" join(["a", "b"], "--") "#));
+        assert_eq!(too_large, eval(Some(3), r#" format("{}{}", 12, 34) "#));
+        //a function which builds a large result step by step
+        assert_eq!(
+            too_large,
+            eval(
+                Some(100),
+                "let f = fn(l) { if (len(l) > 1000) { return l; } f(l + l) }; f([1])"
+            )
+        );
+
+        //no limit by default
+        assert_eq!(
+            Ok("1001".to_string()),
+            eval(None, "len(to_array(0..=1000))")
+        );
+    }
+
+    #[test]
+    fn test68() {
+        let output = |s: &str| {
+            let out = Rc::new(RefCell::new(Vec::new()));
+            let err = Rc::new(RefCell::new(Vec::new()));
+            let evaluator = Evaluator::with_builtin(Builtin::with_output(out.clone(), err.clone()));
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .unwrap();
+            let out = String::from_utf8(out.borrow().clone()).unwrap();
+            let err = String::from_utf8(err.borrow().clone()).unwrap();
+            (out, err)
+        };
+
+        assert_eq!(("\n".to_string(), "".to_string()), output("print()"));
+        assert_eq!(("1\n".to_string(), "".to_string()), output("print(1)"));
+        assert_eq!(
+            ("1 a b [2, c] 2.5\n".to_string(), "".to_string()),
+            output(r#"print(1, "a", 'b', [2, "c"], 2.5)"#)
+        );
+        assert_eq!(
+            (" \n".to_string(), "".to_string()),
+            output(r#"print("", "")"#)
+        );
+
+        assert_eq!(("".to_string(), "".to_string()), output("print_raw()"));
+        assert_eq!(
+            ("12ab\n3".to_string(), "".to_string()),
+            output(r#"print_raw(1, 2); print_raw("a", 'b', "\n"); print_raw(3)"#)
+        );
+        assert_eq!(
+            ("[1, 2]3\n".to_string(), "".to_string()),
+            output("print_raw([1, 2]); print(3)")
+        );
+
+        assert_eq!(
+            ("".to_string(), "\nx 1\n".to_string()),
+            output("eprint(); eprint('x', 1)")
+        );
+
+        assert_null("print(1, 2)");
+        assert_null("print_raw(1, 2)");
+    }
+
+    #[test]
+    fn test69() {
+        assert_eq!("[]", run("argv()"));
+        let mut builtin = Builtin::new();
+        builtin.set_argv(vec!["a".to_string(), "b c".to_string()]);
+        let evaluator = Evaluator::with_builtin(builtin);
+        let o = evaluator
+            .eval(&parse(r#" argv() + ["d"] "#), &mut Environment::new(None))
+            .unwrap();
+        assert_eq!(r#"["a", "b c", "d"]"#, repr(o.as_ref()));
+        let o = evaluator
+            .eval(&parse("args() == argv()"), &mut Environment::new(None))
+            .unwrap();
+        assert_eq!("true", o.to_string());
+        assert_eq!("[]", run("args()"));
+        //`args` is a common variable name, which is not reserved even after `set_argv()`
+        let o = evaluator
+            .eval(&parse("let args = 1; args"), &mut Environment::new(None))
+            .unwrap();
+        assert_eq!("1", o.to_string());
+
+        //`PATH` is set in any reasonable environment
+        assert_boolean(r#" len(env("PATH")) > 0 "#, true);
+        assert_null(r#" env("MONKEY_LANG_UNDEFINED_VARIABLE") "#);
+        assert_error("env(1)", "argument 1 of `env`: expected string, got int");
+        assert_error(r#" env("PATH", "HOME") "#, "argument number mismatch");
+        //The process environment is not modified here as the tests run in parallel (see `tests/cli.rs` instead).
+        assert_boolean(r#" env()["PATH"] == env("PATH") "#, true);
+        assert_boolean(r#" "PATH" in env() "#, true);
+        assert_boolean(r#" "MONKEY_LANG_UNDEFINED_VARIABLE" in env() "#, false);
+
+        //capability
+        let mut builtin = Builtin::new();
+        builtin.set_argv(vec!["a".to_string()]);
+        let evaluator = Evaluator::with_config(builtin, EvaluatorConfig::sandboxed());
+        let eval = |s: &str| {
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+        assert_eq!(
+            Err("`argv` is disabled in this sandbox".to_string()),
+            eval("argv()")
+        );
+        assert_eq!(
+            Err("`env` is disabled in this sandbox".to_string()),
+            eval(r#" env("PATH") "#)
+        );
+    }
+
+    #[test]
+    fn test70() {
+        let evaluator = Evaluator::new();
+        let eval = |s: &str| {
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .unwrap()
+                .to_string()
+        };
+        let s = "[random(), random_int(1, 6), shuffle([1, 2, 3, 4, 5]), random()]";
+        eval("seed(42)");
+        let v1 = eval(s);
+        eval("seed(42)");
+        assert_eq!(v1, eval(s));
+        //the seed can also be set by the host
+        evaluator.seed(42);
+        assert_eq!(v1, eval(s));
+        eval("seed(43)");
+        assert_ne!(v1, eval(s));
+
+        assert_eq!("true", eval("let r = random(); (r >= 0.) && (r < 1.)"));
+        for _ in 0..100 {
+            let i: i64 = eval("random_int(-2, 2)").parse().unwrap();
+            assert!((-2..=2).contains(&i));
+        }
+        assert_integer("random_int(3, 3)", 3);
+        assert_error("random_int(3, 2)", "empty range");
+        assert_error("random_int(1., 2)", "argument type mismatch");
+        assert_error("seed(1.)", "argument type mismatch");
+
+        //`shuffle()` returns a permutation
+        assert_eq!("[1, 2, 3, 4, 5]", eval("sort(shuffle([5, 3, 1, 4, 2]))"));
+        assert_array("shuffle([])", &[]);
+        assert_error("shuffle(1)", "argument type mismatch");
+    }
+
+    #[test]
+    fn test71() {
+        //chains of indexes and calls
+        assert_integer("let a = [[1, [2, 3]]]; a[0][1][1]", 3);
+        assert_integer("[[1, 2], [3, 4]][1][0]", 3);
+        assert_integer("let f = fn() { fn(x) { fn(y) { x * y } } }; f()(3)(4)", 12);
+        assert_integer("let f = fn() { fn(x) { [x, [x * 2]] } }; f()(3)[1][0]", 6);
+        assert_integer(
+            r#" let m = {"k": [fn() { {"v": 7} }]}; m["k"][0]()["v"] "#,
+            7,
+        );
+        assert_integer("fn() { [fn(x) { x + 1 }] }()[0](1)", 2);
+        assert_character(r#" let f = fn() { ["ab", "cd"] }; f()[1][0] "#, 'c');
+        assert_integer(r#" ({"a": {"b": 5}})["a"]["b"] "#, 5);
+
+        assert_error("let f = fn() { 1 }; f()()", "`int` is not a function");
+        assert_error(
+            "let f = fn() { 1 }; f()[0]",
+            "`int` is not an array, a string nor a hash",
+        );
+        assert_error(
+            "[[1]][0][0][0]",
+            "`int` is not an array, a string nor a hash",
+        );
+        assert_error("[[1]][0][1]", "array index out of bounds");
+    }
+
+    #[test]
+    fn test72() {
+        //a built-in identifier can be shadowed in an inner scope
+        assert_integer("{ let len = 3; len }", 3);
+        assert_integer(r#" { let len = 3; }; len("ab") "#, 2);
+        assert_array(
+            "let f = fn() { let len = 1; len }; [f(), len([1, 2])]",
+            &[1, 2],
+        );
+        assert_integer("let f = fn(len) { len * 2 }; f(5)", 10);
+        assert_integer("let x = if (true) { let pi = 3; pi }; x", 3);
+        assert_float("{ let pi = 3; }; pi", 3.141592653589793);
+        assert_integer("let f = fn() { let max = 2; fn() { max } }; f()()", 2);
+
+        //but not at the top level
+        assert_error("let len = 3;", "`len` is a built-in identifier");
+        assert_error("let pi = 3;", "`pi` is a built-in identifier");
+    }
+
+    #[test]
+    fn test73() {
+        //operator overloading
+        let point = r#"
+            let point = fn(x, y) {
+                {
+                    "x": x,
+                    "y": y,
+                    "__add__": fn(other) { point(x + other["x"], y + other["y"]) },
+                    "__mul__": fn(k) { point(x * k, y * k) },
+                    "__eq__": fn(other) { (x == other["x"]) && (y == other["y"]) },
+                    "__index__": fn(i) { [x, y][i] },
+                }
+            };
+        "#;
+        let eval = |s: &str| read_and_eval(&format!("{}{}", point, s)).to_string();
+        assert_eq!(
+            "[4, 6]",
+            eval("let p = point(1, 2) + point(3, 4); [p[0], p[1]]")
+        );
+        assert_eq!(
+            "[2, 4]",
+            eval("let p = point(1, 2) * 2; [p[\"x\"], p[\"y\"]]")
+        );
+        assert_eq!(
+            "[true, false, false, true]",
+            eval("let p = point(1, 2); [p == point(1, 2), p == point(2, 1), p != point(1, 2), p != point(2, 1)]")
+        );
+        //the ordinary keys take precedence over `__index__`
+        assert_eq!("1", eval(r#" point(1, 2)["x"] "#));
+
+        //not overloaded
+        assert_error(r#" {"x": 1} + 1 "#, "operand of binary `+`");
+        assert_error(r#" {"__add__": 1} + 1 "#, "operand of binary `+`");
+        assert_error(r#" 1 + {"__add__": fn(x) { x }} "#, "operand of binary `+`");
+        assert_boolean(r#" {"a": 1} == {"a": 1} "#, true);
+        assert_error(r#" {"a": 1}["b"] "#, "key \"b\" not found in hash");
+
+        assert_error(
+            r#" {"__eq__": fn(x) { 1 }} == 1 "#,
+            "`__eq__` returned a non-boolean value",
+        );
+        assert_error(
+            r#" {"__add__": fn() { 1 }} + 1 "#,
+            "argument number mismatch",
+        );
+        assert_integer(r#" {"__sub__": len}  - [1, 2] "#, 2);
+    }
+
+    #[test]
+    fn test74() {
+        //records
+        assert_integer("let p = {.x: 1, .y: 2}; p.x + p.y", 3);
+        assert_integer(r#" let p = {"x": 1}; p.x "#, 1);
+        assert_boolean(r#" {.x: 1} == {"x": 1} "#, true);
+        assert_integer(r#" let k = "k"; {k: 2}.k "#, 2);
+        assert_integer("let k = 1; {k: 2, .k: 3}.k", 3);
+        assert_integer("let p = {.inner: {.v: [10, 20]}}; p.inner.v[1]", 20);
+        assert_integer("let p = {.f: fn(x) { x * 2 }}; p.f(4)", 8);
+        assert_integer("let make = fn(n) { {.n: n} }; make(5).n", 5);
+        assert_integer(r#" let p = {.len: 1}; p.len + len("ab") "#, 3);
+
+        assert_error("let p = {.x: 1}; p.z", "no field `z`");
+        assert_error("let a = [1]; a.x", "`a` is not a hash");
+        assert_error("[1].x", "`array` is not a hash");
+    }
+
+    #[test]
+    fn test75() {
+        assert_array("concat()", &[]);
+        assert_array("concat([1, 2])", &[1, 2]);
+        assert_array("concat([1], [], [2, 3], [4])", &[1, 2, 3, 4]);
+        assert_eq!("[[1], 2]", run("concat([[1]], [2])"));
+        assert_string(r#" concat("ab") "#, "ab");
+        assert_string(r#" concat("a", "", "bc", "あ") "#, "abcあ");
+        assert_array("let l = [1]; concat(l, l); l", &[1]);
+
+        assert_error(
+            r#" concat([1], [2], "a") "#,
+            "argument 3 of `concat`: expected array, got string",
+        );
+        assert_error(
+            r#" concat("a", ['b']) "#,
+            "argument 2 of `concat`: expected string, got array",
+        );
+        assert_error(
+            "concat(1, [2])",
+            "argument 1 of `concat`: expected array, got int",
+        );
+
+        let config = EvaluatorConfig {
+            max_size: Some(3),
+            ..EvaluatorConfig::default()
+        };
+        let evaluator = Evaluator::with_config(Builtin::new(), config);
+        assert_eq!(
+            Err("result too large".to_string()),
+            evaluator
+                .eval(
+                    &parse("concat([1, 2], [3, 4])"),
+                    &mut Environment::new(None)
+                )
+                .map(|o| o.to_string())
+        );
+    }
+
+    #[test]
+    fn test76() {
+        //type annotations
+        assert_integer("let a: int = 5; a", 5);
+        assert_array("let a: array = [1]; a", &[1]);
+        assert_string(r#" let s: string = "x"; s "#, "x");
+        assert_integer("let f: function = fn(x) { x }; f(3)", 3);
+        assert_integer(r#" let f: function = len; f("ab") "#, 2);
+        assert_integer(
+            "let add = fn(a, b) { a + b }; let g: function = partial(add, 1); g(2)",
+            3,
+        );
+        assert_error("let f: function = 1;", "expected function, got int");
+        assert_integer("let f = fn() { let a: int = 1; a }; f()", 1);
+        assert_integer("let a = 5; a", 5);
+
+        assert_error(r#" let a: int = "x"; "#, "expected int, got string");
+        assert_error("let a: float = 1;", "expected float, got int");
+        //nothing is bound on a mismatch
+        let mut env = Environment::new(None);
+        assert!(__eval_in("let a: bool = 1;", &mut env).is_err());
+        assert!(env.get("a").is_none());
+    }
+
+    #[test]
+    fn test77() {
+        assert_string(r#" help("len") "#, "len(l)");
+        assert_string(r#" help("print") "#, "print(args...)");
+        assert_string(r#" help("int") "#, "int(v, radix...)");
+        assert_string(r#" help("pi") "#, "pi: float");
+        assert_error(r#" help("foo") "#, "`foo` is not a built-in identifier");
+        assert_error("help(1)", "argument type mismatch");
+
+        let o = read_and_eval("help()").to_string();
+        let l: Vec<&str> = o.lines().collect();
+        assert!(l.contains(&"len(l)"));
+        assert!(l.contains(&"map(l, f)"));
+        assert!(l.contains(&"help(name...)"));
+        assert!(l.windows(2).all(|w| w[0] < w[1]));
+
+        //custom ones are listed too
+        let mut builtin = Builtin::new();
+        builtin.register_fn("add", |a: i64, b: i64| a + b);
+        let evaluator = Evaluator::with_builtin(builtin);
+        let o = evaluator
+            .eval(&parse("help()"), &mut Environment::new(None))
+            .unwrap();
+        assert!(o.to_string().lines().any(|s| s == "add(arg1, arg2)"));
+    }
+
+    #[test]
+    fn test78() {
+        let mut builtin = Builtin::new();
+        let names = builtin.names();
+        assert!(names.contains(&"len") && names.contains(&"pi"));
+        assert!(names.windows(2).all(|w| w[0] < w[1]));
+        assert!(builtin.get("len").is_some());
+        assert!(builtin.get("no_such_builtin").is_none());
+
+        //a custom built-in function which takes a function
+        builtin.register_function(
+            "twice",
+            BuiltinFunction::with_evaluator(
+                Rc::new(vec![
+                    IdentifierNode::new(Token::Ident("f".to_string())),
+                    IdentifierNode::new(Token::Ident("x".to_string())),
+                ]),
+                Rc::new(|evaluator: &Evaluator, env: &Environment| -> EvalResult {
+                    let f = env.get("f").unwrap();
+                    let x = env.get("x").unwrap().clone();
+                    let x = evaluator.call_function(f.as_ref(), vec![x], env)?;
+                    evaluator.call_function(f.as_ref(), vec![x], env)
+                }),
+            ),
+        );
+        assert!(builtin.names().contains(&"twice"));
+        let evaluator = Evaluator::with_builtin(builtin);
+        let eval = |s: &str| {
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+        assert_eq!(Ok("12".to_string()), eval("twice(fn(x) { x * 2 }, 3)"));
+        assert_eq!(Ok("twice(f, x)".to_string()), eval(r#" help("twice") "#));
+        assert_eq!(
+            Err("`twice` is a built-in identifier".to_string()),
+            eval("let twice = 1;")
+        );
+    }
+
+    #[test]
+    fn test79() {
+        assert_eq!("[1, 2, 3, 4, [5]]", run("flatten([[1, 2], [3], [4, [5]]])"));
+        assert_eq!(
+            "[1, 2, 3, 4, [5]]",
+            run("flatten([[1, 2], [3], [4, [5]]], 1)")
+        );
+        assert_eq!("[1, [2, [3]]]", run("flatten([1, [2, [3]]], 0)"));
+        assert_eq!("[1, 2, [3, [4]]]", run("flatten([[1, [2, [3, [4]]]]], 2)"));
+        assert_array("flatten([[1, [2, [3, [4]]]]], -1)", &[1, 2, 3, 4]);
+        assert_array("flatten([[1], [[2]]], 100)", &[1, 2]);
+        assert_eq!("[[], 1]", run("flatten([[], [[]], 1])"));
+        assert_array("flatten([])", &[]);
+        assert_eq!(
+            r#"[a, b, {k: [1]}]"#,
+            run(r#" flatten(["a", ["b", {"k": [1]}]]) "#)
+        );
+
+        assert_error("flatten(1)", "argument type mismatch");
+        assert_error("flatten([1], 1.)", "argument type mismatch");
+        assert_error("flatten([1], -2)", "invalid depth `-2`");
+        assert_error("flatten([1], 1, 2)", "argument number mismatch");
+
+        //deeply nested input
+        let mut a: Rc<dyn Object> = Rc::new(Array::new(vec![Rc::new(Int::new(1))]));
+        for i in 0..1000 {
+            a = Rc::new(Array::new(vec![Rc::new(Int::new(i)), a]));
+        }
+        let mut env = Environment::new(None);
+        env.set("a", a);
+        assert_eq!(
+            Ok("1001".to_string()),
+            __eval_in("len(flatten(a, -1))", &mut env).map(|o| o.to_string())
+        );
+        assert_eq!(
+            Ok("3".to_string()),
+            __eval_in("len(flatten(a, 1))", &mut env).map(|o| o.to_string())
+        );
+    }
+
+    #[test]
+    fn test80() {
+        let mut builtin = Builtin::new();
+        builtin.register_function(
+            "double",
+            BuiltinFunction::from_fn("double", 1, |args| {
+                match args[0].as_any().downcast_ref::<Int>() {
+                    None => Err("argument type mismatch".to_string()),
+                    Some(i) => Ok(Rc::new(Int::new(2 * i.value()))),
+                }
+            }),
+        );
+        builtin.register_function(
+            "first",
+            BuiltinFunction::from_fn("first", 2, |args| Ok(args[0].clone())),
+        );
+        let evaluator = Evaluator::with_builtin(builtin);
+        let eval = |s: &str| {
+            evaluator
+                .eval(&parse(s), &mut Environment::new(None))
+                .map(|o| o.to_string())
+        };
+        assert_eq!(Ok("double(arg1)".to_string()), eval(r#"help("double")"#));
+        assert_eq!(Ok("6".to_string()), eval("double(3)"));
+        assert_eq!(Ok("[2, 4, 6]".to_string()), eval("map([1, 2, 3], double)"));
+        assert_eq!(Ok("a".to_string()), eval(r#"first("a", 1)"#));
+        assert_eq!(
+            Err("`double`: argument type mismatch".to_string()),
+            eval(r#"double("a")"#)
+        );
+        assert!(eval("double(1, 2)")
+            .unwrap_err()
+            .contains("argument number mismatch"));
+    }
 }
@@ -0,0 +1,605 @@
+use itertools::Itertools;
+
+use super::ast::*;
+use super::lexer::Lexer;
+use super::parser::Parser;
+use super::token::Token;
+use super::util;
+
+//unit of indentation of a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    Tab,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub indent: Indent,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: Indent::Spaces(4),
+        }
+    }
+}
+
+impl FormatOptions {
+    //returns the leading whitespaces of a line at `depth`
+    fn indentation(&self, depth: usize) -> String {
+        match self.indent {
+            Indent::Spaces(n) => " ".repeat(n * depth),
+            Indent::Tab => "\t".repeat(depth),
+        }
+    }
+}
+
+//formats `source` in the canonical style
+//- one statement per line
+//- blocks are indented by four spaces (see `FormatOptions`)
+//- a single space around binary operators and after commas
+//- no redundant parentheses
+//- no trailing comma in an argument list or an array literal, and a trailing comma after every `match` arm
+//- a comment on the same line as a statement is preceded by a single space
+pub fn format_source(source: &str) -> Result<String, String> {
+    format_source_with(source, &FormatOptions::default())
+}
+
+//Comments are kept, though one in the middle of a statement (e.g. between the elements of an array literal) is moved
+// before the statement.
+pub fn format_source_with(source: &str, options: &FormatOptions) -> Result<String, String> {
+    let tokens = Lexer::with_comments(source).tokenize()?;
+    let root = Parser::new(tokens).parse().map_err(|e| e.to_string())?;
+    Ok(format_with(&root, options))
+}
+
+pub fn format(root: &RootNode) -> String {
+    format_with(root, &FormatOptions::default())
+}
+
+pub fn format_with(root: &RootNode, options: &FormatOptions) -> String {
+    let mut ret = format_statements(root.statements(), 0, options).join("\n");
+    if !ret.is_empty() {
+        ret.push('\n');
+    }
+    ret
+}
+
+//formats a function literal (e.g. to show a function object)
+pub fn format_function(parameters: &[IdentifierNode], body: &BlockExpressionNode) -> String {
+    format_function_at(parameters, body, 0, &FormatOptions::default())
+}
+
+fn format_function_at(
+    parameters: &[IdentifierNode],
+    body: &BlockExpressionNode,
+    depth: usize,
+    options: &FormatOptions,
+) -> String {
+    format!(
+        "fn({}) {}",
+        parameters.iter().map(|e| e.get_name()).join(", "),
+        format_block(body, depth, options)
+    )
+}
+
+//returns the formatted lines
+fn format_statements(
+    statements: &[Box<dyn StatementNode>],
+    depth: usize,
+    options: &FormatOptions,
+) -> Vec<String> {
+    let formatted = statements
+        .iter()
+        .map(|e| format_statement(e.as_ref(), depth, options))
+        .collect_vec();
+    let is_comment = |i: &usize| {
+        statements[*i]
+            .as_any()
+            .downcast_ref::<CommentNode>()
+            .is_some()
+    };
+    let mut ret: Vec<String> = vec![];
+    for (i, s) in formatted.iter().enumerate() {
+        let mut s = s.clone();
+        if let Some(n) = statements[i].as_any().downcast_ref::<CommentNode>() {
+            //stays at the end of the line of the previous statement
+            if n.trailing() {
+                if let Some(last) = ret.last_mut() {
+                    last.push(' ');
+                    last.push_str(&s);
+                    continue;
+                }
+            }
+        }
+        if let Some(n) = statements[i]
+            .as_any()
+            .downcast_ref::<ExpressionStatementNode>()
+        {
+            //comments don't affect the parsing
+            let next = (i + 1..statements.len()).find(|j| !is_comment(j));
+            if needs_semicolon(n, next.map(|j| &formatted[j])) {
+                s.push(';');
+            }
+        }
+        ret.push(format!("{}{}", options.indentation(depth), s));
+    }
+    ret
+}
+
+//A semicolon is needed after an expression statement unless it is the last one in the block (i.e. the value of the
+// block), as e.g. `f` and `(1)` on separate lines would be parsed as a function call `f(1)`.
+//A block-like statement such as `if` omits it as well if the next statement can't be parsed as its continuation.
+fn needs_semicolon(n: &ExpressionStatementNode, next: Option<&String>) -> bool {
+    let next = match next {
+        None => return false,
+        Some(next) => next,
+    };
+    let e = n.expression().as_any();
+    let is_block_like = e.downcast_ref::<IfExpressionNode>().is_some()
+        || e.downcast_ref::<MatchExpressionNode>().is_some()
+        || e.downcast_ref::<BlockExpressionNode>().is_some();
+    !is_block_like || next.starts_with(['(', '[', '-'])
+}
+
+fn format_statement(n: &dyn StatementNode, depth: usize, options: &FormatOptions) -> String {
+    if let Some(n) = n.as_any().downcast_ref::<LetStatementNode>() {
+        return format!(
+            "let {}{} = {};",
+            n.identifier().get_name(),
+            n.annotation()
+                .map(|t| format!(": {}", t))
+                .unwrap_or_default(),
+            format_expression(n.expression(), depth, options)
+        );
+    }
+    if let Some(n) = n.as_any().downcast_ref::<ReturnStatementNode>() {
+        return match n.expression() {
+            None => "return;".to_string(),
+            Some(e) => format!("return {};", format_expression(e.as_ref(), depth, options)),
+        };
+    }
+    if let Some(n) = n.as_any().downcast_ref::<ExpressionStatementNode>() {
+        return format_expression(n.expression(), depth, options);
+    }
+    if let Some(n) = n.as_any().downcast_ref::<CommentNode>() {
+        return format!("//{}", n.text());
+    }
+    unreachable!();
+}
+
+fn format_block(n: &BlockExpressionNode, depth: usize, options: &FormatOptions) -> String {
+    if n.statements().is_empty() {
+        return "{}".to_string();
+    }
+    format!(
+        "{{\n{}\n{}}}",
+        format_statements(n.statements(), depth + 1, options).join("\n"),
+        options.indentation(depth)
+    )
+}
+
+//binding power of an expression, which mirrors `Precedence` of the parser
+fn precedence(n: &dyn ExpressionNode) -> u8 {
+    if let Some(n) = n.as_any().downcast_ref::<BinaryExpressionNode>() {
+        return match n.operator() {
+            Token::Or => 1,
+            Token::And => 2,
+            Token::Eq | Token::NotEq | Token::Lt | Token::Gt | Token::LtEq | Token::GtEq => 3,
+            Token::In => 3,
+            Token::Plus | Token::Minus => 5,
+            _ => 6,
+        };
+    }
+    if n.as_any().downcast_ref::<RangeExpressionNode>().is_some() {
+        return 4;
+    }
+    if n.as_any().downcast_ref::<UnaryExpressionNode>().is_some() {
+        return 7;
+    }
+    8
+}
+
+//parenthesizes `n` if its precedence is lower than `min`
+fn format_operand(
+    n: &dyn ExpressionNode,
+    min: u8,
+    depth: usize,
+    options: &FormatOptions,
+) -> String {
+    let s = format_expression(n, depth, options);
+    if precedence(n) < min {
+        format!("({})", s)
+    } else {
+        s
+    }
+}
+
+fn format_operator(t: &Token) -> &'static str {
+    match t {
+        Token::Plus => "+",
+        Token::Minus => "-",
+        Token::Asterisk => "*",
+        Token::Slash => "/",
+        Token::Percent => "%",
+        Token::Power => "**",
+        Token::Invert => "!",
+        Token::Eq => "==",
+        Token::NotEq => "!=",
+        Token::Lt => "<",
+        Token::Gt => ">",
+        Token::LtEq => "<=",
+        Token::GtEq => ">=",
+        Token::And => "&&",
+        Token::Or => "||",
+        Token::In => "in",
+        _ => unreachable!(),
+    }
+}
+
+//escapes the characters which cannot appear literally in a literal delimited by `quote`
+fn format_character(c: char, quote: char) -> String {
+    match c {
+        '\\' | '\0' | '\n' | '\r' | '\t' => util::escape_character(c, quote),
+        c if c == quote => util::escape_character(c, quote),
+        c => c.to_string(),
+    }
+}
+
+fn format_expression(n: &dyn ExpressionNode, depth: usize, options: &FormatOptions) -> String {
+    let e = n.as_any();
+
+    if let Some(n) = e.downcast_ref::<BinaryExpressionNode>() {
+        //binary operators are left-associative
+        let p = precedence(n);
+        return format!(
+            "{} {} {}",
+            format_operand(n.left(), p, depth, options),
+            format_operator(n.operator()),
+            format_operand(n.right(), p + 1, depth, options)
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<RangeExpressionNode>() {
+        let p = precedence(n);
+        return format!(
+            "{}{}{}",
+            format_operand(n.start(), p, depth, options),
+            if n.inclusive() { "..=" } else { ".." },
+            format_operand(n.end(), p + 1, depth, options)
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<UnaryExpressionNode>() {
+        return format!(
+            "{}{}",
+            format_operator(n.operator()),
+            format_operand(n.expression(), precedence(n), depth, options)
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<IndexExpressionNode>() {
+        return format!(
+            "{}[{}]",
+            format_operand(n.array(), 8, depth, options),
+            format_expression(n.index(), depth, options)
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<FieldExpressionNode>() {
+        let record = n.record().as_any();
+        //`1.x` would be lexed as `1.` followed by `x`
+        let record = if record.is::<IntegerLiteralNode>()
+            || record.is::<BigIntegerLiteralNode>()
+            || record.is::<FloatLiteralNode>()
+        {
+            format!("({})", format_expression(n.record(), depth, options))
+        } else {
+            format_operand(n.record(), 8, depth, options)
+        };
+        return format!("{}.{}", record, n.field().get_name());
+    }
+
+    if let Some(n) = e.downcast_ref::<CallExpressionNode>() {
+        return format!(
+            "{}({})",
+            format_operand(n.function(), 8, depth, options),
+            n.arguments()
+                .iter()
+                .map(|e| format_expression(e.as_ref(), depth, options))
+                .join(", ")
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<IfExpressionNode>() {
+        let mut s = format!(
+            "if ({}) {}",
+            format_expression(n.condition(), depth, options),
+            format_block(n.if_value(), depth, options)
+        );
+        if let Some(else_value) = n.else_value() {
+            s += &format!(" else {}", format_block(else_value, depth, options));
+        }
+        return s;
+    }
+
+    if let Some(n) = e.downcast_ref::<MatchExpressionNode>() {
+        let mut s = format!(
+            "match {} {{\n",
+            format_expression(n.scrutinee(), depth, options)
+        );
+        for arm in n.arms() {
+            let pattern = match arm.pattern() {
+                MatchPattern::Wildcard => "_".to_string(),
+                MatchPattern::Type(t) => t.clone(),
+                //An identifier at the head of a pattern is regarded as a type name.
+                MatchPattern::Value(v) => {
+                    let v = format_expression(v.as_ref(), depth + 1, options);
+                    if v.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                        format!("({})", v)
+                    } else {
+                        v
+                    }
+                }
+            };
+            s += &format!(
+                "{}{} => {},\n",
+                options.indentation(depth + 1),
+                pattern,
+                format_expression(arm.value(), depth + 1, options)
+            );
+        }
+        s += &format!("{}}}", options.indentation(depth));
+        return s;
+    }
+
+    if let Some(n) = e.downcast_ref::<BlockExpressionNode>() {
+        return format_block(n, depth, options);
+    }
+
+    if let Some(n) = e.downcast_ref::<FunctionLiteralNode>() {
+        return format_function_at(n.parameters(), n.body(), depth, options);
+    }
+
+    if let Some(n) = e.downcast_ref::<ArrayLiteralNode>() {
+        return format!(
+            "[{}]",
+            n.elements()
+                .iter()
+                .map(|e| format_expression(e.as_ref(), depth, options))
+                .join(", ")
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<HashLiteralNode>() {
+        if n.pairs().is_empty() {
+            return "{:}".to_string();
+        }
+        return format!(
+            "{{{}}}",
+            n.pairs()
+                .iter()
+                .map(|(k, v)| format!(
+                    "{}: {}",
+                    format_expression(k.as_ref(), depth, options),
+                    format_expression(v.as_ref(), depth, options)
+                ))
+                .join(", ")
+        );
+    }
+
+    if let Some(n) = e.downcast_ref::<IdentifierNode>() {
+        return n.get_name().to_string();
+    }
+
+    if let Some(n) = e.downcast_ref::<IntegerLiteralNode>() {
+        return n.get_value().to_string();
+    }
+
+    if let Some(n) = e.downcast_ref::<BigIntegerLiteralNode>() {
+        return n.get_value().to_string();
+    }
+
+    if let Some(n) = e.downcast_ref::<FloatLiteralNode>() {
+        //`Display` of `f64` omits the fractional part of e.g. `3.0`, which would make it an integer literal
+        let s = n.get_value().to_string();
+        return if s.contains('.') { s } else { s + ".0" };
+    }
+
+    if let Some(n) = e.downcast_ref::<BooleanLiteralNode>() {
+        return n.get_value().to_string();
+    }
+
+    if let Some(n) = e.downcast_ref::<CharacterLiteralNode>() {
+        return format!("'{}'", format_character(n.get_value(), '\''));
+    }
+
+    if let Some(n) = e.downcast_ref::<StringLiteralNode>() {
+        return format!(
+            "\"{}\"",
+            n.get_value()
+                .chars()
+                .map(|c| format_character(c, '"'))
+                .join("")
+        );
+    }
+
+    unreachable!();
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::program::Program;
+    use super::*;
+
+    #[test]
+    fn test_golden() {
+        let input = include_str!("../testdata/fmt/messy.mk");
+        let expected = include_str!("../testdata/fmt/messy.golden.mk");
+        assert_eq!(expected, format_source(input).unwrap());
+    }
+
+    #[test]
+    fn test_idempotence() {
+        let input = include_str!("../testdata/fmt/messy.mk");
+        let once = format_source(input).unwrap();
+        assert_eq!(once, format_source(&once).unwrap());
+
+        //formatting doesn't change the meaning
+        assert_eq!(
+            format!("{:#?}", Program::parse(input).unwrap().root()),
+            format!("{:#?}", Program::parse(&once).unwrap().root())
+        );
+
+        let expected = include_str!("../testdata/fmt/messy.golden.mk");
+        assert_eq!(expected, format_source(expected).unwrap());
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let test = |input: &str, expected: &str| {
+            assert_eq!(format!("{}\n", expected), format_source(input).unwrap());
+        };
+        test("((1 + 2)) * 3", "(1 + 2) * 3");
+        test("1 + (2 * 3)", "1 + 2 * 3");
+        test("(1 - 2) - 3", "1 - 2 - 3");
+        test("1 - (2 - 3)", "1 - (2 - 3)");
+        test("-(1 + 2)", "-(1 + 2)");
+        test("(-f)(1)", "(-f)(1)");
+        test("(a + b)[0]", "(a + b)[0]");
+        test("(1..3) in r", "1..3 in r");
+        test("1..(2 + 3)", "1..2 + 3");
+        test("(a || b) && c", "(a || b) && c");
+        test("3.0 + 1.5", "3.0 + 1.5");
+        test(r#" "a\tb\"'" + '\'' "#, r#""a\tb\"'" + '\''"#);
+        test("f(1, 2,)", "f(1, 2)");
+        test("fn(){}", "fn() {}");
+        test(r#"{ "a" : 1 + 2, 3: {:}, }"#, r#"{"a": 1 + 2, 3: {:}}"#);
+        test("{ .x: 1, k: 2 }", r#"{"x": 1, k: 2}"#);
+        test("p . x . y", "p.x.y");
+        test("let a : int=1;", "let a: int = 1;");
+        test("(a + b).x", "(a + b).x");
+        test("(-a).x", "(-a).x");
+        test("(1).x", "(1).x");
+        test("f().x(1)", "f().x(1)");
+        test(
+            "match x { (-1) => 1, (y) => 2 }",
+            "match x {\n    -1 => 1,\n    (y) => 2,\n}",
+        );
+    }
+
+    #[test]
+    fn test_indent() {
+        let program = Program::parse(
+            "let f = fn(x) { if (x) { match x { 1 => { 2 }, _ => 3 } } else { {} } }; f(1)",
+        )
+        .unwrap();
+        let format = |indent: Indent| format_with(program.root(), &FormatOptions { indent });
+
+        let expected = r#"let f = fn(x) {
+    if (x) {
+        match x {
+            1 => {
+                2
+            },
+            _ => 3,
+        }
+    } else {
+        {}
+    }
+};
+f(1)
+"#;
+        assert_eq!(expected, format(Indent::Spaces(4)));
+        assert_eq!(
+            expected,
+            format_with(program.root(), &FormatOptions::default())
+        );
+        assert_eq!(expected, format(Indent::Tab).replace('\t', "    "));
+        assert_eq!(expected.replace("    ", "  "), format(Indent::Spaces(2)));
+        assert!(format(Indent::Tab).contains("\n\t\t\t1 => {\n\t\t\t\t2\n\t\t\t},\n"));
+
+        //the result can be parsed back and re-formatted with another width
+        let tab = format(Indent::Tab);
+        assert_eq!(
+            format(Indent::Spaces(2)),
+            format_source_with(
+                &tab,
+                &FormatOptions {
+                    indent: Indent::Spaces(2)
+                }
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_comment() {
+        let input = r#"
+//header
+let a=1;   // one
+let f=fn(x){   //after the brace
+  // standalone
+  if(x){x}else{   // else
+  0}
+  //before the value
+  x*2   //the value
+  //dangling
+};
+let l=[1, //inner
+2];
+f(a) //no semicolon is added
+//end
+"#;
+        let expected = r#"//header
+let a = 1; // one
+let f = fn(x) {
+    //after the brace
+    // standalone
+    if (x) {
+        x
+    } else {
+        // else
+        0
+    }
+    //before the value
+    x * 2 //the value
+    //dangling
+};
+//inner
+let l = [1, 2];
+f(a) //no semicolon is added
+//end
+"#;
+        let formatted = format_source(input).unwrap();
+        assert_eq!(expected, formatted);
+        assert_eq!(formatted, format_source(&formatted).unwrap());
+
+        //comments don't change the meaning
+        assert_eq!(
+            format!("{:#?}", Program::parse(input).unwrap().root()),
+            format!("{:#?}", Program::parse(&formatted).unwrap().root())
+        );
+
+        assert_eq!("//a\n", format_source("//a").unwrap());
+        assert_eq!("{\n    //a\n}\n", format_source("{ //a\n }").unwrap());
+        assert_eq!(
+            "if (x) {\n\t//a\n}\n",
+            format_source_with(
+                "if (x) {//a\n}",
+                &FormatOptions {
+                    indent: Indent::Tab
+                }
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_error() {
+        assert!(format_source("let").is_err());
+    }
+}
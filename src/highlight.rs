@@ -0,0 +1,154 @@
+use super::lexer::{Lexer, Span};
+use super::token::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Keyword, //including `true` and `false`
+    Identifier,
+    Number,
+    String,
+    Char,
+    Operator,
+    Punctuation, //parentheses, braces, brackets, `,`, `:`, `;` and `.`
+    Comment,
+    Error,
+}
+
+//classifies the tokens in `source` for syntax highlighting (e.g. by an editor)
+//This never fails; a part which cannot be tokenized is classified as `TokenClass::Error` and the rest of the source is
+// classified as usual.
+//Whitespace is not included in the result.
+pub fn highlight(source: &str) -> Vec<(Span, TokenClass)> {
+    let mut lexer = Lexer::with_comments(source);
+    let mut ret = vec![];
+    loop {
+        match lexer.get_next_spanned_token() {
+            (_, Ok(Token::Eof)) => break,
+            (span, Ok(token)) => ret.push((span, classify(&token))),
+            //the lexer resumes after the erroneous part
+            (span, Err(_)) if !span.is_empty() => ret.push((span, TokenClass::Error)),
+            //gives up if the lexer cannot make progress
+            (span, Err(_)) => {
+                ret.push((span.start..source.len(), TokenClass::Error));
+                break;
+            }
+        }
+    }
+    ret
+}
+
+fn classify(token: &Token) -> TokenClass {
+    match token {
+        Token::Eof => unreachable!(),
+        Token::Function
+        | Token::Let
+        | Token::Return
+        | Token::True
+        | Token::False
+        | Token::If
+        | Token::Else
+        | Token::In
+        | Token::Match => TokenClass::Keyword,
+        Token::Ident(_) => TokenClass::Identifier,
+        Token::Int(_) | Token::BigInt(_) | Token::Float(_) => TokenClass::Number,
+        Token::String(_) => TokenClass::String,
+        Token::Char(_) => TokenClass::Char,
+        Token::Comment { .. } => TokenClass::Comment,
+        Token::Comma
+        | Token::Colon
+        | Token::Semicolon
+        | Token::Lparen
+        | Token::Rparen
+        | Token::Lbrace
+        | Token::Rbrace
+        | Token::Lbracket
+        | Token::Rbracket
+        | Token::Dot => TokenClass::Punctuation,
+        _ => TokenClass::Operator,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn test(source: &str, expected: &[(&str, TokenClass)]) {
+        let actual: Vec<(&str, TokenClass)> = highlight(source)
+            .into_iter()
+            .map(|(span, class)| (&source[span], class))
+            .collect();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_highlight() {
+        use TokenClass::*;
+
+        test(
+            "let s = \"é\" + 'a';\nif (x >= 1.5) { f(s) } \\\n else { true }",
+            &[
+                ("let", Keyword),
+                ("s", Identifier),
+                ("=", Operator),
+                ("\"é\"", String),
+                ("+", Operator),
+                ("'a'", Char),
+                (";", Punctuation),
+                ("if", Keyword),
+                ("(", Punctuation),
+                ("x", Identifier),
+                (">=", Operator),
+                ("1.5", Number),
+                (")", Punctuation),
+                ("{", Punctuation),
+                ("f", Identifier),
+                ("(", Punctuation),
+                ("s", Identifier),
+                (")", Punctuation),
+                ("}", Punctuation),
+                ("else", Keyword),
+                ("{", Punctuation),
+                ("true", Keyword),
+                ("}", Punctuation),
+            ],
+        );
+
+        //a broken string literal consumes the rest of the source
+        test(
+            "let s = \"abc\n1",
+            &[
+                ("let", Keyword),
+                ("s", Identifier),
+                ("=", Operator),
+                ("\"abc\n1", Error),
+            ],
+        );
+
+        //the classification resumes after an error
+        test(
+            "a @ 'bc' & \\ b",
+            &[
+                ("a", Identifier),
+                ("@", Error),
+                ("'bc'", Error),
+                ("& ", Error),
+                ("\\ ", Error),
+                ("b", Identifier),
+            ],
+        );
+
+        test(
+            "a / b // c / d\r\n//\n",
+            &[
+                ("a", Identifier),
+                ("/", Operator),
+                ("b", Identifier),
+                ("// c / d", Comment),
+                ("//", Comment),
+            ],
+        );
+
+        test("", &[]);
+    }
+}
@@ -1,21 +1,58 @@
 use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
 
 use super::token::{self, Token};
 use super::util;
 
 pub type LexerResult<T> = Result<T, String>;
 
+//byte range in the source
+pub type Span = Range<usize>;
+
 pub struct Lexer {
     queue: VecDeque<char>,
+    offsets: Vec<usize>, //byte offset of each character, followed by the length of the input
+    keep_comments: bool,
+    line_break_seen: bool, //whether a line break has been eaten since the previous token
+    //position right after the previous token if it can end an operand (e.g. an identifier or `)`)
+    //This makes `p.0` lexed as `p`, `.` and `0` (which is a parse error) rather than `p` and `.0`.
+    operand_end: Option<usize>,
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Self {
         Lexer {
             queue: input.to_string().chars().collect(),
+            offsets: input
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([input.len()])
+                .collect(),
+            keep_comments: false,
+            line_break_seen: true,
+            operand_end: None,
+        }
+    }
+
+    //same as `new()` but comments are returned as `Token::Comment` instead of being skipped as whitespace
+    //This is for the tools which reproduce the source (e.g. the formatter).
+    pub fn with_comments(input: &str) -> Self {
+        Lexer {
+            keep_comments: true,
+            ..Self::new(input)
         }
     }
 
+    //index of the next character to read
+    fn position(&self) -> usize {
+        self.offsets.len() - 1 - self.queue.len()
+    }
+
+    //byte range from the `start`-th character to the current position
+    fn span(&self, start: usize) -> Span {
+        self.offsets[start]..self.offsets[self.position()]
+    }
+
     fn read_identifier(&mut self) -> String {
         let mut l = vec![];
         while !self.queue.is_empty() && util::is_identifier(self.queue[0]) {
@@ -27,16 +64,36 @@ impl Lexer {
     fn read_number(&mut self) -> LexerResult<String> {
         let mut l = vec![];
         while !self.queue.is_empty() && util::is_digit(self.queue[0]) {
+            //`1..10` is a range rather than a malformed number
+            if self.is_range_operator_next() {
+                break;
+            }
             l.push(self.queue.pop_front().unwrap());
         }
-        if l.iter().filter(|c| (**c == '.')).count() >= 2 {
+        if l.iter().filter(|c| **c == '.').count() >= 2 {
             return Err("two or more dots found in a number literal".to_string());
-        } else if (l.len() == 1) && (l[0] == '.') {
-            return Err("isolated `.` found".to_string());
         }
         Ok(l.into_iter().collect())
     }
 
+    fn is_range_operator_next(&self) -> bool {
+        (self.queue.len() >= 2) && (self.queue[0] == '.') && (self.queue[1] == '.')
+    }
+
+    //`..` or `..=`
+    fn read_range_operator(&mut self) -> LexerResult<String> {
+        self.queue.pop_front().unwrap();
+        self.queue.pop_front().unwrap();
+        match self.queue.front() {
+            Some('.') => Err("`...` is not a valid operator".to_string()),
+            Some('=') => {
+                self.queue.pop_front().unwrap();
+                Ok("..=".to_string())
+            }
+            _ => Ok("..".to_string()),
+        }
+    }
+
     fn read_string(&mut self) -> LexerResult<String> {
         let mut l = vec![self.queue.pop_front().unwrap()];
         assert_eq!('"', l[0]);
@@ -59,6 +116,14 @@ impl Lexer {
                         Some(c) => c,
                     }
                 }
+                //A line break in a literal is always `\n` regardless of the line ending of the source (`\r\n` or `\r`).
+                //Write `\r` to include a carriage return.
+                '\r' => {
+                    if self.queue.front() == Some(&'\n') {
+                        self.queue.pop_front().unwrap();
+                    }
+                    '\n'
+                }
                 c => c,
             };
             l.push(c);
@@ -71,6 +136,7 @@ impl Lexer {
         if self.queue.is_empty() {
             return Err("unexpected end of a character literal".to_string());
         } else if self.queue[0] == '\'' {
+            self.queue.pop_front().unwrap();
             return Err("character literal is empty".to_string());
         }
         let ret = match self.queue.pop_front().unwrap() {
@@ -91,21 +157,118 @@ impl Lexer {
         if self.queue.is_empty() {
             return Err("unexpected end of a character literal".to_string());
         } else if self.queue[0] != '\'' {
+            //skips the rest of the literal so that the lexer can resume after it
+            while let Some(c) = self.queue.pop_front() {
+                if (c == '\'') || (c == '\n') || (c == '\r') {
+                    break;
+                }
+            }
             return Err("character literal can contain only one character".to_string());
         }
         self.queue.pop_front().unwrap();
         Ok(ret)
     }
 
+    //`\` at the end of a line joins the line with the next one
+    //This is purely cosmetic as a newline is just whitespace between tokens.
+    //A line ends with `\n`, `\r\n` or `\r`.
+    fn eat_line_continuation(&mut self) -> LexerResult<()> {
+        assert_eq!('\\', self.queue.pop_front().unwrap());
+        match self.queue.pop_front() {
+            Some('\n') => Ok(()),
+            Some('\r') => {
+                if self.queue.front() == Some(&'\n') {
+                    self.queue.pop_front().unwrap();
+                }
+                Ok(())
+            }
+            _ => Err("`\\` is allowed only at the end of a line".to_string()),
+        }
+    }
+
     pub fn get_next_token(&mut self) -> LexerResult<Token> {
-        //eats whitespace
-        while !self.queue.is_empty() && self.queue[0].is_ascii_whitespace() {
-            self.queue.pop_front().unwrap();
+        self.get_next_spanned_token().1
+    }
+
+    //same as `get_next_token()` but also returns the span of the token
+    //On an error, the span covers the characters consumed while reading the erroneous token.
+    //The span of `Token::Eof` is the empty range at the end of the input.
+    pub fn get_next_spanned_token(&mut self) -> (Span, LexerResult<Token>) {
+        //eats whitespace (and comments unless `keep_comments` is set)
+        loop {
+            while !self.queue.is_empty() && self.queue[0].is_ascii_whitespace() {
+                let c = self.queue.pop_front().unwrap();
+                if (c == '\n') || (c == '\r') {
+                    self.line_break_seen = true;
+                }
+            }
+            if self.is_comment_next() {
+                let start = self.position();
+                let text = self.read_comment();
+                if self.keep_comments {
+                    let trailing = !self.line_break_seen;
+                    self.line_break_seen = false;
+                    return (self.span(start), Ok(Token::Comment { text, trailing }));
+                }
+                continue;
+            }
+            if self.queue.front() != Some(&'\\') {
+                break;
+            }
+            let start = self.position();
+            if let Err(e) = self.eat_line_continuation() {
+                return (self.span(start), Err(e));
+            }
+        }
+        self.line_break_seen = false;
+        let start = self.position();
+        let token = self.read_token();
+        self.operand_end = match token {
+            Ok(Token::Ident(_)) | Ok(Token::String(_)) | Ok(Token::Char(_)) | Ok(Token::Rparen)
+            | Ok(Token::Rbracket) | Ok(Token::Rbrace) => Some(self.position()),
+            _ => None,
+        };
+        (self.span(start), token)
+    }
+
+    fn is_comment_next(&self) -> bool {
+        (self.queue.len() >= 2) && (self.queue[0] == '/') && (self.queue[1] == '/')
+    }
+
+    //`//` up to (but not including) the end of the line
+    //Returns the text following `//` without trailing whitespace.
+    fn read_comment(&mut self) -> String {
+        self.queue.pop_front().unwrap();
+        self.queue.pop_front().unwrap();
+        let mut l = vec![];
+        while !self.queue.is_empty() && (self.queue[0] != '\n') && (self.queue[0] != '\r') {
+            l.push(self.queue.pop_front().unwrap());
         }
+        l.into_iter().collect::<String>().trim_end().to_string()
+    }
+
+    //reads a token which starts at the current position (i.e. whitespace has already been eaten)
+    fn read_token(&mut self) -> LexerResult<Token> {
         if self.queue.is_empty() {
             return Ok(Token::Eof);
         }
+        //control characters other than whitespace (e.g. `\0`) are never valid outside a literal
+        if self.queue[0].is_control() {
+            let c = self.queue.pop_front().unwrap();
+            return Err(format!(
+                "unexpected control character `{}`",
+                c.escape_default()
+            ));
+        }
         let sequence: String = match self.queue[0] {
+            '.' if self.is_range_operator_next() => self.read_range_operator()?,
+            //field access (e.g. `p.x`) unless it starts a number literal (e.g. `.5`)
+            '.' if !self.queue.get(1).is_some_and(|c| c.is_ascii_digit())
+                || (self.operand_end == Some(self.position())) =>
+            {
+                self.queue.pop_front().unwrap();
+                ".".to_string()
+            }
             c if util::is_digit(c) => self.read_number()?,
             c if util::is_identifier(c) => self.read_identifier(), //this includes keywords such as `if`
             '"' => self.read_string()?,
@@ -123,6 +286,10 @@ impl Lexer {
                 ]);
                 let cur = self.queue.pop_front().unwrap();
                 let ret = match c {
+                    '=' if (self.queue.front() == Some(&'>')) => {
+                        self.queue.pop_front().unwrap();
+                        "=>".to_string()
+                    }
                     '=' | '!' | '*' | '>' | '<' => {
                         if self.queue.is_empty() {
                             c.to_string()
@@ -154,9 +321,49 @@ impl Lexer {
         };
         token::lookup_token(&sequence)
     }
+
+    //reads all the tokens
+    //The last element of the returned vector is always `Token::Eof`.
+    pub fn tokenize(&mut self) -> LexerResult<Vec<Token>> {
+        let mut v = vec![];
+        loop {
+            let token = self.get_next_token()?;
+            if token == Token::Eof {
+                break;
+            }
+            v.push(token);
+        }
+        v.push(Token::Eof);
+        Ok(v)
+    }
+
+    //similar to `tokenize()` but doesn't stop at the first error
+    //The characters which caused an error are skipped, and all the errors are collected.
+    //This is useful for editor integrations which show all the errors at once.
+    pub fn tokenize_recovering(&mut self) -> (Vec<Token>, Vec<String>) {
+        let mut v = vec![];
+        let mut errors = vec![];
+        loop {
+            let len = self.queue.len();
+            match self.get_next_token() {
+                Ok(Token::Eof) => break,
+                Ok(token) => v.push(token),
+                Err(e) => {
+                    errors.push(e);
+                    //guarantees the progress
+                    if self.queue.len() == len {
+                        self.queue.pop_front().unwrap();
+                    }
+                }
+            }
+        }
+        v.push(Token::Eof);
+        (v, errors)
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::approx_constant)]
 mod tests {
 
     use super::*;
@@ -175,9 +382,9 @@ mod tests {
 
     fn test(input: &str, expected: &[LexerResult<Token>]) {
         let mut lexer = Lexer::new(input);
-        for i in 0..expected.len() {
+        for (i, expected) in expected.iter().enumerate() {
             println!("i = {}", i);
-            assert_eq!(expected[i], lexer.get_next_token());
+            assert_eq!(*expected, lexer.get_next_token());
         }
     }
 
@@ -220,7 +427,7 @@ mod tests {
             . 1.2.3 1.2.3.4
         "#;
         let expected = vec![
-            Err("isolated `.` found".to_string()),
+            Ok(Token::Dot),
             Err("two or more dots found in a number literal".to_string()),
             Err("two or more dots found in a number literal".to_string()),
             Ok(Token::Eof),
@@ -228,6 +435,29 @@ mod tests {
         test(input, &expected);
     }
 
+    #[test]
+    // #[ignore]
+    fn test_float_03() {
+        //`.` right after an operand is a field access even if a digit follows
+        let input = r#"
+            p.0 a .5 ).5 +.5
+        "#;
+        let expected = vec![
+            Ok(Token::Ident("p".to_string())),
+            Ok(Token::Dot),
+            Ok(Token::Int(0)),
+            Ok(Token::Ident("a".to_string())),
+            Ok(Token::Float(0.5)),
+            Ok(Token::Rparen),
+            Ok(Token::Dot),
+            Ok(Token::Int(5)),
+            Ok(Token::Plus),
+            Ok(Token::Float(0.5)),
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+    }
+
     #[test]
     // #[ignore]
     fn test_identifier() {
@@ -360,7 +590,7 @@ mod tests {
     // #[ignore]
     fn test_keywords() {
         let input = r#"
-            true false fn let return if else
+            true false fn let return if else in match
         "#;
         let expected = vec![
             Ok(Token::True),
@@ -370,6 +600,8 @@ mod tests {
             Ok(Token::Return),
             Ok(Token::If),
             Ok(Token::Else),
+            Ok(Token::In),
+            Ok(Token::Match),
             Ok(Token::Eof),
         ];
         test(input, &expected);
@@ -379,7 +611,7 @@ mod tests {
     // #[ignore]
     fn test_operators_01() {
         let input = r#"
-            = + - * / % ** ! == != < > <= >= && || , ; () { } [ ]
+            = + - * / % ** ! == != < > <= >= && || , : ; () { } [ ] =>
         "#;
         let expected = vec![
             Ok(Token::Assign),
@@ -399,6 +631,7 @@ mod tests {
             Ok(Token::And),
             Ok(Token::Or),
             Ok(Token::Comma),
+            Ok(Token::Colon),
             Ok(Token::Semicolon),
             Ok(Token::Lparen),
             Ok(Token::Rparen),
@@ -406,6 +639,7 @@ mod tests {
             Ok(Token::Rbrace),
             Ok(Token::Lbracket),
             Ok(Token::Rbracket),
+            Ok(Token::FatArrow),
             Ok(Token::Eof),
         ];
         test(input, &expected);
@@ -435,6 +669,132 @@ mod tests {
         test(input, &expected);
     }
 
+    #[test]
+    fn test_unexpected_characters() {
+        let input = "@ # $ あ";
+        let expected = vec![
+            Err("unexpected character `@`".to_string()),
+            Err("unexpected character `#`".to_string()),
+            Err("unexpected character `$`".to_string()),
+            Err("unexpected character `あ`".to_string()),
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+
+        let input = "1 \u{1} \u{7f}\u{0}2 '\u{1}'";
+        let expected = vec![
+            Ok(Token::Int(1)),
+            Err("unexpected control character `\\u{1}`".to_string()),
+            Err("unexpected control character `\\u{7f}`".to_string()),
+            Err("unexpected control character `\\u{0}`".to_string()),
+            Ok(Token::Int(2)),
+            Ok(Token::Char('\u{1}')), //allowed inside a literal
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        let input = "let a = 1 + \\\n    2 \\\r\n\\\n* 3;";
+        let expected = vec![
+            Ok(Token::Let),
+            Ok(Token::Ident("a".to_string())),
+            Ok(Token::Assign),
+            Ok(Token::Int(1)),
+            Ok(Token::Plus),
+            Ok(Token::Int(2)),
+            Ok(Token::Asterisk),
+            Ok(Token::Int(3)),
+            Ok(Token::Semicolon),
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+
+        let input = "1 \\ 2 \\";
+        let expected = vec![
+            Ok(Token::Int(1)),
+            Err("`\\` is allowed only at the end of a line".to_string()),
+            Ok(Token::Int(2)),
+            Err("`\\` is allowed only at the end of a line".to_string()),
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+
+        //inside a literal
+        let input = r#" "a\\" "#;
+        let expected = vec![Ok(Token::String("a\\".to_string())), Ok(Token::Eof)];
+        test(input, &expected);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_comment() {
+        let comment = |text: &str, trailing: bool| {
+            Ok(Token::Comment {
+                text: text.to_string(),
+                trailing,
+            })
+        };
+        let input = "//first\r\nlet a = 1 / 2; // second  \n  // third\r\n\"//\" //";
+
+        //skipped by default
+        let expected = vec![
+            Ok(Token::Let),
+            Ok(Token::Ident("a".to_string())),
+            Ok(Token::Assign),
+            Ok(Token::Int(1)),
+            Ok(Token::Slash),
+            Ok(Token::Int(2)),
+            Ok(Token::Semicolon),
+            Ok(Token::String("//".to_string())),
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+
+        let mut lexer = Lexer::with_comments(input);
+        let expected = vec![
+            comment("first", false),
+            Ok(Token::Let),
+            Ok(Token::Ident("a".to_string())),
+            Ok(Token::Assign),
+            Ok(Token::Int(1)),
+            Ok(Token::Slash),
+            Ok(Token::Int(2)),
+            Ok(Token::Semicolon),
+            comment(" second", true),
+            comment(" third", false),
+            Ok(Token::String("//".to_string())),
+            comment("", true),
+            Ok(Token::Eof),
+        ];
+        for expected in expected {
+            assert_eq!(expected, lexer.get_next_token());
+        }
+
+        //a line continuation doesn't break the line
+        let mut lexer = Lexer::with_comments("1 \\\n//a");
+        assert_eq!(Ok(Token::Int(1)), lexer.get_next_token());
+        assert_eq!(comment("a", true), lexer.get_next_token());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let lf = "let s = \"a\nb\n\";\nlet c = '\\r'; \\\n1 +\n2\n";
+        let crlf = lf.replace('\n', "\r\n");
+        let cr = lf.replace('\n', "\r");
+        let expected = Lexer::new(lf).tokenize().unwrap();
+        assert_eq!(14, expected.len());
+        assert_eq!(Token::String("a\nb\n".to_string()), expected[3]);
+        assert_eq!(expected, Lexer::new(&crlf).tokenize().unwrap());
+        assert_eq!(expected, Lexer::new(&cr).tokenize().unwrap());
+
+        //an escaped carriage return is kept
+        let input = "\"a\\r\r\nb\"";
+        let expected = vec![Ok(Token::String("a\r\nb".to_string())), Ok(Token::Eof)];
+        test(input, &expected);
+    }
+
     #[test]
     fn test_misc_01() {
         let input = r#"
@@ -478,4 +838,100 @@ mod tests {
         ];
         test(input, &expected);
     }
+
+    #[test]
+    fn test_range_operators() {
+        let input = r#"
+            1..10 0..=n 1.5..2. a..b
+        "#;
+        let expected = vec![
+            Ok(Token::Int(1)),
+            Ok(Token::DotDot),
+            Ok(Token::Int(10)),
+            Ok(Token::Int(0)),
+            Ok(Token::DotDotEq),
+            Ok(Token::Ident("n".to_string())),
+            Ok(Token::Float(1.5)),
+            Ok(Token::DotDot),
+            Ok(Token::Float(2.0)),
+            Ok(Token::Ident("a".to_string())),
+            Ok(Token::DotDot),
+            Ok(Token::Ident("b".to_string())),
+            Ok(Token::Eof),
+        ];
+        test(input, &expected);
+
+        let input = r#"
+            1...3
+        "#;
+        let expected = vec![
+            Ok(Token::Int(1)),
+            Err("`...` is not a valid operator".to_string()),
+        ];
+        test(input, &expected);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let input = r#"
+            let a = [1];
+        "#;
+        let expected = vec![
+            Token::Let,
+            Token::Ident("a".to_string()),
+            Token::Assign,
+            Token::Lbracket,
+            Token::Int(1),
+            Token::Rbracket,
+            Token::Semicolon,
+            Token::Eof,
+        ];
+        assert_eq!(Ok(expected), Lexer::new(input).tokenize());
+
+        let input = r#"
+            1 & 2 'xy'
+        "#;
+        assert_eq!(
+            Err("`&&` expected but not found".to_string()),
+            Lexer::new(input).tokenize()
+        );
+    }
+
+    #[test]
+    fn test_tokenize_recovering() {
+        let input = r#"
+            let a = 1 & 2; let b = 'xy'; '' 1.2.3 b
+        "#;
+        let (tokens, errors) = Lexer::new(input).tokenize_recovering();
+        assert_eq!(
+            vec![
+                Token::Let,
+                Token::Ident("a".to_string()),
+                Token::Assign,
+                Token::Int(1),
+                Token::Int(2),
+                Token::Semicolon,
+                Token::Let,
+                Token::Ident("b".to_string()),
+                Token::Assign,
+                Token::Semicolon,
+                Token::Ident("b".to_string()),
+                Token::Eof,
+            ],
+            tokens
+        );
+        assert_eq!(
+            vec![
+                "`&&` expected but not found",
+                "character literal can contain only one character",
+                "character literal is empty",
+                "two or more dots found in a number literal",
+            ],
+            errors
+        );
+
+        let (tokens, errors) = Lexer::new("1").tokenize_recovering();
+        assert_eq!(vec![Token::Int(1), Token::Eof], tokens);
+        assert!(errors.is_empty());
+    }
 }
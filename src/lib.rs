@@ -1,11 +1,166 @@
+pub mod analysis;
 pub mod ast;
+pub mod bignum;
 pub mod builtin;
 pub mod environment;
 pub mod evaluator;
+pub mod formatter;
+pub mod highlight;
 pub mod lexer;
 pub mod object;
 pub mod operator;
+pub mod optimizer;
 pub mod parser;
+pub mod program;
+pub mod random;
+#[cfg(feature = "repl")]
 pub mod repl;
 pub mod token;
 pub mod util;
+
+use std::cell::RefCell;
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+use ast::RootNode;
+use builtin::Builtin;
+use environment::Environment;
+use evaluator::Evaluator;
+use lexer::Lexer;
+use object::Exit;
+use parser::Parser;
+use program::Program;
+use token::Token;
+
+pub use highlight::highlight;
+
+//the result of `run()`
+//Everything is owned and plain, so that this can be passed across a WebAssembly boundary (e.g. via `wasm-bindgen`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RunOutput {
+    pub value: Option<String>, //the result of the program unless it failed or exited
+    pub error: Option<String>, //the lexer, parser or runtime error
+    //where the lexer or parser error was found
+    //A runtime error has no position as the AST doesn't keep track of the source.
+    pub error_position: Option<Position>,
+    pub exit_code: Option<i32>, //the argument of `exit()` if called
+    pub stdout: String,         //everything written by `print()`
+    pub stderr: String,         //everything written by `eprint()`
+}
+
+//the output followed by the result, the exit code or the error, as shown on a console
+impl Display for RunOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.stdout, self.stderr)?;
+        if let Some(v) = &self.value {
+            write!(f, "{}", v)?;
+        }
+        if let Some(code) = self.exit_code {
+            write!(f, "exited with code {}", code)?;
+        }
+        if let Some(p) = &self.error_position {
+            write!(f, "{}: ", p)?;
+        }
+        if let Some(e) = &self.error {
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+//1-based line and column (in characters) in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    //position of the byte offset `offset` in `source`
+    //A line ends with `\n`, `\r\n` or `\r` as in the lexer.
+    fn new(source: &str, offset: usize) -> Self {
+        let mut position = Position { line: 1, column: 1 };
+        let mut chars = source[..offset].chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' if chars.peek() == Some(&'\n') => (),
+                '\n' | '\r' => {
+                    position = Position {
+                        line: position.line + 1,
+                        column: 1,
+                    }
+                }
+                _ => position.column += 1,
+            }
+        }
+        position
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+//same as `Program::parse()` but the error comes with its position
+//The tokens are read one by one to know their spans, which are used to locate a parser error too.
+pub fn parse_with_position(source: &str) -> Result<RootNode, (String, Position)> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = vec![];
+    let mut offsets = vec![];
+    loop {
+        let (span, token) = lexer.get_next_spanned_token();
+        let token = token.map_err(|e| (e, Position::new(source, span.start)))?;
+        offsets.push(span.start);
+        let is_eof = token == Token::Eof;
+        tokens.push(token);
+        if is_eof {
+            break;
+        }
+    }
+    let mut parser = Parser::new(tokens);
+    parser.parse().map_err(|e| {
+        let offset = offsets[parser.position()];
+        (e.to_string(), Position::new(source, offset))
+    })
+}
+
+//same as `Program::parse_optimized()` but the error comes with its position
+fn parse(source: &str) -> Result<Program, (String, Position)> {
+    let mut root = parse_with_position(source)?;
+    optimizer::optimize(&mut root);
+    Ok(Program::new(root))
+}
+
+//lexes, parses and evaluates `source` with the output of `print()` and `eprint()` captured
+//This depends on neither a terminal nor the process, so that this can be exported to WebAssembly.
+pub fn run(source: &str) -> RunOutput {
+    let program = match parse(source) {
+        Ok(p) => p,
+        Err((e, position)) => {
+            return RunOutput {
+                error: Some(e),
+                error_position: Some(position),
+                ..RunOutput::default()
+            }
+        }
+    };
+    let stdout = Rc::new(RefCell::new(Vec::new()));
+    let stderr = Rc::new(RefCell::new(Vec::new()));
+    let evaluator = Evaluator::with_builtin(Builtin::with_output(stdout.clone(), stderr.clone()));
+    let result = evaluator.eval(program.root(), &mut Environment::new(None));
+    let mut ret = RunOutput {
+        stdout: String::from_utf8_lossy(&stdout.borrow()).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr.borrow()).into_owned(),
+        ..RunOutput::default()
+    };
+    match result {
+        Ok(o) => match o.as_any().downcast_ref::<Exit>() {
+            Some(e) => ret.exit_code = Some(e.code()),
+            None => ret.value = Some(o.to_string()),
+        },
+        Err(e) => ret.error = Some(e),
+    }
+    ret
+}
@@ -1,7 +1,226 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use monkey_lang::analysis;
+use monkey_lang::builtin::Builtin;
+use monkey_lang::environment::Environment;
+use monkey_lang::evaluator::Evaluator;
+use monkey_lang::formatter::{self, FormatOptions, Indent};
+use monkey_lang::object::{Exit, Null};
+use monkey_lang::program::Program;
 use monkey_lang::repl;
 
 const HISTORY_FILE: &str = "./.history";
 
+const USAGE: &str = "\
+usage:
+    monkey_lang [--keep-session-on-exit]  (starts the REPL)
+    monkey_lang <file> [<arg>]...
+    monkey_lang -e <source> [-e <source>]...
+    monkey_lang fmt [--check] [--indent <width>|tab] <file>
+    monkey_lang --lint <file>
+    monkey_lang --help";
+
+//`monkey_lang fmt [--check] [--indent <width>|tab] <file>`
+//rewrites `<file>` in the canonical style, or with `--check` exits with `1` if `<file>` is not formatted
+//`--indent` sets the number of spaces per indentation level (`4` by default), or `tab` to indent with tabs.
+fn fmt(args: &[String]) -> i32 {
+    let usage = || {
+        eprintln!("usage: monkey_lang fmt [--check] [--indent <width>|tab] <file>");
+        2
+    };
+    let mut check = false;
+    let mut options = FormatOptions::default();
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--indent" => {
+                options.indent = match args.next().map(|s| s.as_str()) {
+                    None => return usage(),
+                    Some("tab") => Indent::Tab,
+                    Some(width) => match width.parse() {
+                        Err(_) => return usage(),
+                        Ok(width) => Indent::Spaces(width),
+                    },
+                };
+            }
+            _ if file.is_none() => file = Some(arg),
+            _ => return usage(),
+        }
+    }
+    let file = match file {
+        None => return usage(),
+        Some(file) => file,
+    };
+    let source = match fs::read_to_string(file) {
+        Err(e) => {
+            eprintln!("failed to read `{}`: {}", file, e);
+            return 2;
+        }
+        Ok(s) => s,
+    };
+    let formatted = match formatter::format_source_with(&source, &options) {
+        Err(e) => {
+            eprintln!("failed to parse `{}`: {}", file, e);
+            return 2;
+        }
+        Ok(s) => s,
+    };
+    if formatted == source {
+        return 0;
+    }
+    if check {
+        eprintln!("`{}` is not formatted", file);
+        return 1;
+    }
+    if let Err(e) = fs::write(file, formatted) {
+        eprintln!("failed to write `{}`: {}", file, e);
+        return 2;
+    }
+    0
+}
+
+//`monkey_lang --lint <file>`
+//prints the warnings of `analysis::lint()` and exits with `1` if any
+fn lint(args: &[String]) -> i32 {
+    let file = match args {
+        [file] => file,
+        _ => {
+            eprintln!("usage: monkey_lang --lint <file>");
+            return 2;
+        }
+    };
+    let source = match fs::read_to_string(file) {
+        Err(e) => {
+            eprintln!("failed to read `{}`: {}", file, e);
+            return 2;
+        }
+        Ok(s) => s,
+    };
+    let root = match monkey_lang::parse_with_position(&source) {
+        Err((e, position)) => {
+            eprintln!("{}:{}: error: {}", file, position, e);
+            return 2;
+        }
+        Ok(root) => root,
+    };
+    let warnings = analysis::lint(&root);
+    for warning in &warnings {
+        println!("{}: warning: {}", file, warning);
+    }
+    if warnings.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+//`monkey_lang -e <source> [-e <source>]...`
+//evaluates `<source>` as a complete program and prints the result unless it is `null`
+// (e.g. `monkey_lang -e "2 + 2"` prints `4`)
+//Multiple sources are joined as separate lines in the order.
+fn eval(args: &[String]) -> i32 {
+    let mut sources = vec![];
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), args.next()) {
+            ("-e", Some(source)) => sources.push(source.as_str()),
+            _ => {
+                eprintln!("usage: monkey_lang -e <source> [-e <source>]...");
+                return 2;
+            }
+        }
+    }
+    let evaluator = Evaluator::new();
+    let result = Program::parse_optimized(&sources.join("\n"))
+        .and_then(|program| evaluator.eval(program.root(), &mut Environment::new(None)));
+    match result {
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+        Ok(o) => {
+            if let Some(e) = o.as_any().downcast_ref::<Exit>() {
+                return e.code();
+            }
+            if o.as_any().downcast_ref::<Null>().is_none() {
+                println!("{}", o);
+            }
+            0
+        }
+    }
+}
+
+//`monkey_lang <file> [<arg>]...`
+//runs `<file>` as a script, which can read `<arg>`s via `argv()`
+//Unlike `-e`, the result is not printed.
+fn run_script(args: &[String]) -> i32 {
+    let (file, script_args) = match args {
+        [file, script_args @ ..] => (file, script_args),
+        _ => {
+            eprintln!("usage: monkey_lang <file> [<arg>]...");
+            return 2;
+        }
+    };
+    let source = match fs::read_to_string(file) {
+        Err(e) => {
+            eprintln!("failed to read `{}`: {}", file, e);
+            return 2;
+        }
+        Ok(s) => s,
+    };
+    let mut builtin = Builtin::new();
+    builtin.set_argv(script_args.to_vec());
+    let evaluator = Evaluator::with_builtin(builtin);
+    let result = Program::parse_optimized(&source)
+        .and_then(|program| evaluator.eval(program.root(), &mut Environment::new(None)));
+    match result {
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+        Ok(o) => match o.as_any().downcast_ref::<Exit>() {
+            Some(e) => e.code(),
+            None => 0,
+        },
+    }
+}
+
 fn main() -> rustyline::Result<()> {
-    repl::start(HISTORY_FILE)
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(|s| s.as_str()) == Some("fmt") {
+        process::exit(fmt(&args[2..]));
+    }
+    if args.get(1).map(|s| s.as_str()) == Some("--lint") {
+        process::exit(lint(&args[2..]));
+    }
+    if args.get(1).map(|s| s.as_str()) == Some("-e") {
+        process::exit(eval(&args[1..]));
+    }
+    //`exit()` in the REPL prints the code and the session continues
+    if (args.len() == 2) && (args[1] == "--keep-session-on-exit") {
+        let options = repl::ReplOptions {
+            keep_session_on_exit: true,
+        };
+        let code = repl::start_with(HISTORY_FILE, &options)?;
+        process::exit(code);
+    }
+    if let Some(flag) = args.get(1) {
+        if (flag == "--help") || (flag == "-h") {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        //A script whose name starts with `-` can still be run as e.g. `./-a`.
+        if flag.starts_with('-') {
+            eprintln!("unknown option `{}`\n{}", flag, USAGE);
+            process::exit(2);
+        }
+        process::exit(run_script(&args[1..]));
+    }
+
+    let code = repl::start(HISTORY_FILE)?;
+    process::exit(code);
 }
@@ -1,25 +1,41 @@
 use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::{self, Display};
 use std::rc::Rc;
 
 use itertools::Itertools;
 
 use super::ast::*;
+use super::bignum::BigNum;
 use super::environment::Environment;
-use super::evaluator::EvalResult;
+use super::evaluator::{EvalResult, Evaluator};
+use super::formatter;
+use super::token::Token;
+use super::util;
 
 /*-------------------------------------*/
 
 pub trait Object: Display {
     fn as_any(&self) -> &dyn Any;
+    fn type_name(&self) -> &'static str;
 }
 
+//the values of `type_name()` which can be written in a program (e.g. as a `match` pattern)
+//`built-in function` is not an identifier, and `return value` and alike never appear as values.
+pub const TYPE_NAMES: [&str; 11] = [
+    "null", "int", "float", "bool", "char", "string", "array", "hash", "range", "function", "thunk",
+];
+
 macro_rules! impl_object {
-    ($t:ty) => {
+    ($t:ty, $name:expr) => {
         impl Object for $t {
             fn as_any(&self) -> &dyn Any {
                 self
             }
+            fn type_name(&self) -> &'static str {
+                $name
+            }
         }
     };
 }
@@ -28,7 +44,7 @@ macro_rules! impl_object {
 
 pub struct Null {}
 
-impl_object!(Null);
+impl_object!(Null, "null");
 
 impl Null {
     #[allow(clippy::new_without_default)]
@@ -49,7 +65,7 @@ pub struct Int {
     value: i64,
 }
 
-impl_object!(Int);
+impl_object!(Int, "int");
 
 impl Int {
     pub fn new(value: i64) -> Self {
@@ -68,11 +84,45 @@ impl Display for Int {
 
 /*-------------------------------------*/
 
+//an integer which doesn't fit in `i64` (only created with `bignum` feature)
+//Integers are always normalized; i.e. a value within the range of `i64` is represented by `Int`.
+//Use `new_integer()` to construct either of them.
+pub struct BigInt {
+    value: BigNum,
+}
+
+impl_object!(BigInt, "int");
+
+impl BigInt {
+    pub fn new(value: BigNum) -> Self {
+        Self { value }
+    }
+    pub fn value(&self) -> &BigNum {
+        &self.value
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+//returns `Int` if `value` fits in `i64` and `BigInt` otherwise
+pub fn new_integer(value: BigNum) -> Rc<dyn Object> {
+    match value.to_i64() {
+        Some(i) => Rc::new(Int::new(i)),
+        None => Rc::new(BigInt::new(value)),
+    }
+}
+
+/*-------------------------------------*/
+
 pub struct Float {
     value: f64,
 }
 
-impl_object!(Float);
+impl_object!(Float, "float");
 
 impl Float {
     pub fn new(value: f64) -> Self {
@@ -95,7 +145,7 @@ pub struct Bool {
     value: bool,
 }
 
-impl_object!(Bool);
+impl_object!(Bool, "bool");
 
 impl Bool {
     pub fn new(value: bool) -> Self {
@@ -118,7 +168,7 @@ pub struct Char {
     value: char,
 }
 
-impl_object!(Char);
+impl_object!(Char, "char");
 
 impl Char {
     pub fn new(value: char) -> Self {
@@ -137,10 +187,31 @@ impl Display for Char {
 
 /*-------------------------------------*/
 
-//implemented by `Str` and `Array`
-#[allow(clippy::len_without_is_empty)]
-pub trait Indexable: Object {
-    fn len(&self) -> usize;
+//implemented by the objects which have a length (i.e. the number of elements)
+//A new collection type should implement this so that e.g. `len()` supports it.
+//`len()` fails only if the length doesn't fit in `usize` (e.g. the range `-9223372036854775808..=9223372036854775807`).
+pub trait Collection: Object {
+    fn len(&self) -> Result<usize, String>;
+    fn is_empty(&self) -> Result<bool, String> {
+        Ok(self.len()? == 0)
+    }
+}
+
+//returns `Some` if `o` has a length
+pub fn as_collection(o: &dyn Object) -> Option<&dyn Collection> {
+    if let Some(c) = o.as_any().downcast_ref::<Str>() {
+        return Some(c);
+    }
+    if let Some(c) = o.as_any().downcast_ref::<Array>() {
+        return Some(c);
+    }
+    if let Some(c) = o.as_any().downcast_ref::<Hash>() {
+        return Some(c);
+    }
+    if let Some(c) = o.as_any().downcast_ref::<Range>() {
+        return Some(c);
+    }
+    None
 }
 
 /*-------------------------------------*/
@@ -148,10 +219,10 @@ pub trait Indexable: Object {
 #[derive(Clone)]
 pub struct Str {
     value: Rc<String>,
-    length: usize, //for performance of `Indexable`
+    length: usize, //for performance of `Collection`
 }
 
-impl_object!(Str);
+impl_object!(Str, "string");
 
 impl Str {
     pub fn new(value: Rc<String>) -> Self {
@@ -163,9 +234,9 @@ impl Str {
     }
 }
 
-impl Indexable for Str {
-    fn len(&self) -> usize {
-        self.length
+impl Collection for Str {
+    fn len(&self) -> Result<usize, String> {
+        Ok(self.length)
     }
 }
 
@@ -182,7 +253,7 @@ pub struct Array {
     elements: Vec<Rc<dyn Object>>,
 }
 
-impl_object!(Array);
+impl_object!(Array, "array");
 
 impl Array {
     pub fn new(elements: Vec<Rc<dyn Object>>) -> Self {
@@ -193,9 +264,9 @@ impl Array {
     }
 }
 
-impl Indexable for Array {
-    fn len(&self) -> usize {
-        self.elements.len()
+impl Collection for Array {
+    fn len(&self) -> Result<usize, String> {
+        Ok(self.elements.len())
     }
 }
 
@@ -207,11 +278,198 @@ impl Display for Array {
 
 /*-------------------------------------*/
 
+//value of a hash key
+//Only the types whose equality is well-defined can be hash keys (e.g. a float cannot).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Int(i64),
+    BigInt(BigNum), //never in the range of `i64` (see `new_integer()`)
+    Bool(bool),
+    Char(char),
+    Str(String),
+}
+
+impl HashKey {
+    pub fn new(o: &dyn Object) -> Result<Self, String> {
+        if let Some(o) = o.as_any().downcast_ref::<Int>() {
+            return Ok(Self::Int(o.value()));
+        }
+        if let Some(o) = o.as_any().downcast_ref::<BigInt>() {
+            return Ok(Self::BigInt(o.value().clone()));
+        }
+        if let Some(o) = o.as_any().downcast_ref::<Bool>() {
+            return Ok(Self::Bool(o.value()));
+        }
+        if let Some(o) = o.as_any().downcast_ref::<Char>() {
+            return Ok(Self::Char(o.value()));
+        }
+        if let Some(o) = o.as_any().downcast_ref::<Str>() {
+            return Ok(Self::Str(o.value().to_string()));
+        }
+        Err(format!(
+            "unhashable type `{}` used as a hash key",
+            o.type_name()
+        ))
+    }
+}
+
+//`(<key>, <value>)`
+pub type HashEntry = (Rc<dyn Object>, Rc<dyn Object>);
+
+//A dictionary.
+//Iteration (e.g. `keys()` and `Display`) is in the insertion order so that the output is reproducible across runs.
+//Overwriting the value of an existing key keeps its position.
+//On the other hand, `==` on hashes ignores the order (see `operator::binary_eq()`).
+#[derive(Clone)]
+pub struct Hash {
+    entries: Vec<HashEntry>,
+    index: HashMap<HashKey, usize>, //key to the position in `entries`
+}
+
+impl_object!(Hash, "hash");
+
+impl Hash {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            entries: vec![],
+            index: HashMap::new(),
+        }
+    }
+    pub fn entries(&self) -> &Vec<HashEntry> {
+        &self.entries
+    }
+    pub fn get(&self, key: &dyn Object) -> Result<Option<&Rc<dyn Object>>, String> {
+        let key = HashKey::new(key)?;
+        Ok(self.index.get(&key).map(|&i| &self.entries[i].1))
+    }
+    pub fn insert(&mut self, key: Rc<dyn Object>, value: Rc<dyn Object>) -> Result<(), String> {
+        match self.index.get(&HashKey::new(key.as_ref())?) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index
+                    .insert(HashKey::new(key.as_ref())?, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+        Ok(())
+    }
+    //removes `key` keeping the order of the other entries and returns the removed value
+    pub fn remove(&mut self, key: &dyn Object) -> Result<Option<Rc<dyn Object>>, String> {
+        let i = match self.index.remove(&HashKey::new(key)?) {
+            None => return Ok(None),
+            Some(i) => i,
+        };
+        let (_, value) = self.entries.remove(i);
+        for j in self.index.values_mut() {
+            if *j > i {
+                *j -= 1;
+            }
+        }
+        Ok(Some(value))
+    }
+}
+
+impl Collection for Hash {
+    fn len(&self) -> Result<usize, String> {
+        Ok(self.entries.len())
+    }
+}
+
+impl Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.entries.is_empty() {
+            return write!(f, "{{:}}");
+        }
+        write!(
+            f,
+            "{{{}}}",
+            self.entries
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, v))
+                .join(", ")
+        )
+    }
+}
+
+/*-------------------------------------*/
+
+//A lazy sequence of integers.
+//Elements are produced on demand by `iter()` so that e.g. `0..1000000000` costs nothing until iterated.
+#[derive(Clone)]
+pub struct Range {
+    start: i64,
+    end: i64,
+    inclusive: bool,
+}
+
+impl_object!(Range, "range");
+
+impl Range {
+    pub fn new(start: i64, end: i64, inclusive: bool) -> Self {
+        Self {
+            start,
+            end,
+            inclusive,
+        }
+    }
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+    pub fn inclusive(&self) -> bool {
+        self.inclusive
+    }
+    pub fn contains(&self, i: i64) -> bool {
+        if self.inclusive {
+            (self.start..=self.end).contains(&i)
+        } else {
+            (self.start..self.end).contains(&i)
+        }
+    }
+    pub fn iter(&self) -> Box<dyn Iterator<Item = i64>> {
+        if self.inclusive {
+            Box::new(self.start..=self.end)
+        } else {
+            Box::new(self.start..self.end)
+        }
+    }
+}
+
+impl Collection for Range {
+    fn len(&self) -> Result<usize, String> {
+        if self.start > self.end {
+            return Ok(0);
+        }
+        self.end
+            .abs_diff(self.start)
+            .checked_add(self.inclusive as u64)
+            .and_then(|len| usize::try_from(len).ok())
+            .ok_or_else(|| "length overflow".to_string())
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.start,
+            if self.inclusive { "..=" } else { ".." },
+            self.end
+        )
+    }
+}
+
+/*-------------------------------------*/
+
 pub struct ReturnValue {
     value: Rc<dyn Object>,
 }
 
-impl_object!(ReturnValue);
+impl_object!(ReturnValue, "return value");
 
 impl ReturnValue {
     pub fn new(value: Rc<dyn Object>) -> Self {
@@ -230,10 +488,69 @@ impl Display for ReturnValue {
 
 /*-------------------------------------*/
 
+//the result of a program which called `exit()`
+//`exit()` stops the evaluation via the error path (see `Evaluator::exit()`), so this is never seen by the program itself.
+//The host (e.g. the REPL) decides what to do with it; the interpreter itself never terminates the process.
+#[derive(Debug, Clone)]
+pub struct Exit {
+    code: i32,
+}
+
+impl_object!(Exit, "exit");
+
+impl Exit {
+    pub fn new(code: i32) -> Self {
+        Self { code }
+    }
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+}
+
+impl Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit({})", self.code)
+    }
+}
+
+/*-------------------------------------*/
+
 //implemented by `Function` and `BuiltinFunction`
 pub trait FunctionBase: Object {
     fn num_parameter(&self) -> usize;
     fn parameters(&self) -> &Vec<IdentifierNode>;
+    //if `true`, the last parameter receives the rest of the arguments (zero or more) as an array
+    fn is_variadic(&self) -> bool {
+        false
+    }
+    //whether the function can be called with `n` arguments
+    fn accepts(&self, n: usize) -> bool {
+        if self.is_variadic() {
+            n + 1 >= self.num_parameter()
+        } else {
+            n == self.num_parameter()
+        }
+    }
+}
+
+//returns `Some` if `o` is callable
+pub fn as_function(o: &dyn Object) -> Option<&dyn FunctionBase> {
+    if let Some(f) = o.as_any().downcast_ref::<Function>() {
+        return Some(f);
+    }
+    if let Some(f) = o.as_any().downcast_ref::<BuiltinFunction>() {
+        return Some(f);
+    }
+    None
+}
+
+//whether `o` is of the type `name` (one of `TYPE_NAMES`)
+//`function` includes built-in functions (i.e. anything `as_function()` accepts) as `built-in function` can't be written.
+pub fn has_type_name(o: &dyn Object, name: &str) -> bool {
+    match name {
+        "function" => as_function(o).is_some(),
+        _ => o.type_name() == name,
+    }
 }
 
 /*-------------------------------------*/
@@ -245,7 +562,7 @@ pub struct Function {
     env: Environment,
 }
 
-impl_object!(Function);
+impl_object!(Function, "function");
 
 impl Function {
     pub fn new(
@@ -276,31 +593,86 @@ impl FunctionBase for Function {
     }
 }
 
+//shows the source of the function literal reconstructed from the AST (without the captured environment)
 impl Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "function")
+        write!(
+            f,
+            "{}",
+            formatter::format_function(&self.parameters, &self.body)
+        )
     }
 }
 
 /*-------------------------------------*/
 
+pub type BuiltinFunctionBody = dyn Fn(&Evaluator, &Environment) -> EvalResult;
+
 #[derive(Clone)]
 pub struct BuiltinFunction {
     parameters: Rc<Vec<IdentifierNode>>,
-    f: Rc<dyn Fn(&Environment) -> EvalResult>,
+    f: Rc<BuiltinFunctionBody>,
+    variadic: bool,
 }
 
-impl_object!(BuiltinFunction);
+impl_object!(BuiltinFunction, "built-in function");
 
 impl BuiltinFunction {
     pub fn new(
         parameters: Rc<Vec<IdentifierNode>>,
         f: Rc<dyn Fn(&Environment) -> EvalResult>,
     ) -> Self {
-        Self { parameters, f }
+        Self {
+            parameters,
+            f: Rc::new(move |_, env| f(env)),
+            variadic: false,
+        }
     }
-    pub fn call(&self, env: &Environment) -> EvalResult {
-        (self.f)(env)
+    //same as `new()` but `f` can call back a function object via `Evaluator::call_function()`
+    pub fn with_evaluator(parameters: Rc<Vec<IdentifierNode>>, f: Rc<BuiltinFunctionBody>) -> Self {
+        Self {
+            parameters,
+            f,
+            variadic: false,
+        }
+    }
+    //same as `with_evaluator()` but the last parameter receives the rest of the arguments as an array
+    //`parameters` must not be empty.
+    pub fn variadic(parameters: Rc<Vec<IdentifierNode>>, f: Rc<BuiltinFunctionBody>) -> Self {
+        assert!(!parameters.is_empty());
+        Self {
+            parameters,
+            f,
+            variadic: true,
+        }
+    }
+    //creates a function which takes `arity` arguments and passes them to `f` as a slice
+    //This is handier than `new()` for an embedder as the arguments need not be looked up by name. The parameters are
+    // named `arg1`, `arg2`, ... and an error returned by `f` is prefixed by `name` (e.g. "`double`: argument type
+    // mismatch").
+    //
+    //Example:
+    // BuiltinFunction::from_fn("first", 2, |args| Ok(args[0].clone()))
+    pub fn from_fn<F>(name: &str, arity: usize, f: F) -> Self
+    where
+        F: Fn(&[Rc<dyn Object>]) -> EvalResult + 'static,
+    {
+        let name = name.to_string();
+        let parameters: Vec<IdentifierNode> = (1..=arity)
+            .map(|i| IdentifierNode::new(Token::Ident(format!("arg{}", i))))
+            .collect();
+        Self::new(
+            Rc::new(parameters),
+            Rc::new(move |env: &Environment| -> EvalResult {
+                let args: Vec<Rc<dyn Object>> = (1..=arity)
+                    .map(|i| env.get(&format!("arg{}", i)).unwrap().clone())
+                    .collect();
+                f(&args).map_err(|e| format!("`{}`: {}", name, e))
+            }),
+        )
+    }
+    pub fn call(&self, evaluator: &Evaluator, env: &Environment) -> EvalResult {
+        (self.f)(evaluator, env)
     }
 }
 
@@ -311,6 +683,9 @@ impl FunctionBase for BuiltinFunction {
     fn parameters(&self) -> &Vec<IdentifierNode> {
         &self.parameters
     }
+    fn is_variadic(&self) -> bool {
+        self.variadic
+    }
 }
 
 impl Display for BuiltinFunction {
@@ -320,3 +695,298 @@ impl Display for BuiltinFunction {
 }
 
 /*-------------------------------------*/
+
+//returned by `lazy()`
+//The function is called by the first `force()` and the result is cached for the subsequent ones.
+pub struct Thunk {
+    function: Rc<dyn Object>,
+    value: RefCell<Option<Rc<dyn Object>>>,
+}
+
+impl_object!(Thunk, "thunk");
+
+impl Thunk {
+    pub fn new(function: Rc<dyn Object>) -> Self {
+        Self {
+            function,
+            value: RefCell::new(None),
+        }
+    }
+    pub fn function(&self) -> &Rc<dyn Object> {
+        &self.function
+    }
+    pub fn value(&self) -> Option<Rc<dyn Object>> {
+        self.value.borrow().clone()
+    }
+    pub fn set_value(&self, value: Rc<dyn Object>) {
+        *self.value.borrow_mut() = Some(value);
+    }
+}
+
+impl Display for Thunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "thunk")
+    }
+}
+
+/*-------------------------------------*/
+
+//Conversions from `Object` to Rust types, which spare embedders the downcast boilerplate.
+//Arrays are converted element-wise (e.g. to `Vec<i64>` or `Vec<Vec<i64>>`) when they are homogeneous.
+
+#[derive(Debug, PartialEq)]
+pub struct ConversionError {
+    expected: &'static str,
+    actual: &'static str,
+}
+
+impl ConversionError {
+    pub fn new(expected: &'static str, actual: &'static str) -> Self {
+        Self { expected, actual }
+    }
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+    pub fn actual(&self) -> &'static str {
+        self.actual
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, got {}", self.expected, self.actual)
+    }
+}
+
+macro_rules! impl_try_from_object {
+    ($rust_type:ty, $object_type:ty, $expected:expr, |$o:ident| $conversion:expr) => {
+        impl TryFrom<&dyn Object> for $rust_type {
+            type Error = ConversionError;
+            fn try_from(o: &dyn Object) -> Result<Self, Self::Error> {
+                match o.as_any().downcast_ref::<$object_type>() {
+                    Some($o) => Ok($conversion),
+                    None => Err(ConversionError::new($expected, o.type_name())),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from_object!(i64, Int, "int", |o| o.value());
+impl_try_from_object!(f64, Float, "float", |o| o.value());
+impl_try_from_object!(bool, Bool, "bool", |o| o.value());
+impl_try_from_object!(char, Char, "char", |o| o.value());
+impl_try_from_object!(String, Str, "string", |o| o.value().to_string());
+impl_try_from_object!(Vec<Rc<dyn Object>>, Array, "array", |o| o
+    .elements()
+    .clone());
+
+impl<T> TryFrom<&dyn Object> for Vec<T>
+where
+    T: for<'a> TryFrom<&'a dyn Object, Error = ConversionError>,
+{
+    type Error = ConversionError;
+    fn try_from(o: &dyn Object) -> Result<Self, Self::Error> {
+        match o.as_any().downcast_ref::<Array>() {
+            None => Err(ConversionError::new("array", o.type_name())),
+            Some(a) => a
+                .elements()
+                .iter()
+                .map(|e| T::try_from(e.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+/*-------------------------------------*/
+
+//Conversions from Rust types to `Object`, which are the inverse of the `TryFrom` conversions above.
+//`None` is converted to `Null`.
+pub trait IntoObject {
+    fn into_object(self) -> Rc<dyn Object>;
+}
+
+impl IntoObject for Rc<dyn Object> {
+    fn into_object(self) -> Rc<dyn Object> {
+        self
+    }
+}
+
+impl IntoObject for i64 {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Int::new(self))
+    }
+}
+
+impl IntoObject for f64 {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Float::new(self))
+    }
+}
+
+impl IntoObject for bool {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Bool::new(self))
+    }
+}
+
+impl IntoObject for char {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Char::new(self))
+    }
+}
+
+impl IntoObject for &str {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Str::new(Rc::new(self.to_string())))
+    }
+}
+
+impl IntoObject for String {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Str::new(Rc::new(self)))
+    }
+}
+
+impl<T: IntoObject> IntoObject for Vec<T> {
+    fn into_object(self) -> Rc<dyn Object> {
+        Rc::new(Array::new(
+            self.into_iter().map(|e| e.into_object()).collect(),
+        ))
+    }
+}
+
+impl<T: IntoObject> IntoObject for Option<T> {
+    fn into_object(self) -> Rc<dyn Object> {
+        match self {
+            None => Rc::new(Null::new()),
+            Some(e) => e.into_object(),
+        }
+    }
+}
+
+/*-------------------------------------*/
+
+//representation for debugging
+//Unlike `Display`, strings and characters are quoted and their special characters are escaped
+// (e.g. `"a\tb"` rather than `a<TAB>b`), including those in an array or a hash.
+pub fn repr(o: &dyn Object) -> String {
+    if let Some(o) = o.as_any().downcast_ref::<Str>() {
+        return format!(
+            "\"{}\"",
+            o.value()
+                .chars()
+                .map(|c| util::escape_character(c, '"'))
+                .join("")
+        );
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Char>() {
+        return format!("'{}'", util::escape_character(o.value(), '\''));
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Array>() {
+        return format!(
+            "[{}]",
+            o.elements().iter().map(|e| repr(e.as_ref())).join(", ")
+        );
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Hash>() {
+        if o.entries().is_empty() {
+            return "{:}".to_string();
+        }
+        return format!(
+            "{{{}}}",
+            o.entries()
+                .iter()
+                .map(|(k, v)| format!("{}: {}", repr(k.as_ref()), repr(v.as_ref())))
+                .join(", ")
+        );
+    }
+    o.to_string()
+}
+
+/*-------------------------------------*/
+
+//Conversions between `Object` and JSON, which let a host application pass structured data to and from scripts.
+//A character is converted to a string of length one.
+
+#[cfg(feature = "json")]
+pub fn to_json(o: &dyn Object) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+    if o.as_any().downcast_ref::<Null>().is_some() {
+        return Ok(Value::Null);
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Int>() {
+        return Ok(Value::from(o.value()));
+    }
+    //not converted to a float, which would silently lose the precision
+    #[cfg(feature = "bignum")]
+    if let Some(o) = o.as_any().downcast_ref::<BigInt>() {
+        return Err(format!(
+            "`{}` is out of the range of JSON integers",
+            o.value()
+        ));
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Float>() {
+        return match serde_json::Number::from_f64(o.value()) {
+            None => Err(format!("`{}` cannot be converted to JSON", o.value())),
+            Some(n) => Ok(Value::Number(n)),
+        };
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Bool>() {
+        return Ok(Value::Bool(o.value()));
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Char>() {
+        return Ok(Value::String(o.value().to_string()));
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Str>() {
+        return Ok(Value::String(o.value().to_string()));
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Array>() {
+        return o
+            .elements()
+            .iter()
+            .map(|e| to_json(e.as_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array);
+    }
+    if let Some(o) = o.as_any().downcast_ref::<Hash>() {
+        let mut m = serde_json::Map::new();
+        for (k, v) in o.entries() {
+            match k.as_any().downcast_ref::<Str>() {
+                None => return Err(format!("{} key cannot be converted to JSON", k.type_name())),
+                Some(k) => m.insert(k.value().to_string(), to_json(v.as_ref())?),
+            };
+        }
+        return Ok(Value::Object(m));
+    }
+    Err(format!("{} cannot be converted to JSON", o.type_name()))
+}
+
+//Integers which don't fit in `i64` are converted to floats.
+//A JSON object is converted to a hash whose keys are in the order of `serde_json::Map` (i.e. sorted by default).
+#[cfg(feature = "json")]
+pub fn from_json(v: serde_json::Value) -> Result<Rc<dyn Object>, String> {
+    use serde_json::Value;
+    let ret: Rc<dyn Object> = match v {
+        Value::Null => Rc::new(Null::new()),
+        Value::Bool(b) => Rc::new(Bool::new(b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Rc::new(Int::new(i)),
+            None => Rc::new(Float::new(n.as_f64().unwrap())),
+        },
+        Value::String(s) => Rc::new(Str::new(Rc::new(s))),
+        Value::Array(v) => Rc::new(Array::new(
+            v.into_iter().map(from_json).collect::<Result<_, _>>()?,
+        )),
+        Value::Object(m) => {
+            let mut h = Hash::new();
+            for (k, v) in m {
+                h.insert(Rc::new(Str::new(Rc::new(k))), from_json(v)?)?;
+            }
+            Rc::new(h)
+        }
+    };
+    Ok(ret)
+}
+
+/*-------------------------------------*/
@@ -1,11 +1,18 @@
 use std::rc::Rc;
 
+use super::bignum::BigNum;
 use super::evaluator::EvalResult;
 use super::object::*;
 
 pub fn unary_minus(o: &dyn Object) -> EvalResult {
     if let Some(o) = o.as_any().downcast_ref::<Int>() {
-        return Ok(Rc::new(Int::new(-o.value())));
+        if let Some(i) = o.value().checked_neg() {
+            return Ok(Rc::new(Int::new(i)));
+        }
+    }
+    if let Some(o) = to_bignum(o) {
+        check_bignum_enabled()?;
+        return Ok(new_integer(o.neg()));
     }
     if let Some(o) = o.as_any().downcast_ref::<Float>() {
         return Ok(Rc::new(Float::new(-o.value())));
@@ -32,9 +39,44 @@ fn try_cast<'a, T1: Object + 'static, T2: Object + 'static>(
     None
 }
 
+//converts `Int` or `BigInt` to `BigNum`
+fn to_bignum(o: &dyn Object) -> Option<BigNum> {
+    if let Some(o) = o.as_any().downcast_ref::<Int>() {
+        return Some(BigNum::from(o.value()));
+    }
+    if let Some(o) = o.as_any().downcast_ref::<BigInt>() {
+        return Some(o.value().clone());
+    }
+    None
+}
+
+//similar to `try_cast()` but succeeds if both of the operands are integers (`Int` or `BigInt`)
+//Integer arithmetic falls back to this when the result overflows `i64`.
+fn try_cast_bignum(left: &dyn Object, right: &dyn Object) -> Option<(BigNum, BigNum)> {
+    Some((to_bignum(left)?, to_bignum(right)?))
+}
+
+//upper limit of the size of the result of `**` on integers, without which e.g. `2 ** 4294967295` takes forever
+const MAX_POWER_BITS: u64 = 1 << 18;
+
+//an overflow of `i64` is an error unless `bignum` feature is enabled
+fn check_bignum_enabled() -> Result<(), String> {
+    if cfg!(feature = "bignum") {
+        Ok(())
+    } else {
+        Err("integer overflow".to_string())
+    }
+}
+
 pub fn binary_plus(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
-        return Ok(Rc::new(Int::new(t.0.value() + t.1.value())));
+        if let Some(i) = t.0.value().checked_add(t.1.value()) {
+            return Ok(Rc::new(Int::new(i)));
+        }
+    }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        check_bignum_enabled()?;
+        return Ok(new_integer(l.add(&r)));
     }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Float::new(t.0.value() + t.1.value())));
@@ -53,12 +95,26 @@ pub fn binary_plus(left: &dyn Object, right: &dyn Object) -> EvalResult {
         }
         return Ok(Rc::new(Array::new(elements)));
     }
-    Err("operand of binary `+` is not a number, a string nor an array".to_string())
+    //The entries of the right operand override those of the left one on key collision.
+    if let Some(t) = try_cast::<Hash, Hash>(left, right) {
+        let mut h = t.0.clone();
+        for (k, v) in t.1.entries() {
+            h.insert(k.clone(), v.clone())?;
+        }
+        return Ok(Rc::new(h));
+    }
+    Err("operand of binary `+` is not a number, a string, an array nor a hash".to_string())
 }
 
 pub fn binary_minus(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
-        return Ok(Rc::new(Int::new(t.0.value() - t.1.value())));
+        if let Some(i) = t.0.value().checked_sub(t.1.value()) {
+            return Ok(Rc::new(Int::new(i)));
+        }
+    }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        check_bignum_enabled()?;
+        return Ok(new_integer(l.sub(&r)));
     }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Float::new(t.0.value() - t.1.value())));
@@ -68,7 +124,13 @@ pub fn binary_minus(left: &dyn Object, right: &dyn Object) -> EvalResult {
 
 pub fn binary_asterisk(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
-        return Ok(Rc::new(Int::new(t.0.value() * t.1.value())));
+        if let Some(i) = t.0.value().checked_mul(t.1.value()) {
+            return Ok(Rc::new(Int::new(i)));
+        }
+    }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        check_bignum_enabled()?;
+        return Ok(new_integer(l.mul(&r)));
     }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Float::new(t.0.value() * t.1.value())));
@@ -78,10 +140,19 @@ pub fn binary_asterisk(left: &dyn Object, right: &dyn Object) -> EvalResult {
 
 pub fn binary_slash(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
-        if t.0.value() == 0 {
+        if t.1.value() == 0 {
             return Err("zero division".to_string());
         }
-        return Ok(Rc::new(Int::new(t.0.value() / t.1.value())));
+        if let Some(i) = t.0.value().checked_div(t.1.value()) {
+            return Ok(Rc::new(Int::new(i)));
+        }
+    }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        check_bignum_enabled()?;
+        return match l.div_rem(&r) {
+            None => Err("zero division".to_string()),
+            Some((q, _)) => Ok(new_integer(q)),
+        };
     }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         if t.1.value() == 0.0 {
@@ -97,7 +168,16 @@ pub fn binary_percent(left: &dyn Object, right: &dyn Object) -> EvalResult {
         if t.1.value() == 0 {
             return Err("zero division in `%`".to_string());
         }
-        return Ok(Rc::new(Int::new(t.0.value() % t.1.value())));
+        if let Some(i) = t.0.value().checked_rem(t.1.value()) {
+            return Ok(Rc::new(Int::new(i)));
+        }
+    }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        check_bignum_enabled()?;
+        return match l.div_rem(&r) {
+            None => Err("zero division in `%`".to_string()),
+            Some((_, r)) => Ok(new_integer(r)),
+        };
     }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         if t.1.value() == 0.0 {
@@ -109,11 +189,25 @@ pub fn binary_percent(left: &dyn Object, right: &dyn Object) -> EvalResult {
 }
 
 pub fn binary_power(left: &dyn Object, right: &dyn Object) -> EvalResult {
-    if let Some(t) = try_cast::<Int, Int>(left, right) {
-        if t.1.value() < 0 {
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        if r.is_negative() {
             return Err("negative exponent in <int>**<int> operation".to_string());
         }
-        return Ok(Rc::new(Int::new(t.0.value().pow(t.1.value() as u32))));
+        let exponent = match r.to_i64().and_then(|i| u32::try_from(i).ok()) {
+            None => return Err("too large exponent in <int>**<int> operation".to_string()),
+            Some(i) => i,
+        };
+        if let Some(l) = l.to_i64() {
+            if let Some(i) = l.checked_pow(exponent) {
+                return Ok(Rc::new(Int::new(i)));
+            }
+        }
+        check_bignum_enabled()?;
+        //the result has at least this number of bits (`|l| >= 2` here as `l.checked_pow()` never fails otherwise)
+        if (l.bits() - 1) * exponent as u64 >= MAX_POWER_BITS {
+            return Err("too large result in <int>**<int> operation".to_string());
+        }
+        return Ok(new_integer(l.pow(exponent)));
     }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Float::new(t.0.value().powf(t.1.value()))));
@@ -125,6 +219,9 @@ pub fn binary_eq(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() == t.1.value())));
     }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        return Ok(Rc::new(Bool::new(l == r)));
+    }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() == t.1.value())));
     }
@@ -137,13 +234,59 @@ pub fn binary_eq(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Str, Str>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() == t.1.value())));
     }
+    //structural equality
+    if let Some(t) = try_cast::<Array, Array>(left, right) {
+        if t.0.elements().len() != t.1.elements().len() {
+            return Ok(Rc::new(Bool::new(false)));
+        }
+        for (l, r) in t.0.elements().iter().zip(t.1.elements()) {
+            let b = binary_eq(l.as_ref(), r.as_ref())?;
+            if !b.as_any().downcast_ref::<Bool>().unwrap().value() {
+                return Ok(b);
+            }
+        }
+        return Ok(Rc::new(Bool::new(true)));
+    }
+    //Two hashes are equal if they have the same set of keys with equal values, regardless of the insertion order.
+    if let Some(t) = try_cast::<Hash, Hash>(left, right) {
+        if t.0.len() != t.1.len() {
+            return Ok(Rc::new(Bool::new(false)));
+        }
+        for (k, l) in t.0.entries() {
+            let r = match t.1.get(k.as_ref())? {
+                None => return Ok(Rc::new(Bool::new(false))),
+                Some(r) => r,
+            };
+            let b = binary_eq(l.as_ref(), r.as_ref())?;
+            if !b.as_any().downcast_ref::<Bool>().unwrap().value() {
+                return Ok(b);
+            }
+        }
+        return Ok(Rc::new(Bool::new(true)));
+    }
+    //reference equality: a function is equal only to itself (i.e. the same object possibly bound to different names),
+    // and two function literals are different even if they are written identically
+    if as_function(left).is_some() && as_function(right).is_some() {
+        return Ok(Rc::new(Bool::new(std::ptr::addr_eq(left, right))));
+    }
     Err("unsupported operand type for binary `==`".to_string())
 }
 
+//same as `binary_eq()` but objects which cannot be compared (e.g. `1` and `"a"`) are just regarded as different
+pub fn is_equal(left: &dyn Object, right: &dyn Object) -> bool {
+    binary_eq(left, right)
+        .ok()
+        .and_then(|b| b.as_any().downcast_ref::<Bool>().map(|b| b.value()))
+        .unwrap_or(false)
+}
+
 pub fn binary_noteq(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() != t.1.value())));
     }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        return Ok(Rc::new(Bool::new(l != r)));
+    }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() != t.1.value())));
     }
@@ -156,6 +299,15 @@ pub fn binary_noteq(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Str, Str>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() != t.1.value())));
     }
+    if try_cast::<Array, Array>(left, right).is_some()
+        || try_cast::<Hash, Hash>(left, right).is_some()
+        || (as_function(left).is_some() && as_function(right).is_some())
+    {
+        let b = binary_eq(left, right).map_err(|_| "unsupported operand type for binary `!=`")?;
+        return Ok(Rc::new(Bool::new(
+            !b.as_any().downcast_ref::<Bool>().unwrap().value(),
+        )));
+    }
     Err("unsupported operand type for binary `!=`".to_string())
 }
 
@@ -163,6 +315,9 @@ pub fn binary_lt(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() < t.1.value())));
     }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        return Ok(Rc::new(Bool::new(l < r)));
+    }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() < t.1.value())));
     }
@@ -179,6 +334,9 @@ pub fn binary_gt(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() > t.1.value())));
     }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        return Ok(Rc::new(Bool::new(l > r)));
+    }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() > t.1.value())));
     }
@@ -195,6 +353,9 @@ pub fn binary_lteq(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() <= t.1.value())));
     }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        return Ok(Rc::new(Bool::new(l <= r)));
+    }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() <= t.1.value())));
     }
@@ -211,6 +372,9 @@ pub fn binary_gteq(left: &dyn Object, right: &dyn Object) -> EvalResult {
     if let Some(t) = try_cast::<Int, Int>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() >= t.1.value())));
     }
+    if let Some((l, r)) = try_cast_bignum(left, right) {
+        return Ok(Rc::new(Bool::new(l >= r)));
+    }
     if let Some(t) = try_cast::<Float, Float>(left, right) {
         return Ok(Rc::new(Bool::new(t.0.value() >= t.1.value())));
     }
@@ -236,3 +400,27 @@ pub fn binary_or(left: &dyn Object, right: &dyn Object) -> EvalResult {
     }
     Err("operand of binary `|| is not a boolean".to_string())
 }
+
+//`<element> in <array>` tests if the array contains an element equal to `<element>`.
+//`<integer> in <range>` tests if the integer is within the range.
+//`<substring or character> in <string>` tests if the string contains it.
+//`<key> in <hash>` tests if the hash has the key.
+pub fn binary_in(left: &dyn Object, right: &dyn Object) -> EvalResult {
+    if let Some(h) = right.as_any().downcast_ref::<Hash>() {
+        return Ok(Rc::new(Bool::new(h.get(left)?.is_some())));
+    }
+    if let Some(a) = right.as_any().downcast_ref::<Array>() {
+        let found = a.elements().iter().any(|e| is_equal(left, e.as_ref()));
+        return Ok(Rc::new(Bool::new(found)));
+    }
+    if let Some(t) = try_cast::<Int, Range>(left, right) {
+        return Ok(Rc::new(Bool::new(t.1.contains(t.0.value()))));
+    }
+    if let Some(t) = try_cast::<Str, Str>(left, right) {
+        return Ok(Rc::new(Bool::new(t.1.value().contains(t.0.value()))));
+    }
+    if let Some(t) = try_cast::<Char, Str>(left, right) {
+        return Ok(Rc::new(Bool::new(t.1.value().contains(t.0.value()))));
+    }
+    Err("unsupported operand type for binary `in`".to_string())
+}
@@ -0,0 +1,105 @@
+use super::analysis::LintWarning;
+use super::ast::*;
+
+//rewrites `root` into an equivalent but cheaper AST, and returns the warnings found on the way
+//Currently `if (true) { a } else { b }` is folded to `{ a }` and `if (false) { a } else { b }` to `{ b }` (`{}` without
+// `else`). The taken branch is kept as a block so that the variables defined in it stay local to it.
+pub fn optimize(root: &mut RootNode) -> Vec<LintWarning> {
+    let mut warnings = vec![];
+    visit(root, &mut warnings);
+    warnings
+}
+
+fn visit(node: &mut dyn Node, warnings: &mut Vec<LintWarning>) {
+    for child in children_mut(node) {
+        match child {
+            ChildMut::Expression(e) => {
+                fold_if_expression(e, warnings);
+                visit(e.as_node_mut(), warnings);
+            }
+            ChildMut::Block(b) => visit(b, warnings),
+        }
+    }
+}
+
+//replaces `e` with the taken branch if `e` is an `if` with a literal condition
+//Only a literal is regarded as constant (e.g. `if (!false)` is left as is).
+fn fold_if_expression(e: &mut Box<dyn ExpressionNode>, warnings: &mut Vec<LintWarning>) {
+    let Some(n) = e.as_any_mut().downcast_mut::<IfExpressionNode>() else {
+        return;
+    };
+    let Some(condition) = n
+        .condition()
+        .as_any()
+        .downcast_ref::<BooleanLiteralNode>()
+        .map(|c| c.get_value())
+    else {
+        return;
+    };
+    match condition {
+        false => warnings.push(LintWarning::DeadIfBranch),
+        true if n.else_value().is_some() => warnings.push(LintWarning::DeadElseBranch),
+        true => (),
+    }
+    *e = Box::new(n.take_branch(condition));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::lexer::Lexer;
+    use super::super::parser::Parser;
+    use super::*;
+
+    fn optimized(s: &str) -> (RootNode, Vec<LintWarning>) {
+        let mut root = Parser::new(Lexer::new(s).tokenize().unwrap())
+            .parse()
+            .unwrap();
+        let warnings = optimize(&mut root);
+        (root, warnings)
+    }
+
+    fn debug(s: &str) -> String {
+        let root = Parser::new(Lexer::new(s).tokenize().unwrap())
+            .parse()
+            .unwrap();
+        format!("{:?}", root)
+    }
+
+    #[test]
+    fn test_optimize() {
+        use LintWarning::*;
+
+        let (root, warnings) = optimized("if(false){1}else{2}");
+        assert_eq!(debug("{2}"), format!("{:?}", root));
+        assert_eq!(vec![DeadIfBranch], warnings);
+
+        let (root, warnings) =
+            optimized("if (true) { 1 } else { 2 }; if (true) { 3 }; if (false) { 4 }");
+        assert_eq!(debug("{ 1 }; { 3 }; {}"), format!("{:?}", root));
+        assert_eq!(vec![DeadElseBranch, DeadIfBranch], warnings);
+
+        //nested ones, including those in the taken branch and in a function body
+        let (root, _) = optimized(
+            "let f = fn() { if (true) { if (false) { 1 } else { [if (true) { 2 }] } } }; f()",
+        );
+        assert_eq!(
+            debug("let f = fn() { { { [{ 2 }] } } }; f()"),
+            format!("{:?}", root)
+        );
+
+        //only a literal condition is regarded as constant
+        let s = "let a = true; if (a) { 1 } else { 2 }; if (!false) { 1 } else { 2 }";
+        let (root, warnings) = optimized(s);
+        assert_eq!(debug(s), format!("{:?}", root));
+        assert!(warnings.is_empty());
+
+        //`run()` evaluates the folded program, where the taken branch keeps its own scope
+        assert_eq!(
+            Some("2".to_string()),
+            crate::run("if(false){1}else{2}").value
+        );
+        let s = "let a = 1; if (true) { let a = 2; a }; a";
+        assert_eq!(Some("1".to_string()), crate::run(s).value);
+    }
+}
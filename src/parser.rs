@@ -4,6 +4,7 @@ use std::mem;
 use std::rc::Rc;
 
 use super::ast::*;
+use super::object::TYPE_NAMES;
 use super::token::Token;
 
 /*-------------------------------------*/
@@ -13,11 +14,12 @@ enum Precedence {
     Lowest = 0,
     Or,      //`||`
     And,     //`&&`
-    Cmp,     //`==`, `!=`, `<`, `>`, `>=`, `<=`
+    Cmp,     //`==`, `!=`, `<`, `>`, `>=`, `<=`, `in`
+    Range,   //`..`, `..=`
     Sum,     //`+`, `-`
     Product, //`*`, `/`, `%`, `**`
     Unary,   //`-`, `!`
-    Call,    //`(`, `[`
+    Call,    //`(`, `[`, `.`
 }
 
 fn lookup_precedence(token: &Token) -> Precedence {
@@ -30,6 +32,9 @@ fn lookup_precedence(token: &Token) -> Precedence {
         Token::Gt => Precedence::Cmp,
         Token::LtEq => Precedence::Cmp,
         Token::GtEq => Precedence::Cmp,
+        Token::In => Precedence::Cmp,
+        Token::DotDot => Precedence::Range,
+        Token::DotDotEq => Precedence::Range,
         Token::Plus => Precedence::Sum,
         Token::Minus => Precedence::Sum,
         Token::Asterisk => Precedence::Product,
@@ -38,6 +43,7 @@ fn lookup_precedence(token: &Token) -> Precedence {
         Token::Power => Precedence::Product,
         Token::Lparen => Precedence::Call,
         Token::Lbracket => Precedence::Call,
+        Token::Dot => Precedence::Call,
         Token::Rparen => Precedence::Lowest,
         Token::Rbracket => Precedence::Lowest,
         _ => Precedence::Lowest,
@@ -69,29 +75,99 @@ impl Display for ParseError {
 
 /*-------------------------------------*/
 
+//rejects a misspelled type name (e.g. `intt`) in a `match` pattern or a `let` annotation, which would otherwise never
+// match
+fn check_type_name(s: String) -> ParseResult<String> {
+    if TYPE_NAMES.contains(&s.as_str()) {
+        Ok(s)
+    } else {
+        Err(ParseError::Error(format!("unknown type `{}`", s)))
+    }
+}
+
+/*-------------------------------------*/
+
+//maximum nesting depth of expressions, beyond which parsing (and evaluation) would overflow the stack
+const MAX_DEPTH: usize = 200;
+
 pub struct Parser {
     tokens: VecDeque<Token>,
+    num_tokens: usize,
+    //`Token::Comment`s are taken out of `tokens` and each of them is paired with the index (in `tokens`) of the token
+    // following it
+    comments: VecDeque<(usize, CommentNode)>,
+    depth: usize,
+    //`true` if the tokens passed to `new()` don't end with `Token::Eof`, which is reported by `parse()` and alike
+    missing_eof: bool,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        assert!(!tokens.is_empty());
-        assert_eq!(tokens.last().unwrap(), &Token::Eof);
+    pub fn new(mut tokens: Vec<Token>) -> Self {
+        let missing_eof = tokens.last() != Some(&Token::Eof);
+        if missing_eof {
+            tokens.push(Token::Eof);
+        }
+        let mut comments = VecDeque::new();
+        let mut v = VecDeque::with_capacity(tokens.len());
+        for token in tokens {
+            match token {
+                Token::Comment { text, trailing } => {
+                    comments.push_back((v.len(), CommentNode::new(text, trailing)))
+                }
+                token => v.push_back(token),
+            }
+        }
         Parser {
-            tokens: VecDeque::from(tokens),
+            num_tokens: v.len(),
+            tokens: v,
+            comments,
+            depth: 0,
+            missing_eof,
+        }
+    }
+
+    //number of the tokens consumed so far
+    //After `parse()` fails, this is the index of the token at which the error was found (comments not counted).
+    pub fn position(&self) -> usize {
+        self.num_tokens - self.tokens.len()
+    }
+
+    //takes the comments which precede the `i`-th token
+    fn take_comments_before(&mut self, i: usize) -> Vec<CommentNode> {
+        let mut ret = vec![];
+        while self.comments.front().is_some_and(|(j, _)| *j < i) {
+            ret.push(self.comments.pop_front().unwrap().1);
+        }
+        ret
+    }
+
+    //appends the comments preceding the next token to `statements`
+    fn push_comments(&mut self, statements: &mut Vec<Box<dyn StatementNode>>) {
+        for comment in self.take_comments_before(self.position() + 1) {
+            statements.push(Box::new(comment));
+        }
+    }
+
+    //The comments in the middle of the statements `statements[start..]` (e.g. one between the arguments of a function
+    // call) are moved before them as they can't be kept in place.
+    fn hoist_comments(&mut self, statements: &mut Vec<Box<dyn StatementNode>>, start: usize) {
+        let comments = self.take_comments_before(self.position());
+        for (i, comment) in comments.into_iter().enumerate() {
+            let comment = CommentNode::new(comment.text().to_string(), false);
+            statements.insert(start + i, Box::new(comment));
         }
     }
 
     fn get_next(&mut self) -> ParseResult<Token> {
-        match self.tokens.pop_front() {
+        match self.tokens.front() {
             None => unreachable!(), //at least `Eof` is assumed to exist as a guardian
-            Some(Token::Eof) => Err(ParseError::Eof),
-            Some(t) => Ok(t),
+            Some(Token::Eof) => Err(ParseError::Eof), //the guardian is never consumed
+            Some(_) => Ok(self.tokens.pop_front().unwrap()),
         }
     }
 
     fn peek_next(&self) -> ParseResult<&Token> {
-        match self.tokens.get(0) {
+        match self.tokens.front() {
             None => unreachable!(), //at least `Eof` is assumed to exist as a guardian
             Some(Token::Eof) => Err(ParseError::Eof),
             Some(t) => Ok(t),
@@ -99,29 +175,79 @@ impl Parser {
     }
 
     pub fn parse(&mut self) -> ParseResult<RootNode> {
+        self.check_eof()?;
+        let mut statements = vec![];
+        while let Some(result) = self.parse_root_statement(&mut statements) {
+            result?;
+        }
+        Ok(RootNode::new(statements))
+    }
+
+    //similar to `parse()` but doesn't stop at the first error
+    //When a statement fails to be parsed, the error is recorded and the tokens are skipped up to the next `;` or `}`
+    // (panic-mode recovery). The returned `RootNode` consists of the statements successfully parsed.
+    pub fn parse_recovering(&mut self) -> (RootNode, Vec<ParseError>) {
         let mut statements = vec![];
-        //reads the next statement
+        let mut errors = vec![];
+        if let Err(e) = self.check_eof() {
+            return (RootNode::new(statements), vec![e]);
+        }
+        while let Some(result) = self.parse_root_statement(&mut statements) {
+            if let Err(e) = result {
+                errors.push(e);
+                self.skip_statement();
+            }
+        }
+        (RootNode::new(statements), errors)
+    }
+
+    fn check_eof(&self) -> ParseResult<()> {
+        if self.missing_eof {
+            return Err(ParseError::Error(
+                "token stream not terminated by `Eof`".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    //reads the next top-level statement (and the comments preceding it) into `statements`, skipping empty statements
+    //`None` is returned at the end of the input.
+    fn parse_root_statement(
+        &mut self,
+        statements: &mut Vec<Box<dyn StatementNode>>,
+    ) -> Option<ParseResult<()>> {
         loop {
+            self.push_comments(statements);
             if self.tokens[0] == Token::Eof {
-                break;
+                return None;
             }
             //empty statement
             if self.expect_next(Token::Semicolon) {
                 self.get_next().unwrap();
                 continue;
             }
-            let statement = match self.parse_statement() {
-                Err(ParseError::Eof) => {
-                    return Err(ParseError::Error(
-                        "unexpected eof in the middle of a statement".to_string(),
-                    ))
+            let start = statements.len();
+            return Some(match self.parse_statement() {
+                Err(ParseError::Eof) => Err(ParseError::Error(
+                    "unexpected eof in the middle of a statement".to_string(),
+                )),
+                Err(e) => Err(e),
+                Ok(statement) => {
+                    statements.push(statement);
+                    self.hoist_comments(statements, start);
+                    Ok(())
                 }
-                Err(e) => return Err(e),
-                Ok(e) => e,
-            };
-            statements.push(statement);
+            });
+        }
+    }
+
+    //skips the tokens up to and including the next `;` or `}`
+    fn skip_statement(&mut self) {
+        while let Ok(token) = self.get_next() {
+            if (token == Token::Semicolon) || (token == Token::Rbrace) {
+                break;
+            }
         }
-        Ok(RootNode::new(statements))
     }
 
     fn parse_statement(&mut self) -> ParseResult<Box<dyn StatementNode>> {
@@ -142,18 +268,136 @@ impl Parser {
     //{<statement(s)>}
     fn parse_block_expression(&mut self) -> ParseResult<BlockExpressionNode> {
         assert_eq!(Token::Lbrace, self.get_next().unwrap());
-        let mut statements = vec![];
+        self.parse_rest_of_block_expression(vec![])
+    }
+
+    //parses the statements following `statements` up to `}`
+    fn parse_rest_of_block_expression(
+        &mut self,
+        mut statements: Vec<Box<dyn StatementNode>>,
+    ) -> ParseResult<BlockExpressionNode> {
+        self.hoist_comments(&mut statements, 0);
         loop {
+            self.push_comments(&mut statements);
             if self.peek_next()? == &Token::Rbrace {
                 self.get_next().unwrap();
                 break;
             }
+            let start = statements.len();
             statements.push(self.parse_statement()?);
+            self.hoist_comments(&mut statements, start);
         }
         Ok(BlockExpressionNode::new(statements))
     }
 
-    //let <identifier> = <expression>;
+    //A `{` in an expression context starts a hash literal if the first expression is followed by `:`, and a block
+    // expression otherwise.
+    //`{}` is an empty block and `{:}` is an empty hash.
+    fn parse_block_expression_or_hash_literal(&mut self) -> ParseResult<Box<dyn ExpressionNode>> {
+        assert_eq!(Token::Lbrace, self.tokens[0]);
+        match &self.tokens[1] {
+            Token::Colon => return self.parse_hash_literal(None).map(|e| Box::new(e) as _),
+            Token::Rbrace | Token::Let | Token::Return | Token::Semicolon => {
+                return self.parse_block_expression().map(|e| Box::new(e) as _)
+            }
+            _ => (),
+        }
+        self.get_next().unwrap();
+        let expr = self.parse_hash_key()?;
+        if self.expect_next(Token::Colon) {
+            return self
+                .parse_hash_literal(Some(expr))
+                .map(|e| Box::new(e) as _);
+        }
+        //same as `parse_expression_statement()`
+        if self.expect_next(Token::Semicolon) {
+            self.get_next().unwrap();
+        }
+        self.parse_rest_of_block_expression(vec![Box::new(ExpressionStatementNode::new(expr))])
+            .map(|e| Box::new(e) as _)
+    }
+
+    //{<key>: <value>, <key>: <value>, ...}
+    //The last pair can optionally be followed by a comma (e.g. `{"a": 1,}`).
+    //
+    //`first_key` is the key of the first pair if it has already been read, in which case the next token is the `:`
+    // following it. Otherwise, `{:}` (i.e. an empty hash) is expected.
+    fn parse_hash_literal(
+        &mut self,
+        first_key: Option<Box<dyn ExpressionNode>>,
+    ) -> ParseResult<HashLiteralNode> {
+        let mut pairs = vec![];
+        let mut key = match first_key {
+            Some(key) => key,
+            None => {
+                assert_eq!(Token::Lbrace, self.get_next().unwrap());
+                assert_eq!(Token::Colon, self.get_next().unwrap());
+                if !self.expect_next(Token::Rbrace) {
+                    return Err(ParseError::Error(
+                        "`}` expected after `{:` in empty hash literal".to_string(),
+                    ));
+                }
+                self.get_next().unwrap();
+                return Ok(HashLiteralNode::new(pairs));
+            }
+        };
+        loop {
+            if !self.expect_next(Token::Colon) {
+                return Err(ParseError::Error(
+                    "`:` expected but not found in hash literal".to_string(),
+                ));
+            }
+            self.get_next().unwrap();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            pairs.push((key, value));
+            match self.peek_next()? {
+                Token::Rbrace => {
+                    self.get_next().unwrap();
+                    break;
+                }
+                Token::Comma => {
+                    self.get_next().unwrap();
+                }
+                _ => {
+                    return Err(ParseError::Error(
+                        "`,` expected but not found in hash literal".to_string(),
+                    ))
+                }
+            }
+            if self.expect_next(Token::Rbrace) {
+                self.get_next().unwrap();
+                break;
+            }
+            key = self.parse_hash_key()?;
+        }
+        Ok(HashLiteralNode::new(pairs))
+    }
+
+    //`.x` is a field name, so `{.x: 1}` is the same as `{"x": 1}` (which makes a hash usable as a record with `p.x`).
+    //Any other key is an expression to evaluate (e.g. `{k: 1}` uses the value of the variable `k`).
+    fn parse_hash_key(&mut self) -> ParseResult<Box<dyn ExpressionNode>> {
+        if !self.expect_next(Token::Dot) {
+            return self.parse_expression(Precedence::Lowest);
+        }
+        self.get_next().unwrap();
+        let name = match self.get_next()? {
+            Token::Ident(name) => name,
+            _ => {
+                return Err(ParseError::Error(
+                    "field name expected after `.`".to_string(),
+                ))
+            }
+        };
+        //otherwise `{.x}` would be a block
+        if !self.expect_next(Token::Colon) {
+            return Err(ParseError::Error(
+                "`:` expected but not found in hash literal".to_string(),
+            ));
+        }
+        Ok(Box::new(StringLiteralNode::new(Token::String(name))))
+    }
+
+    //let <identifier>[: <type name>] = <expression>;
     fn parse_let_statement(&mut self) -> ParseResult<LetStatementNode> {
         assert_eq!(Token::Let, self.get_next().unwrap());
 
@@ -164,6 +408,24 @@ impl Parser {
         }
         let identifier = IdentifierNode::new(self.get_next()?);
 
+        let annotation = if self.expect_next(Token::Colon) {
+            self.get_next().unwrap();
+            match self.peek_next() {
+                Ok(Token::Ident(s)) => {
+                    let s = s.clone();
+                    self.get_next().unwrap();
+                    Some(check_type_name(s)?)
+                }
+                _ => {
+                    return Err(ParseError::Error(
+                        "type name missing after `:` in `let`".to_string(),
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+
         if !self.expect_next(Token::Assign) {
             return Err(ParseError::Error("`=` missing in `let`".to_string()));
         }
@@ -176,7 +438,7 @@ impl Parser {
         }
         self.get_next().unwrap();
 
-        Ok(LetStatementNode::new(identifier, expr))
+        Ok(LetStatementNode::new(identifier, annotation, expr))
     }
 
     //return [<expression>];
@@ -204,12 +466,26 @@ impl Parser {
     }
 
     fn parse_expression(&mut self, precedence: Precedence) -> ParseResult<Box<dyn ExpressionNode>> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError::Error("nesting too deep".to_string()));
+        }
+        self.depth += 1;
+        let ret = self.parse_expression_without_depth_check(precedence);
+        self.depth -= 1;
+        ret
+    }
+
+    fn parse_expression_without_depth_check(
+        &mut self,
+        precedence: Precedence,
+    ) -> ParseResult<Box<dyn ExpressionNode>> {
         //parses first expression
         let mut expr: Box<dyn ExpressionNode> = match self.peek_next()? {
-            Token::Lbrace => self.parse_block_expression().map(|e| Box::new(e) as _),
+            Token::Lbrace => self.parse_block_expression_or_hash_literal(),
             Token::Lparen => self.parse_grouped_expression(),
             Token::Ident(_) => self.parse_identifier().map(|e| Box::new(e) as _),
             Token::Int(_) => self.parse_integer_literal().map(|e| Box::new(e) as _),
+            Token::BigInt(_) => self.parse_big_integer_literal().map(|e| Box::new(e) as _),
             Token::Float(_) => self.parse_float_literal().map(|e| Box::new(e) as _),
             Token::True => self.parse_boolean_literal().map(|e| Box::new(e) as _),
             Token::False => self.parse_boolean_literal().map(|e| Box::new(e) as _),
@@ -219,6 +495,7 @@ impl Parser {
             Token::Invert => self.parse_unary_expression().map(|e| Box::new(e) as _),
             Token::Minus => self.parse_unary_expression().map(|e| Box::new(e) as _),
             Token::If => self.parse_if_expression().map(|e| Box::new(e) as _),
+            Token::Match => self.parse_match_expression().map(|e| Box::new(e) as _),
             Token::Function => self.parse_function_literal().map(|e| Box::new(e) as _),
             t => Err(ParseError::Error(format!(
                 "unexpected start of expression: {:?}",
@@ -239,6 +516,10 @@ impl Parser {
             expr = match next {
                 Token::Lparen => Box::new(self.parse_call_expression(expr)?) as _,
                 Token::Lbracket => Box::new(self.parse_index_expression(expr)?) as _,
+                Token::Dot => Box::new(self.parse_field_expression(expr)?) as _,
+                Token::DotDot | Token::DotDotEq => {
+                    Box::new(self.parse_range_expression(expr)?) as _
+                }
                 _ => Box::new(self.parse_binary_expression(expr)?) as _,
             };
         }
@@ -270,6 +551,10 @@ impl Parser {
         Ok(IntegerLiteralNode::new(self.get_next()?))
     }
 
+    fn parse_big_integer_literal(&mut self) -> ParseResult<BigIntegerLiteralNode> {
+        Ok(BigIntegerLiteralNode::new(self.get_next()?))
+    }
+
     fn parse_float_literal(&mut self) -> ParseResult<FloatLiteralNode> {
         Ok(FloatLiteralNode::new(self.get_next()?))
     }
@@ -338,6 +623,20 @@ impl Parser {
         Ok(BinaryExpressionNode::new(operator, left, right))
     }
 
+    //<expression>..<expression> or <expression>..=<expression>
+    fn parse_range_expression(
+        &mut self,
+        start: Box<dyn ExpressionNode>,
+    ) -> ParseResult<RangeExpressionNode> {
+        let operator = self.get_next()?;
+        let end = self.parse_expression(lookup_precedence(&operator))?;
+        Ok(RangeExpressionNode::new(
+            start,
+            end,
+            operator == Token::DotDotEq,
+        ))
+    }
+
     //<array name or array literal>[<index>]
     fn parse_index_expression(
         &mut self,
@@ -359,6 +658,21 @@ impl Parser {
         Ok(IndexExpressionNode::new(array, index))
     }
 
+    //<expression>.<field>
+    fn parse_field_expression(
+        &mut self,
+        record: Box<dyn ExpressionNode>,
+    ) -> ParseResult<FieldExpressionNode> {
+        assert_eq!(Token::Dot, self.get_next().unwrap());
+        if !self.expect_next(Token::Ident(String::new())) {
+            return Err(ParseError::Error(
+                "field name expected after `.`".to_string(),
+            ));
+        }
+        let field = self.parse_identifier()?;
+        Ok(FieldExpressionNode::new(record, field))
+    }
+
     //<function name or function literal>(<argument(s)>)
     //
     //The last <argument> can optionally be followed by a comma (e.g. `(a, b,)`).
@@ -441,6 +755,63 @@ impl Parser {
         Ok(IfExpressionNode::new(condition, if_value, else_value))
     }
 
+    //match <expression> { <pattern> => <expression>, ... }
+    //
+    //The last arm can optionally be followed by a comma.
+    //
+    //Examples of patterns:
+    // _       (wildcard)
+    // int     (type name, which must be one of `TYPE_NAMES`)
+    // 3       (any other expression, compared by value)
+    // (x)     (a variable, which needs parentheses not to be regarded as a type name)
+    fn parse_match_expression(&mut self) -> ParseResult<MatchExpressionNode> {
+        assert_eq!(Token::Match, self.get_next().unwrap());
+        let scrutinee = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_next(Token::Lbrace) {
+            return Err(ParseError::Error("`{` missing in `match`".to_string()));
+        }
+        self.get_next().unwrap();
+        let mut arms = vec![];
+        loop {
+            if self.peek_next()? == &Token::Rbrace {
+                self.get_next().unwrap();
+                break;
+            }
+            let pattern = match self.peek_next()? {
+                Token::Ident(s) if (s == "_") => {
+                    self.get_next().unwrap();
+                    MatchPattern::Wildcard
+                }
+                Token::Ident(s) => {
+                    let s = s.clone();
+                    self.get_next().unwrap();
+                    MatchPattern::Type(check_type_name(s)?)
+                }
+                _ => MatchPattern::Value(self.parse_expression(Precedence::Lowest)?),
+            };
+            if !self.expect_next(Token::FatArrow) {
+                return Err(ParseError::Error("`=>` missing in `match` arm".to_string()));
+            }
+            self.get_next().unwrap();
+            arms.push(MatchArm::new(
+                pattern,
+                self.parse_expression(Precedence::Lowest)?,
+            ));
+            match self.peek_next()? {
+                Token::Rbrace => (),
+                Token::Comma => {
+                    self.get_next().unwrap();
+                }
+                _ => {
+                    return Err(ParseError::Error(
+                        "`,` expected but not found in `match`".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(MatchExpressionNode::new(scrutinee, arms))
+    }
+
     //fn (<parameter(s)>) { <statement(s)> }
     //
     //The last <argument> can optionally be followed by a comma (e.g. `(a, b,)`).
@@ -545,8 +916,8 @@ mod tests {
     fn test_error(input: &str, expected: &str) {
         let mut parser = Parser::new(get_tokens(input));
         let root = parser.parse();
-        if root.is_ok() {
-            println!("{:#?}", root.as_ref().unwrap());
+        if let Ok(root) = &root {
+            println!("{:#?}", root);
         }
         assert!(root.is_err());
         match root {
@@ -714,6 +1085,7 @@ mod tests {
                                 "a",
                             ),
                         },
+                        annotation: None,
                         expression: IntegerLiteralNode {
                             token: Int(
                                 1,
@@ -752,6 +1124,48 @@ mod tests {
         "#;
         let expected = "`;` missing in `let`";
         test_error(input, expected);
+
+        let input = r#"
+            let a: = 3;
+        "#;
+        let expected = "type name missing after `:` in `let`";
+        test_error(input, expected);
+
+        let input = r#"
+            let a: integer = 3;
+        "#;
+        let expected = "unknown type `integer`";
+        test_error(input, expected);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_let_statement_03() {
+        let input = r#"
+            let a: int = 1;
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    LetStatementNode {
+                        identifier: IdentifierNode {
+                            token: Ident(
+                                "a",
+                            ),
+                        },
+                        annotation: Some(
+                            "int",
+                        ),
+                        expression: IntegerLiteralNode {
+                            token: Int(
+                                1,
+                            ),
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
     }
 
     #[test]
@@ -1207,6 +1621,76 @@ mod tests {
         test_error(input, expected);
     }
 
+    #[test]
+    fn test_hash_literal_01() {
+        let input = r#"
+            {:} {"a": 1, b: 2,}
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: HashLiteralNode {
+                            pairs: [],
+                        },
+                    },
+                    ExpressionStatementNode {
+                        expression: HashLiteralNode {
+                            pairs: [
+                                (
+                                    StringLiteralNode {
+                                        token: String(
+                                            "a",
+                                        ),
+                                    },
+                                    IntegerLiteralNode {
+                                        token: Int(
+                                            1,
+                                        ),
+                                    },
+                                ),
+                                (
+                                    IdentifierNode {
+                                        token: Ident(
+                                            "b",
+                                        ),
+                                    },
+                                    IntegerLiteralNode {
+                                        token: Int(
+                                            2,
+                                        ),
+                                    },
+                                ),
+                            ],
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+    }
+
+    #[test]
+    fn test_hash_literal_02() {
+        let input = r#"
+            {"a": 1 "b": 2}
+        "#;
+        let expected = "`,` expected but not found in hash literal";
+        test_error(input, expected);
+
+        let input = r#"
+            {:1}
+        "#;
+        let expected = "`}` expected after `{:` in empty hash literal";
+        test_error(input, expected);
+
+        let input = r#"
+            {"a": }
+        "#;
+        let expected = "unexpected start of expression: Rbrace";
+        test_error(input, expected);
+    }
+
     #[test]
     // #[ignore]
     fn test_unary_expression_01() {
@@ -1337,21 +1821,93 @@ mod tests {
 
     #[test]
     // #[ignore]
-    fn test_call_expression_01() {
+    fn test_field_expression_01() {
         let input = r#"
-            f(); f(a); f(a,); f(a, b); f(a, b, c)
+            a.b.c; {.x: 1}.x
         "#;
         let expected = r#"
             RootNode {
                 statements: [
                     ExpressionStatementNode {
-                        expression: CallExpressionNode {
-                            function: IdentifierNode {
+                        expression: FieldExpressionNode {
+                            record: FieldExpressionNode {
+                                record: IdentifierNode {
+                                    token: Ident(
+                                        "a",
+                                    ),
+                                },
+                                field: IdentifierNode {
+                                    token: Ident(
+                                        "b",
+                                    ),
+                                },
+                            },
+                            field: IdentifierNode {
                                 token: Ident(
-                                    "f",
+                                    "c",
                                 ),
                             },
-                            arguments: [],
+                        },
+                    },
+                    ExpressionStatementNode {
+                        expression: FieldExpressionNode {
+                            record: HashLiteralNode {
+                                pairs: [
+                                    (
+                                        StringLiteralNode {
+                                            token: String(
+                                                "x",
+                                            ),
+                                        },
+                                        IntegerLiteralNode {
+                                            token: Int(
+                                                1,
+                                            ),
+                                        },
+                                    ),
+                                ],
+                            },
+                            field: IdentifierNode {
+                                token: Ident(
+                                    "x",
+                                ),
+                            },
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+
+        let input = r#"
+            a."b"
+        "#;
+        let expected = "field name expected after `.`";
+        test_error(input, expected);
+
+        test_error("p.0", "field name expected after `.`");
+        test_error("f().5", "field name expected after `.`");
+        test_error("{.x}", "`:` expected but not found in hash literal");
+        test_error("{.", "unexpected eof in the middle of a statement");
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_call_expression_01() {
+        let input = r#"
+            f(); f(a); f(a,); f(a, b); f(a, b, c)
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: CallExpressionNode {
+                            function: IdentifierNode {
+                                token: Ident(
+                                    "f",
+                                ),
+                            },
+                            arguments: [],
                         },
                     },
                     ExpressionStatementNode {
@@ -1461,6 +2017,78 @@ mod tests {
         test_error(input, expected);
     }
 
+    #[test]
+    // #[ignore]
+    fn test_call_expression_03() {
+        //chains of calls and indexes are parsed left to right
+        let input = r#"
+            f(1)[2](3); a[0][1]()
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: CallExpressionNode {
+                            function: IndexExpressionNode {
+                                array: CallExpressionNode {
+                                    function: IdentifierNode {
+                                        token: Ident(
+                                            "f",
+                                        ),
+                                    },
+                                    arguments: [
+                                        IntegerLiteralNode {
+                                            token: Int(
+                                                1,
+                                            ),
+                                        },
+                                    ],
+                                },
+                                index: IntegerLiteralNode {
+                                    token: Int(
+                                        2,
+                                    ),
+                                },
+                            },
+                            arguments: [
+                                IntegerLiteralNode {
+                                    token: Int(
+                                        3,
+                                    ),
+                                },
+                            ],
+                        },
+                    },
+                    ExpressionStatementNode {
+                        expression: CallExpressionNode {
+                            function: IndexExpressionNode {
+                                array: IndexExpressionNode {
+                                    array: IdentifierNode {
+                                        token: Ident(
+                                            "a",
+                                        ),
+                                    },
+                                    index: IntegerLiteralNode {
+                                        token: Int(
+                                            0,
+                                        ),
+                                    },
+                                },
+                                index: IntegerLiteralNode {
+                                    token: Int(
+                                        1,
+                                    ),
+                                },
+                            },
+                            arguments: [],
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+    }
+
     #[test]
     // #[ignore]
     fn test_if_expression_01() {
@@ -1895,4 +2523,422 @@ mod tests {
         "#;
         test(input, expected);
     }
+
+    #[test]
+    // #[ignore]
+    fn test_in_expression_01() {
+        let input = r#"
+            1 + 2 in a == false
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: BinaryExpressionNode {
+                            operator: Eq,
+                            left: BinaryExpressionNode {
+                                operator: In,
+                                left: BinaryExpressionNode {
+                                    operator: Plus,
+                                    left: IntegerLiteralNode {
+                                        token: Int(
+                                            1,
+                                        ),
+                                    },
+                                    right: IntegerLiteralNode {
+                                        token: Int(
+                                            2,
+                                        ),
+                                    },
+                                },
+                                right: IdentifierNode {
+                                    token: Ident(
+                                        "a",
+                                    ),
+                                },
+                            },
+                            right: BooleanLiteralNode {
+                                token: False,
+                            },
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_range_expression_01() {
+        let input = r#"
+            1..10; 0..=n + 1 == r
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: RangeExpressionNode {
+                            start: IntegerLiteralNode {
+                                token: Int(
+                                    1,
+                                ),
+                            },
+                            end: IntegerLiteralNode {
+                                token: Int(
+                                    10,
+                                ),
+                            },
+                            inclusive: false,
+                        },
+                    },
+                    ExpressionStatementNode {
+                        expression: BinaryExpressionNode {
+                            operator: Eq,
+                            left: RangeExpressionNode {
+                                start: IntegerLiteralNode {
+                                    token: Int(
+                                        0,
+                                    ),
+                                },
+                                end: BinaryExpressionNode {
+                                    operator: Plus,
+                                    left: IdentifierNode {
+                                        token: Ident(
+                                            "n",
+                                        ),
+                                    },
+                                    right: IntegerLiteralNode {
+                                        token: Int(
+                                            1,
+                                        ),
+                                    },
+                                },
+                                inclusive: true,
+                            },
+                            right: IdentifierNode {
+                                token: Ident(
+                                    "r",
+                                ),
+                            },
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_match_expression_01() {
+        let input = r#"
+            match x { 1 => a, int => { b }, _ => c, }
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: MatchExpressionNode {
+                            scrutinee: IdentifierNode {
+                                token: Ident(
+                                    "x",
+                                ),
+                            },
+                            arms: [
+                                MatchArm {
+                                    pattern: Value(
+                                        IntegerLiteralNode {
+                                            token: Int(
+                                                1,
+                                            ),
+                                        },
+                                    ),
+                                    value: IdentifierNode {
+                                        token: Ident(
+                                            "a",
+                                        ),
+                                    },
+                                },
+                                MatchArm {
+                                    pattern: Type(
+                                        "int",
+                                    ),
+                                    value: BlockExpressionNode {
+                                        statements: [
+                                            ExpressionStatementNode {
+                                                expression: IdentifierNode {
+                                                    token: Ident(
+                                                        "b",
+                                                    ),
+                                                },
+                                            },
+                                        ],
+                                    },
+                                },
+                                MatchArm {
+                                    pattern: Wildcard,
+                                    value: IdentifierNode {
+                                        token: Ident(
+                                            "c",
+                                        ),
+                                    },
+                                },
+                            ],
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+
+        let input = r#"
+            match x {}
+        "#;
+        let expected = r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: MatchExpressionNode {
+                            scrutinee: IdentifierNode {
+                                token: Ident(
+                                    "x",
+                                ),
+                            },
+                            arms: [],
+                        },
+                    },
+                ],
+            }
+        "#;
+        test(input, expected);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_match_expression_02() {
+        let input = r#"
+            match x 1 => 2 }
+        "#;
+        let expected = "`{` missing in `match`";
+        test_error(input, expected);
+
+        let input = r#"
+            match x { 1 2 }
+        "#;
+        let expected = "`=>` missing in `match` arm";
+        test_error(input, expected);
+
+        let input = r#"
+            match x { 1 => 2 3 => 4 }
+        "#;
+        let expected = "`,` expected but not found in `match`";
+        test_error(input, expected);
+
+        let input = r#"
+            match x { intt => 1, _ => 2 }
+        "#;
+        let expected = "unknown type `intt`";
+        test_error(input, expected);
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_parse_recovering_01() {
+        let input = r#"
+            let = 1;
+            let b = 1;
+            let c 2;
+            let c = b;
+        "#;
+        let mut parser = Parser::new(get_tokens(input));
+        let (root, errors) = parser.parse_recovering();
+        assert_eq!(
+            vec![
+                ParseError::Error(
+                    "identifier missing or reserved keyword used after `let`".to_string()
+                ),
+                ParseError::Error("`=` missing in `let`".to_string()),
+            ],
+            errors
+        );
+        assert_eq!(2, root.statements().len());
+        let expected = r#"
+            RootNode {
+                statements: [
+                    LetStatementNode {
+                        identifier: IdentifierNode {
+                            token: Ident(
+                                "b",
+                            ),
+                        },
+                        annotation: None,
+                        expression: IntegerLiteralNode {
+                            token: Int(
+                                1,
+                            ),
+                        },
+                    },
+                    LetStatementNode {
+                        identifier: IdentifierNode {
+                            token: Ident(
+                                "c",
+                            ),
+                        },
+                        annotation: None,
+                        expression: IdentifierNode {
+                            token: Ident(
+                                "b",
+                            ),
+                        },
+                    },
+                ],
+            }
+        "#;
+        assert_eq!(
+            expected.split_whitespace().join(" "),
+            format!("{:#?}", root).split_whitespace().join(" ")
+        );
+
+        //the default mode still stops at the first error
+        let mut parser = Parser::new(get_tokens(input));
+        assert!(parser.parse().is_err());
+
+        let mut parser = Parser::new(get_tokens("1; 2"));
+        let (root, errors) = parser.parse_recovering();
+        assert!(errors.is_empty());
+        assert_eq!(2, root.statements().len());
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_malformed_input() {
+        let expected = ParseError::Error("token stream not terminated by `Eof`".to_string());
+        assert_eq!(Err(expected), Parser::new(vec![]).parse().map(|_| ()));
+        let (root, errors) = Parser::new(vec![Token::Int(1)]).parse_recovering();
+        assert!(root.statements().is_empty());
+        assert_eq!(1, errors.len());
+
+        let nested = |n: usize| format!("{}1{}", "(".repeat(n), ")".repeat(n));
+        assert!(Parser::new(get_tokens(&nested(100))).parse().is_ok());
+        test_error(&nested(20000), "nesting too deep");
+        test_error(&format!("{}1", "-".repeat(20000)), "nesting too deep");
+        test_error(
+            &format!("{}1{}", "[".repeat(20000), "]".repeat(20000)),
+            "nesting too deep",
+        );
+        test_error(&"{".repeat(20000), "nesting too deep");
+    }
+
+    #[test]
+    // #[ignore]
+    fn test_comment_01() {
+        let input = r#"
+            //leading
+            let a = [1, //inner
+                2]; //trailing
+            let f = fn() {
+                //first
+                1 //value
+                //last
+            };
+            //end
+        "#;
+        let tokens = Lexer::with_comments(input).tokenize().unwrap();
+        let root = Parser::new(tokens).parse().unwrap();
+        let comments = |statements: &[Box<dyn StatementNode>]| {
+            statements
+                .iter()
+                .map(|e| match e.as_any().downcast_ref::<CommentNode>() {
+                    None => "<statement>".to_string(),
+                    Some(c) => format!("{}:{}", c.text(), c.trailing()),
+                })
+                .collect_vec()
+        };
+        assert_eq!(
+            vec![
+                "leading:false",
+                "inner:false",
+                "<statement>",
+                "trailing:true",
+                "<statement>",
+                "end:false",
+            ],
+            comments(root.statements())
+        );
+        let f = root.statements()[4]
+            .as_any()
+            .downcast_ref::<LetStatementNode>()
+            .unwrap()
+            .expression()
+            .as_any()
+            .downcast_ref::<FunctionLiteralNode>()
+            .unwrap();
+        assert_eq!(
+            vec!["first:false", "<statement>", "value:true", "last:false"],
+            comments(f.body().statements())
+        );
+
+        //the first statement of a block is parsed before the block is distinguished from a hash literal
+        let tokens = Lexer::with_comments("{ //a\n 1; //b\n 2 }")
+            .tokenize()
+            .unwrap();
+        let root = Parser::new(tokens).parse().unwrap();
+        let block = root.statements()[0]
+            .as_any()
+            .downcast_ref::<ExpressionStatementNode>()
+            .unwrap()
+            .expression()
+            .as_any()
+            .downcast_ref::<BlockExpressionNode>()
+            .unwrap();
+        assert_eq!(
+            vec!["a:false", "<statement>", "b:true", "<statement>"],
+            comments(block.statements())
+        );
+
+        //comments are ignored without `Lexer::with_comments()`
+        test(
+            "1 //a",
+            r#"
+            RootNode {
+                statements: [
+                    ExpressionStatementNode {
+                        expression: IntegerLiteralNode {
+                            token: Int(
+                                1,
+                            ),
+                        },
+                    },
+                ],
+            }
+        "#,
+        );
+    }
+
+    //feeds arbitrary strings to the lexer and the parser to check they never panic
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn test_no_panic_arbitrary_string(s in ".*") {
+            if let Ok(tokens) = Lexer::new(&s).tokenize() {
+                let _ = Parser::new(tokens).parse();
+            }
+            let (tokens, _) = Lexer::new(&s).tokenize_recovering();
+            let _ = Parser::new(tokens).parse_recovering();
+        }
+
+        //biased to the characters of the language so that the parser is reached more often
+        #[test]
+        fn test_no_panic_monkey_like_string(s in r#"([a-z0-9_ ]|let|fn|if|else|return|match|in|=>|[-+*/%!=<>&|.,;:'"(){}\[\]\\])*"#) {
+            if let Ok(tokens) = Lexer::new(&s).tokenize() {
+                let _ = Parser::new(tokens).parse();
+            }
+            let (tokens, _) = Lexer::new(&s).tokenize_recovering();
+            let _ = Parser::new(tokens).parse_recovering();
+            let (tokens, _) = Lexer::with_comments(&s).tokenize_recovering();
+            let _ = Parser::new(tokens).parse_recovering();
+        }
+    }
 }
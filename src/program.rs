@@ -0,0 +1,91 @@
+use std::rc::Rc;
+
+use super::ast::RootNode;
+use super::lexer::Lexer;
+use super::optimizer;
+use super::parser::Parser;
+
+//A parsed program which can be evaluated any number of times.
+//
+//Cloning is cheap as the AST is shared via `Rc`.
+//Evaluation never modifies the AST; all the state (variables, closures, ...) lives in the `Environment` passed to
+// `Evaluator::eval()`, so evaluating the program against a fresh environment each time never leaks state between runs.
+//A function object created by a run shares the AST of its body with the program, which is safe for the same reason.
+//
+//As `Rc` is used, a program can't be sent to another thread; parse the source per thread instead.
+#[derive(Debug, Clone)]
+pub struct Program {
+    root: Rc<RootNode>,
+}
+
+impl Program {
+    pub fn new(root: RootNode) -> Self {
+        Self {
+            root: Rc::new(root),
+        }
+    }
+
+    //lexes and parses `source`
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let root = Parser::new(tokens).parse().map_err(|e| e.to_string())?;
+        Ok(Self::new(root))
+    }
+
+    //same as `parse()` but also applies `optimizer::optimize()`, which is for evaluation (while the linter needs the
+    // original AST)
+    //The warnings of the optimization are dropped as the linter reports them too.
+    pub fn parse_optimized(source: &str) -> Result<Self, String> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let mut root = Parser::new(tokens).parse().map_err(|e| e.to_string())?;
+        optimizer::optimize(&mut root);
+        Ok(Self::new(root))
+    }
+
+    pub fn root(&self) -> &RootNode {
+        &self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::environment::Environment;
+    use super::super::evaluator::Evaluator;
+    use super::super::object::*;
+    use super::*;
+
+    #[test]
+    fn test_reuse() {
+        let program = Program::parse(
+            r#"
+            let double = fn(x) { x * 2 };
+            let result = double(input);
+            result
+        "#,
+        )
+        .unwrap();
+        let evaluator = Evaluator::new();
+        let mut functions = vec![];
+        for i in 0..1000 {
+            //`let` fails if a variable of the previous run remains
+            let mut env = Environment::new(None);
+            env.define("input", i);
+            let o = evaluator.eval(program.root(), &mut env).unwrap();
+            assert_eq!(i * 2, o.as_any().downcast_ref::<Int>().unwrap().value());
+            assert!(env.get("double").is_some());
+            functions.push(env.get("double").unwrap().clone());
+        }
+
+        //functions created by different runs are independent of each other
+        let mut env = Environment::new(None);
+        env.set("f", functions[10].clone());
+        let o = evaluator
+            .eval(Program::parse("f(21)").unwrap().root(), &mut env)
+            .unwrap();
+        assert_eq!(42, o.as_any().downcast_ref::<Int>().unwrap().value());
+
+        assert!(Program::parse("let").is_err());
+        assert!(Program::parse("@").is_err());
+    }
+}
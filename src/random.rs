@@ -0,0 +1,78 @@
+//xorshift64* pseudo-random number generator used by `random()` and alike
+//This is not cryptographically secure, but the sequence is fully determined by the seed so that a run can be
+// reproduced by `seed()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Rng {
+    state: u64, //never `0`
+}
+
+impl Rng {
+    //Any seed (including `0`) is accepted as it is scrambled by SplitMix64 first.
+    pub fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    //uniform in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    //uniform in `[a, b]` (both inclusive)
+    //`a` must not be greater than `b`.
+    pub fn next_range(&mut self, a: i64, b: i64) -> i64 {
+        assert!(a <= b);
+        let span = (b as i128 - a as i128 + 1) as u128;
+        if span > u64::MAX as u128 {
+            return self.next_u64() as i64;
+        }
+        let span = span as u64;
+        //rejects the values in the incomplete last chunk to avoid the modulo bias
+        let limit = u64::MAX - (u64::MAX % span);
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return (a as i128 + (x % span) as i128) as i64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let mut r1 = Rng::new(42);
+        let mut r2 = Rng::new(42);
+        let v1: Vec<u64> = (0..10).map(|_| r1.next_u64()).collect();
+        let v2: Vec<u64> = (0..10).map(|_| r2.next_u64()).collect();
+        assert_eq!(v1, v2);
+        let mut r3 = Rng::new(43);
+        assert_ne!(v1, (0..10).map(|_| r3.next_u64()).collect::<Vec<_>>());
+
+        let mut r = Rng::new(0);
+        for _ in 0..1000 {
+            let f = r.next_f64();
+            assert!((0.0..1.0).contains(&f));
+            let i = r.next_range(-3, 3);
+            assert!((-3..=3).contains(&i));
+        }
+        assert_eq!(5, r.next_range(5, 5));
+        r.next_range(i64::MIN, i64::MAX);
+    }
+}
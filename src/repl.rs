@@ -1,30 +1,36 @@
 use rustyline;
 
+use super::analysis;
 use super::environment::Environment;
 use super::evaluator::Evaluator;
-use super::lexer::{Lexer, LexerResult};
+use super::lexer::Lexer;
+use super::object::{self, Exit, Object};
 use super::parser::Parser;
-use super::token::Token;
 
 const COLOR_END: &str = "\u{001B}[0m";
 const COLOR_RED: &str = "\u{001B}[091m";
+const COLOR_YELLOW: &str = "\u{001B}[093m";
 const COLOR_PURPLE: &str = "\u{001B}[095m";
 
-fn get_tokens(s: &str) -> LexerResult<Vec<Token>> {
-    let mut lexer = Lexer::new(s);
-    let mut v = vec![];
-    loop {
-        let token = lexer.get_next_token()?;
-        if token == Token::Eof {
-            break;
-        }
-        v.push(token);
-    }
-    v.push(Token::Eof);
-    Ok(v)
+//renders the result of an input
+//`repr()` is used so that a control character in a string or a character doesn't mess up the terminal (e.g. `"a\tb"`
+// is shown as is), while `print()` writes the raw value.
+fn format_result(o: &dyn Object) -> String {
+    format!("{}{}{}", COLOR_PURPLE, object::repr(o), COLOR_END)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplOptions {
+    //If `true`, `exit()` prints "exited with code N" and the session continues instead of ending.
+    pub keep_session_on_exit: bool,
 }
 
-pub fn start(history_file: &str) -> rustyline::Result<()> {
+//returns the exit code specified by `exit()` (or `0` when the input ends)
+pub fn start(history_file: &str) -> rustyline::Result<i32> {
+    start_with(history_file, &ReplOptions::default())
+}
+
+pub fn start_with(history_file: &str, options: &ReplOptions) -> rustyline::Result<i32> {
     let mut rl = rustyline::Editor::<(), _>::with_config(
         rustyline::Config::builder()
             .edit_mode(rustyline::EditMode::Vi)
@@ -38,6 +44,7 @@ pub fn start(history_file: &str) -> rustyline::Result<()> {
     let evaluator = Evaluator::new();
     let mut env = Environment::new(None);
 
+    let mut code = 0;
     loop {
         match rl.readline("\n>> ") {
             Err(_) => break,
@@ -46,7 +53,21 @@ pub fn start(history_file: &str) -> rustyline::Result<()> {
                     continue;
                 }
 
-                let tokens = match get_tokens(&line) {
+                //`:env [<depth>]` lists the identifiers defined in the session
+                if let Some(depth) = line.trim().strip_prefix(":env") {
+                    match depth.trim() {
+                        "" => println!("{}", env.debug_dump(0)),
+                        depth => match depth.parse::<usize>() {
+                            Err(_) => {
+                                println!("{}invalid depth: {}{}", COLOR_RED, depth, COLOR_END)
+                            }
+                            Ok(depth) => println!("{}", env.debug_dump(depth)),
+                        },
+                    }
+                    continue;
+                }
+
+                let tokens = match Lexer::new(&line).tokenize() {
                     Err(e) => {
                         println!("{}{}{}", COLOR_RED, e, COLOR_END);
                         continue;
@@ -62,8 +83,21 @@ pub fn start(history_file: &str) -> rustyline::Result<()> {
                     Err(e) => println!("{}{}{}", COLOR_RED, e, COLOR_END),
                     Ok(e) => {
                         // println!("{:#?}", e);
+                        for warning in analysis::check_unreachable_code(&e) {
+                            println!("{}warning: {}{}", COLOR_YELLOW, warning, COLOR_END);
+                        }
                         match evaluator.eval(&e, &mut env) {
-                            Ok(e) => println!("{}{}{}", COLOR_PURPLE, e, COLOR_END),
+                            Ok(e) => {
+                                if let Some(e) = e.as_any().downcast_ref::<Exit>() {
+                                    if options.keep_session_on_exit {
+                                        println!("exited with code {}", e.code());
+                                        continue;
+                                    }
+                                    code = e.code();
+                                    break;
+                                }
+                                println!("{}", format_result(e.as_ref()))
+                            }
                             Err(e) => println!("{}{}{}", COLOR_RED, e, COLOR_END),
                         }
                     }
@@ -72,5 +106,38 @@ pub fn start(history_file: &str) -> rustyline::Result<()> {
         }
     }
 
-    rl.save_history(history_file)
+    rl.save_history(history_file)?;
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::rc::Rc;
+
+    use super::super::object::*;
+    use super::*;
+
+    #[test]
+    fn test_format_result() {
+        let s = Str::new(Rc::new("a\nb\tc".to_string()));
+        assert_eq!(
+            format!("{}\"a\\nb\\tc\"{}", COLOR_PURPLE, COLOR_END),
+            format_result(&s)
+        );
+        assert_eq!(
+            format!("{}'\\n'{}", COLOR_PURPLE, COLOR_END),
+            format_result(&Char::new('\n'))
+        );
+        assert_eq!(
+            format!("{}[\"\\u{{1b}}[0m\"]{}", COLOR_PURPLE, COLOR_END),
+            format_result(&Array::new(vec![Rc::new(Str::new(Rc::new(
+                COLOR_END.to_string()
+            )))]))
+        );
+        assert_eq!(
+            format!("{}3{}", COLOR_PURPLE, COLOR_END),
+            format_result(&Int::new(3))
+        );
+    }
 }
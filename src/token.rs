@@ -1,3 +1,5 @@
+use std::num::IntErrorKind;
+
 use itertools::Itertools;
 
 use super::util;
@@ -7,10 +9,12 @@ pub enum Token {
     Eof,
     Ident(String),
     Int(i64),
+    BigInt(String), //integer literal too large for `i64` (only with `bignum` feature)
     Float(f64),
     String(String),
     Char(char),
     Assign,
+    FatArrow,
     Plus,
     Minus,
     Asterisk,
@@ -27,6 +31,7 @@ pub enum Token {
     And,
     Or,
     Comma,
+    Colon,
     Semicolon,
     Lparen,
     Rparen,
@@ -34,6 +39,9 @@ pub enum Token {
     Rbrace,
     Lbracket,
     Rbracket,
+    Dot,
+    DotDot,
+    DotDotEq,
     Function,
     Let,
     Return,
@@ -41,12 +49,18 @@ pub enum Token {
     False,
     If,
     Else,
+    In,
+    Match,
+    //`//` to the end of the line, which is emitted only by `Lexer::with_comments()`
+    //`trailing` is whether the comment follows a token on the same line (e.g. `let a = 1; //comment`).
+    Comment { text: String, trailing: bool },
 }
 
 pub fn lookup_token(sequence: &str) -> Result<Token, String> {
     let first_char = sequence.chars().next().unwrap();
     let ret = match sequence {
         "=" => Token::Assign,
+        "=>" => Token::FatArrow,
         "+" => Token::Plus,
         "-" => Token::Minus,
         "*" => Token::Asterisk,
@@ -63,6 +77,7 @@ pub fn lookup_token(sequence: &str) -> Result<Token, String> {
         "&&" => Token::And,
         "||" => Token::Or,
         "," => Token::Comma,
+        ":" => Token::Colon,
         ";" => Token::Semicolon,
         "(" => Token::Lparen,
         ")" => Token::Rparen,
@@ -70,6 +85,9 @@ pub fn lookup_token(sequence: &str) -> Result<Token, String> {
         "}" => Token::Rbrace,
         "[" => Token::Lbracket,
         "]" => Token::Rbracket,
+        "." => Token::Dot,
+        ".." => Token::DotDot,
+        "..=" => Token::DotDotEq,
         "fn" => Token::Function,
         "let" => Token::Let,
         "return" => Token::Return,
@@ -77,6 +95,8 @@ pub fn lookup_token(sequence: &str) -> Result<Token, String> {
         "false" => Token::False,
         "if" => Token::If,
         "else" => Token::Else,
+        "in" => Token::In,
+        "match" => Token::Match,
         _ if (first_char == '\'') => Token::Char(sequence.chars().nth(1).unwrap()),
         _ if (first_char == '"') => {
             let l = sequence.chars().collect_vec();
@@ -90,13 +110,19 @@ pub fn lookup_token(sequence: &str) -> Result<Token, String> {
                 }
             } else {
                 match sequence.parse::<i64>() {
+                    Err(e)
+                        if cfg!(feature = "bignum") && (e.kind() == &IntErrorKind::PosOverflow) =>
+                    {
+                        Token::BigInt(sequence.to_string())
+                    }
                     Err(e) => return Err(e.to_string()),
                     Ok(i) => Token::Int(i),
                 }
             }
         }
         _ if util::is_identifier(first_char) => Token::Ident(sequence.to_string()),
-        _ => unreachable!(),
+        //a character which doesn't start any token (e.g. `@`)
+        _ => return Err(format!("unexpected character `{}`", sequence)),
     };
     Ok(ret)
 }
@@ -6,6 +6,22 @@ pub fn is_digit(c: char) -> bool {
     c.is_ascii_digit() || (c == '.')
 }
 
+//inverse of `parse_escaped_character()`
+//`quote` (`'` or `"`) is escaped only when it is the delimiter of the literal.
+//Other control characters are shown as `\u{1b}`, for example.
+pub fn escape_character(c: char, quote: char) -> String {
+    match c {
+        '\\' => "\\\\".to_string(),
+        '\0' => "\\0".to_string(),
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        c if c == quote => format!("\\{}", c),
+        c if c.is_control() => c.escape_unicode().to_string(),
+        c => c.to_string(),
+    }
+}
+
 //An escaped character is of the form `\n`.
 //This function receives `n` and returns `\n`, for example.
 pub fn parse_escaped_character(c: char) -> Option<char> {
@@ -0,0 +1,12 @@
+let   factorial=fn( x ){if(x==0){return 1;};x*factorial(x-1)};;
+let fibonacci = fn(arr, iter, max,) {
+if (iter == max) { return arr; } else {
+  if (iter == 0) { return fibonacci([0, 1,], iter + 1, max); }
+        else { return fibonacci(arr + [arr[len(arr) - 1] + arr[len(arr) - 2]], iter + 1, max); }
+}
+};
+let describe = fn(v) { match v { 0 => "zero", int => "int" , _ => "other"} };
+if (factorial(3) == 6) { print("ok") };
+(1 + 2) * 3;
+let r = (1..=10);
+print(((5 in r)) && !false)
@@ -0,0 +1,221 @@
+#![cfg(feature = "repl")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_monkey_lang"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_eval() {
+    let output = run(&["-e", "2+2"]);
+    assert!(output.status.success());
+    assert_eq!("4\n", String::from_utf8_lossy(&output.stdout));
+
+    let output = run(&["-e", r#"print("a"); let x = 3; x * 2"#]);
+    assert_eq!("a\n6\n", String::from_utf8_lossy(&output.stdout));
+
+    let output = run(&["-e", "exit(3)"]);
+    assert_eq!(Some(3), output.status.code());
+    assert!(output.stdout.is_empty());
+
+    let output = run(&["-e", "x"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "`x` is not defined\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = run(&["-e"]);
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn test_eval_multiple() {
+    //`null` is not printed
+    let output = run(&["-e", "print(len([1,2,3]))"]);
+    assert!(output.status.success());
+    assert_eq!("3\n", String::from_utf8_lossy(&output.stdout));
+
+    //joined in the order
+    let output = run(&["-e", "let a = 2;", "-e", "let b = a * 3;", "-e", "a + b"]);
+    assert_eq!("8\n", String::from_utf8_lossy(&output.stdout));
+
+    //a parse error
+    let output = run(&["-e", "let a = 1;", "-e", "let"]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+
+    let output = run(&["-e", "1", "2"]);
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn test_eval_return() {
+    //a top-level `return` stops the program, and its value is printed as the result
+    let output = run(&["-e", "return 5; print(2); 10"]);
+    assert!(output.status.success());
+    assert_eq!("5\n", String::from_utf8_lossy(&output.stdout));
+
+    //`return;` returns `null`, which is not printed
+    let output = run(&["-e", "print(1); return; 3"]);
+    assert!(output.status.success());
+    assert_eq!("1\n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn test_fmt_indent() {
+    let dir = std::env::temp_dir().join(format!("monkey_lang_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("indent.mk");
+    let file = file.to_str().unwrap();
+
+    std::fs::write(file, "if (x) { 1 }").unwrap();
+    assert!(run(&["fmt", "--indent", "2", file]).status.success());
+    assert_eq!("if (x) {\n  1\n}\n", std::fs::read_to_string(file).unwrap());
+    assert!(run(&["fmt", "--check", "--indent", "2", file])
+        .status
+        .success());
+    assert_eq!(Some(1), run(&["fmt", "--check", file]).status.code());
+
+    assert!(run(&["fmt", "--indent", "tab", file]).status.success());
+    assert_eq!("if (x) {\n\t1\n}\n", std::fs::read_to_string(file).unwrap());
+
+    assert_eq!(Some(2), run(&["fmt", "--indent", "x", file]).status.code());
+    assert_eq!(Some(2), run(&["fmt", file, "--indent"]).status.code());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_script() {
+    let dir = std::env::temp_dir().join(format!("monkey_lang_cli_script_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("script.mk");
+    let file = file.to_str().unwrap();
+
+    std::fs::write(
+        file,
+        r#"print(argv()); print(env("MONKEY_LANG_TEST")); print(env()["MONKEY_LANG_TEST"]); 3"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_monkey_lang"))
+        .args([file, "a", "b c"])
+        .env("MONKEY_LANG_TEST", "x")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!("[a, b c]\nx\nx\n", String::from_utf8_lossy(&output.stdout));
+
+    std::fs::write(file, "exit(3)").unwrap();
+    assert_eq!(Some(3), run(&[file]).status.code());
+
+    std::fs::write(file, "1 + true").unwrap();
+    assert_eq!(Some(1), run(&[file]).status.code());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(Some(2), run(&[file]).status.code());
+}
+
+#[test]
+fn test_script_args() {
+    let dir = std::env::temp_dir().join(format!("monkey_lang_cli_args_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("args.mk");
+    let file = file.to_str().unwrap();
+
+    //the script path is not included
+    std::fs::write(file, "print(args()); print(len(args()))").unwrap();
+    let output = run(&[file, "a", "b", "c"]);
+    assert!(output.status.success());
+    assert_eq!("[a, b, c]\n3\n", String::from_utf8_lossy(&output.stdout));
+
+    let output = run(&[file]);
+    assert_eq!("[]\n0\n", String::from_utf8_lossy(&output.stdout));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_lint() {
+    let dir = std::env::temp_dir().join(format!("monkey_lang_cli_lint_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("lint.mk");
+    let file = file.to_str().unwrap();
+
+    std::fs::write(file, "let a = 1; a").unwrap();
+    assert!(run(&["--lint", file]).status.success());
+
+    //no position is reported for a warning (see `LintWarning`)
+    std::fs::write(file, "let a = 1;\nlet b = 2; a").unwrap();
+    let output = run(&["--lint", file]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!("{}: warning: unused variable `b`\n", file),
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    //a parse error is reported with its position
+    std::fs::write(file, "let a = 1;\nlet b = ;").unwrap();
+    let output = run(&["--lint", file]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with(&format!("{}:2:9: error: ", file)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    let output = run(&["--lint", file]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("failed to read"));
+}
+
+#[test]
+fn test_options() {
+    let output = run(&["--help"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("usage:"));
+
+    //never regarded as a script
+    let output = run(&["--no-such-option"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("unknown option `--no-such-option`\nusage:"));
+}
+
+#[test]
+fn test_repl_keep_session_on_exit() {
+    //runs the REPL in a temporary directory, where the history file is written
+    let dir = std::env::temp_dir().join(format!("monkey_lang_cli_repl_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let repl = |args: &[&str]| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_monkey_lang"))
+            .args(args)
+            .current_dir(&dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(b"exit(3)\nprint(40 + 2)\n")
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = repl(&[]);
+    assert_eq!(Some(3), output.status.code());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("42"));
+
+    let output = repl(&["--keep-session-on-exit"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("exited with code 3"));
+    assert!(stdout.contains("42"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}